@@ -12,10 +12,17 @@
 */
 extern crate js_sys;
 extern crate web_sys;
-use super::config::{resolve_endpoints, Config, UserSettings};
+use super::browser_io::{JsBrowserIo, ScriptedIo};
+use super::config::{resolve_endpoints, Config, MnemonicConfig, UserSettings};
+use super::debot_cache::default_cache;
+use super::events::JsEventListener;
 use super::helpers::create_client;
+use super::keystore::KeyStore;
+use super::local_engine::{LocalDebotParams, LocalDebots};
+use super::term_browser::BrowserOptions;
 use super::term_signing_box::TerminalSigningBox;
-use super::{BrowserHandle, SigningBoxHandle};
+use super::{BrowserHandle, EncryptionBoxHandle, SigningBoxHandle};
+use crate::dapp_encryption_box::DAppEncryptionBox;
 use crate::dapp_signing_box::DAppSigningBox;
 use crate::{ChainLink, DebotManifest, TerminalBrowser, LOGGER};
 use lazy_static::lazy_static;
@@ -25,9 +32,12 @@ use serde_wasm_bindgen::{from_value, to_value, Serializer};
 use std::sync::Arc;
 use std::collections::HashMap;
 use tokio::sync::{Mutex, RwLock};
-use ton_client::crypto::{KeyPair, ParamsOfSign, RegisteredSigningBox, ParamsOfGenerateRandomBytes};
+use ton_client::crypto::{
+    KeyPair, ParamsOfSign, RegisteredEncryptionBox, RegisteredSigningBox, ParamsOfGenerateRandomBytes,
+};
 use ton_client::{ClientConfig, ClientContext};
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
 
 lazy_static! {
     static ref BROWSER_TABLE: BrowserTable = BrowserTable::new();
@@ -74,6 +84,8 @@ pub fn init_log() -> Result<(), JsValue> {
 /// Starts Terminal DeBot Browser with main DeBot.
 ///
 /// Fetches DeBot by address from blockchain and runs it according to pipechain.
+/// mnemonic - optional object with `dictionary`, `word_count` and `hd_path` used to
+/// get keys from `phrase`. Number of words is detected from phrase by default.
 #[wasm_bindgen]
 pub async fn run_debot_browser(
     url: JsValue,
@@ -81,6 +93,7 @@ pub async fn run_debot_browser(
     pubkey: JsValue,
     phrase: JsValue,
     manifest: JsValue,
+    mnemonic: JsValue,
 ) -> Result<Option<JsValue>, JsValue> {
     crate::utils::set_panic_hook();
     let url: String = from_value(url)?;
@@ -88,19 +101,23 @@ pub async fn run_debot_browser(
     let pubkey: Option<String> = from_value(pubkey).ok();
     let phrase: Option<String> = from_value(phrase).ok();
     let manifest: String = from_value(manifest).unwrap();
+    let mnemonic: Option<MnemonicConfig> =
+        from_value(mnemonic).map_err(|e| JsValue::from_str(&e.to_string()))?;
     let mut pipechain: DebotManifest = serde_json::from_str(&manifest).unwrap();
     let addr = pipechain.debot_address.clone();
     let mut debot_config = Config::new();
     debot_config.endpoints = resolve_endpoints(&url);
     debot_config.url = None;
+    debot_config.mnemonic = mnemonic.unwrap_or_default();
 
     let ton = create_client(&debot_config)?;
     info!("DEBUG: client created");
 
     if let Some(phrase) = phrase {
         info!("DEBUG: seed phrase found");
-        let input = std::io::BufReader::new(phrase.as_bytes());
-        let mut sbox = TerminalSigningBox::new(ton.clone(), vec![], Some(input)).await?;
+        let io = ScriptedIo::new(vec![phrase]);
+        let mut sbox =
+            TerminalSigningBox::new(ton.clone(), vec![], &io, None, &debot_config.mnemonic).await?;
         let sbox_handle = sbox.leak();
         for cl in pipechain.chain.iter_mut() {
             if let ChainLink::SigningBox { handle } = cl {
@@ -111,39 +128,111 @@ pub async fn run_debot_browser(
     let mut user_settings = UserSettings::default();
     user_settings.wallet = wallet;
     user_settings.pubkey = pubkey;
+    user_settings.mnemonic = debot_config.mnemonic;
     let mut browser = TerminalBrowser::new(ton.clone(), user_settings, addr).await?;
     info!("browser created");
     browser.run_manifest(pipechain).await?;
     Ok(to_value(&browser.exit_arg.map(|v| v.to_string())).ok())
 }
 
+/// Optional arguments of `create_browser`.
+#[derive(Default)]
+struct CreateBrowserOptions {
+    io: Option<JsBrowserIo>,
+    local_debots: Vec<LocalDebotParams>,
+    on_event: Option<js_sys::Function>,
+    mnemonic: MnemonicConfig,
+}
+
+impl CreateBrowserOptions {
+    fn from_js(options: JsValue) -> Result<Self, JsValue> {
+        if options.is_undefined() || options.is_null() {
+            return Ok(Self::default());
+        }
+        let field = |name: &str| -> Result<Option<JsValue>, JsValue> {
+            let value = js_sys::Reflect::get(&options, &JsValue::from_str(name))?;
+            Ok(Some(value).filter(|v| !v.is_undefined() && !v.is_null()))
+        };
+        let decode_err = |e: serde_wasm_bindgen::Error| JsValue::from_str(&e.to_string());
+        let on_event = match field("on_event")? {
+            Some(value) => Some(
+                value
+                    .dyn_into::<js_sys::Function>()
+                    .map_err(|_| JsValue::from_str("on_event must be a function"))?,
+            ),
+            None => None,
+        };
+        Ok(Self {
+            io: field("io")?.map(|value| value.unchecked_into::<JsBrowserIo>()),
+            local_debots: match field("local_debots")? {
+                Some(value) => from_value(value).map_err(decode_err)?,
+                None => vec![],
+            },
+            on_event,
+            mnemonic: match field("mnemonic")? {
+                Some(value) => from_value(value).map_err(decode_err)?,
+                None => MnemonicConfig::default(),
+            },
+        })
+    }
+}
+
 /// Creates new instance of DeBot Browser and insert it into Global Browser Table.
 /// Returns handle as reference for the Browser. This handle can be used later to
 /// run Browser or to destroy it.
 ///
-/// endpoint - string with blockchain network url.
+/// endpoint - string with blockchain network url. Can be empty if only local DeBots are used.
 /// debot_addr - string with DeBot address.
 /// default_wallet - optional user default wallet address. Used by UserInfo interface.
 /// default_pubkey - optional user public key. Used by UserInfo interface.
+/// options - optional object with optional fields:
+///   io - object with async `input`, `select`, `confirm`, `secret`, `approve` methods and
+///   `print` method. Used to ask user when manifest has no answer.
+///   local_debots - array of local DeBots: objects with `abi`, base64 `state` or `tvc` with
+///   optional `initialData` and `publicKey`, and optional `address`. These DeBots are
+///   executed without network.
+///   on_event - function called with every browser event object (DeBot fetched, interface
+///   call and answer, message routed, approval, exit).
+///   mnemonic - object with `dictionary`, `word_count` and `hd_path` used to get keys from
+///   seed phrases entered by user.
 #[wasm_bindgen]
 pub async fn create_browser(
     endpoint: String,
     debot_addr: String,
     default_wallet: Option<String>,
     default_pubkey: Option<String>,
+    options: JsValue,
 ) -> Result<BrowserHandle, JsValue> {
+    let CreateBrowserOptions { io, local_debots, on_event, mnemonic } =
+        CreateBrowserOptions::from_js(options)?;
     let mut config = Config::new();
-    config.endpoints = resolve_endpoints(&endpoint);
+    if !endpoint.is_empty() {
+        config.endpoints = resolve_endpoints(&endpoint);
+    }
     config.url = None;
+    config.mnemonic = mnemonic;
 
     let ton = create_client(&config)?;
     info!("client created");
 
+    let mut local = LocalDebots::new();
+    for debot in local_debots {
+        local.add(ton.clone(), debot).await?;
+    }
+
     let mut user_settings = UserSettings::default();
     user_settings.wallet = default_wallet;
     user_settings.pubkey = default_pubkey;
+    user_settings.mnemonic = config.mnemonic;
 
-    let browser = TerminalBrowser::new(ton, user_settings, debot_addr).await?;
+    let mut options = BrowserOptions { local_debots: local, ..Default::default() };
+    if let Some(io) = io {
+        options.io = Arc::new(io);
+    }
+    if let Some(callback) = on_event {
+        options.listeners.push(Arc::new(JsEventListener::new(callback)));
+    }
+    let browser = TerminalBrowser::new_with_options(ton, user_settings, debot_addr, options).await?;
     info!("browser created");
 
     Ok(BROWSER_TABLE.insert(browser).await)
@@ -175,10 +264,72 @@ pub async fn run_browser(handle: BrowserHandle, manifest: JsValue) -> Result<JsV
     Ok(js_result)
 }
 
+/// Returns details of the last run of DeBot Browser: DeBots invoked
+/// by other DeBots and results of these invocations.
+///
+/// handle - DeBot Browser id created by `create_browser`.
+#[wasm_bindgen]
+pub async fn get_run_report(handle: BrowserHandle) -> Result<JsValue, JsValue> {
+    let browser = BROWSER_TABLE
+        .get(&handle).await
+        .ok_or(format!("invalid handle"))?;
+    let report = browser.lock().await.report().clone();
+    let serializer = Serializer::new().serialize_maps_as_objects(true);
+    let js_report = report.serialize(&serializer).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    Ok(js_report)
+}
+
+/// Turns on recording of DeBot Browser runs. After the next run
+/// `get_recorded_manifest` returns manifest which reproduces it.
+///
+/// handle - DeBot Browser id created by `create_browser`.
+#[wasm_bindgen]
+pub async fn start_recording(handle: BrowserHandle) -> Result<(), JsValue> {
+    let browser = BROWSER_TABLE
+        .get(&handle).await
+        .ok_or(format!("invalid handle"))?;
+    browser.lock().await.start_recording().await;
+    Ok(())
+}
+
+/// Returns manifest recorded during the last run or `undefined` if
+/// recording is off. `SigningBox` chain links have placeholder handle 0.
+///
+/// handle - DeBot Browser id created by `create_browser`.
+#[wasm_bindgen]
+pub async fn get_recorded_manifest(handle: BrowserHandle) -> Result<JsValue, JsValue> {
+    let browser = BROWSER_TABLE
+        .get(&handle).await
+        .ok_or(format!("invalid handle"))?;
+    let manifest = browser.lock().await.recorded_manifest().await;
+    let serializer = Serializer::new().serialize_maps_as_objects(true);
+    let js_manifest = manifest.serialize(&serializer).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    Ok(js_manifest)
+}
+
+/// Enables persistent cache of DeBot info and ABI. Cache is stored in
+/// `localStorage` under the `key` and shared by all browsers.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub async fn set_debot_cache_storage(key: String) -> Result<(), JsValue> {
+    let storage = crate::debot_cache::LocalStorage::new(&key);
+    default_cache().write().await.set_storage(Box::new(storage))?;
+    Ok(())
+}
+
+/// Removes all entries from the cache of DeBot info and ABI.
+#[wasm_bindgen]
+pub async fn clear_debot_cache() -> Result<(), JsValue> {
+    default_cache().write().await.clear()?;
+    Ok(())
+}
+
 /// Allows to update user settings in DeBot Browser
 /// This settings are used by UserInfo interface.
 /// handle - DeBot Browser id created by `create_browser`.
-/// settings - UserSettings object.
+/// settings - UserSettings object. Optional `keystore` object and `key` (name or
+/// public key of keystore entry) let DeBots use encrypted keys. Optional `mnemonic`
+/// object is used to get keys from seed phrases entered by user.
 #[wasm_bindgen]
 pub async fn update_user_settings(handle: BrowserHandle, settings: JsValue) -> Result<(), JsValue> {
     let browser = BROWSER_TABLE
@@ -191,9 +342,56 @@ pub async fn update_user_settings(handle: BrowserHandle, settings: JsValue) -> R
     user_settings.wallet = settings.wallet;
     user_settings.pubkey = settings.pubkey;
     user_settings.signing_box = settings.signing_box;
+    user_settings.encryption_boxes = settings.encryption_boxes;
+    user_settings.keystore = settings.keystore;
+    user_settings.key = settings.key;
+    user_settings.mnemonic = settings.mnemonic;
     Ok(())
 }
 
+/// Encrypts keypair with passphrase and adds it to keystore.
+/// Returns updated keystore object.
+///
+/// keystore - keystore object or `undefined` to create new one.
+/// name - name of the new entry.
+/// keys - keypair object with `public` and `secret`.
+#[wasm_bindgen]
+pub fn keystore_add_key(
+    keystore: JsValue,
+    name: String,
+    keys: JsValue,
+    passphrase: String,
+) -> Result<JsValue, JsValue> {
+    let keystore: Option<KeyStore> =
+        from_value(keystore).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let keys: KeyPair = from_value(keys).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let mut keystore = keystore.unwrap_or_default();
+    keystore.create(&name, &keys, &passphrase)?;
+    let serializer = Serializer::new().serialize_maps_as_objects(true);
+    keystore.serialize(&serializer).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Returns array of `[name, public]` pairs of keystore entries.
+#[wasm_bindgen]
+pub fn keystore_list_keys(keystore: JsValue) -> Result<JsValue, JsValue> {
+    let keystore: KeyStore =
+        from_value(keystore).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    to_value(&keystore.list()).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Decrypts keypair found by name or public key. Returns keypair object.
+#[wasm_bindgen]
+pub fn keystore_unlock_key(
+    keystore: JsValue,
+    name_or_pubkey: String,
+    passphrase: String,
+) -> Result<JsValue, JsValue> {
+    let keystore: KeyStore =
+        from_value(keystore).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let keys = keystore.unlock(&name_or_pubkey, &passphrase)?;
+    to_value(&keys).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
 /// Generates new ed25519 signing keypair
 #[wasm_bindgen]
 pub fn generate_keypair() -> Result<JsValue, JsValue> {
@@ -258,6 +456,51 @@ pub async fn close_signing_box(
     Ok(())
 }
 
+/// Registers encryption box implemented in JavaScript. Returns handle which
+/// can be passed to DeBots in `encryption_boxes` user setting.
+///
+/// handle - DeBot Browser id created by `create_browser`.
+/// dapp_box - object with async `get_info`, `encrypt` and `decrypt` methods.
+/// Data is passed to `encrypt` and `decrypt` and returned from them in base64.
+#[wasm_bindgen]
+pub async fn register_encryption_box(
+    handle: BrowserHandle,
+    dapp_box: DAppEncryptionBox,
+) -> Result<EncryptionBoxHandle, JsValue> {
+    let browser = BROWSER_TABLE
+        .get(&handle).await
+        .ok_or(format!("invalid handle"))?;
+
+    let client = browser.lock().await.client.clone();
+
+    let registered = ton_client::crypto::register_encryption_box(client, dapp_box)
+        .await
+        .map_err(|e| format!("{}", e))?;
+    Ok(registered.handle.0)
+}
+
+/// Removes encryption box registered by `register_encryption_box`.
+#[wasm_bindgen]
+pub async fn close_encryption_box(
+    handle: BrowserHandle,
+    ebox_handle: EncryptionBoxHandle,
+) -> Result<(), JsValue> {
+    let browser = BROWSER_TABLE
+        .get(&handle).await
+        .ok_or(format!("invalid handle"))?;
+
+    let client = browser.lock().await.client.clone();
+
+    ton_client::crypto::remove_encryption_box(
+        client,
+        RegisteredEncryptionBox {
+            handle: ton_client::crypto::EncryptionBoxHandle(ebox_handle),
+        },
+    )
+    .map_err(|e| format!("{}", e))?;
+    Ok(())
+}
+
 #[wasm_bindgen]
 pub async fn signing_box_public_key(
     handle: BrowserHandle,
@@ -0,0 +1,377 @@
+/*
+* Copyright 2018-2021 TON DEV SOLUTIONS LTD.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific TON DEV software governing permissions and
+* limitations under the License.
+*/
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Read, Write};
+use std::sync::{Arc, Mutex};
+
+/// User I/O used by DeBot Browser for all prompts and messages.
+#[async_trait::async_trait]
+pub trait BrowserIo {
+    /// Shows message to user.
+    async fn print(&self, message: &str);
+    /// Asks user to enter a line.
+    async fn input(&self, prompt: &str) -> Result<String, String>;
+    /// Asks user to enter multiline text.
+    async fn input_multiline(&self, prompt: &str) -> Result<String, String> {
+        self.input(prompt).await
+    }
+    /// Asks user to choose one of the items. Returns index of the chosen item.
+    async fn select(&self, items: &[String]) -> Result<usize, String>;
+    /// Asks user yes/no question.
+    async fn confirm(&self, prompt: &str) -> Result<bool, String>;
+    /// Asks user to enter secret data (seed phrase, keys). Terminal does
+    /// not echo the input.
+    async fn secret(&self, prompt: &str) -> Result<String, String>;
+    /// Asks user to approve DeBot activity. `info` is a human readable
+    /// description of the activity, `activity` is its JSON representation.
+    async fn approve(&self, info: &str, activity: &Value) -> Result<bool, String> {
+        self.print(info).await;
+        self.confirm(approve_prompt(activity)).await
+    }
+}
+
+fn approve_prompt(activity: &Value) -> &'static str {
+    match activity["type"].as_str() {
+        Some("Network") => "Allow network access?",
+        Some("MessageLimit") => "Allow DeBot to continue?",
+        _ => "Confirm the transaction?",
+    }
+}
+
+pub type SharedBrowserIo = Arc<dyn BrowserIo + Send + Sync>;
+
+/// Returns I/O used by default: stdin/stdout.
+pub fn default_io() -> SharedBrowserIo {
+    Arc::new(TerminalIo::new())
+}
+
+/// Asks user to enter a line until it is accepted by `validator`.
+pub async fn input_valid<F>(
+    io: &(dyn BrowserIo + Send + Sync),
+    prompt: &str,
+    mut validator: F,
+) -> Result<String, String>
+where
+    F: FnMut(&String) -> Result<(), String> + Send,
+{
+    loop {
+        let value = io.input(prompt).await?;
+        match validator(&value) {
+            Ok(()) => return Ok(value),
+            Err(e) => io.print(&format!("{}. Try again.", e)).await,
+        }
+    }
+}
+
+fn parse_yes_no(value: &str) -> Result<bool, String> {
+    match value {
+        "y" => Ok(true),
+        "n" => Ok(false),
+        _ => Err(format!("invalid enter")),
+    }
+}
+
+fn parse_item_number(value: &str, count: usize) -> Result<usize, String> {
+    let n = usize::from_str_radix(value.trim(), 10)
+        .map_err(|_| format!("Oops! Invalid action. Try again, please."))?;
+    if n == 0 || n > count {
+        return Err(format!("Auch! Invalid action. Try again, please."));
+    }
+    Ok(n - 1)
+}
+
+/// Reads non-empty line from `reader`.
+pub(crate) fn read_input<R, W>(prompt: &str, reader: &mut R, writer: &mut W) -> Result<String, String>
+where
+    R: BufRead,
+    W: Write,
+{
+    let mut input_str = String::new();
+    while input_str.trim().is_empty() {
+        if !prompt.is_empty() {
+            writeln!(writer, "{}", prompt).map_err(|e| format!("failed to write: {}", e))?;
+        }
+        writer.flush().map_err(|e| format!("failed to flush: {}", e))?;
+        input_str.clear();
+        let read = reader
+            .read_line(&mut input_str)
+            .map_err(|e| format!("failed to read line: {}", e))?;
+        if read == 0 {
+            return Err(format!("input is closed"));
+        }
+    }
+    Ok(input_str.trim().to_owned())
+}
+
+/// Terminal I/O based on stdin and stdout.
+pub struct TerminalIo {}
+
+impl TerminalIo {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    fn read_line(&self, prompt: &str) -> Result<String, String> {
+        let stdio = io::stdin();
+        let mut reader = stdio.lock();
+        let mut writer = io::stdout();
+        read_input(prompt, &mut reader, &mut writer)
+    }
+}
+
+#[async_trait::async_trait]
+impl BrowserIo for TerminalIo {
+    async fn print(&self, message: &str) {
+        println!("{}", message);
+    }
+
+    async fn input(&self, prompt: &str) -> Result<String, String> {
+        self.read_line(prompt)
+    }
+
+    async fn input_multiline(&self, prompt: &str) -> Result<String, String> {
+        println!("{}", prompt);
+        if cfg!(windows) {
+            println!("(Ctrl+Z to exit)");
+        } else {
+            println!("(Ctrl+D to exit)");
+        }
+        let mut value = String::new();
+        io::stdin()
+            .read_to_string(&mut value)
+            .map_err(|e| format!("input error: {}", e))?;
+        println!();
+        Ok(value)
+    }
+
+    async fn select(&self, items: &[String]) -> Result<usize, String> {
+        for (i, item) in items.iter().enumerate() {
+            println!("{}) {}", i + 1, item);
+        }
+        println!();
+        loop {
+            let value = self.read_line("debash$ ")?;
+            match parse_item_number(&value, items.len()) {
+                Ok(index) => return Ok(index),
+                Err(e) => println!("{}", e),
+            }
+        }
+    }
+
+    async fn confirm(&self, prompt: &str) -> Result<bool, String> {
+        let prompt = format!("{} (y/n)", prompt);
+        loop {
+            match parse_yes_no(&self.read_line(&prompt)?) {
+                Ok(value) => return Ok(value),
+                Err(e) => println!("{}. Try again.", e),
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn secret(&self, prompt: &str) -> Result<String, String> {
+        loop {
+            let value = rpassword::prompt_password(format!("{}\n", prompt))
+                .map_err(|e| format!("failed to read secret: {}", e))?;
+            if !value.trim().is_empty() {
+                return Ok(value.trim().to_owned());
+            }
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    async fn secret(&self, prompt: &str) -> Result<String, String> {
+        self.read_line(prompt)
+    }
+}
+
+/// I/O which answers prompts with predefined values and records all output.
+/// Used to run DeBots without user and in tests.
+#[derive(Default)]
+pub struct ScriptedIo {
+    answers: Mutex<VecDeque<String>>,
+    output: Mutex<Vec<String>>,
+}
+
+impl ScriptedIo {
+    pub fn new<S: Into<String>>(answers: Vec<S>) -> Self {
+        Self {
+            answers: Mutex::new(answers.into_iter().map(|x| x.into()).collect()),
+            output: Mutex::new(vec![]),
+        }
+    }
+
+    /// Returns all messages and prompts shown to user.
+    pub fn output(&self) -> Vec<String> {
+        self.output.lock().unwrap().clone()
+    }
+
+    fn write(&self, message: &str) {
+        self.output.lock().unwrap().push(message.to_owned());
+    }
+
+    fn next_answer(&self, prompt: &str) -> Result<String, String> {
+        if !prompt.is_empty() {
+            self.write(prompt);
+        }
+        self.answers
+            .lock()
+            .unwrap()
+            .pop_front()
+            .ok_or(format!("no scripted answer for \"{}\"", prompt))
+    }
+}
+
+#[async_trait::async_trait]
+impl BrowserIo for ScriptedIo {
+    async fn print(&self, message: &str) {
+        self.write(message);
+    }
+
+    async fn input(&self, prompt: &str) -> Result<String, String> {
+        self.next_answer(prompt)
+    }
+
+    async fn select(&self, items: &[String]) -> Result<usize, String> {
+        for (i, item) in items.iter().enumerate() {
+            self.write(&format!("{}) {}", i + 1, item));
+        }
+        parse_item_number(&self.next_answer("")?, items.len())
+    }
+
+    async fn confirm(&self, prompt: &str) -> Result<bool, String> {
+        parse_yes_no(&self.next_answer(prompt)?)
+    }
+
+    async fn secret(&self, prompt: &str) -> Result<String, String> {
+        self.next_answer(prompt)
+    }
+}
+
+mod js {
+    use super::BrowserIo;
+    use crate::dapp_signing_box::{JsFutureSync, Promise};
+    use serde_json::Value;
+    use serde_wasm_bindgen::{from_value, to_value};
+    use wasm_bindgen::prelude::*;
+
+    #[wasm_bindgen]
+    extern "C" {
+        /// JavaScript object which implements user I/O. All methods except
+        /// `print` must return Promise.
+        pub type JsBrowserIo;
+
+        #[wasm_bindgen(method, js_name = print)]
+        fn js_print(this: &JsBrowserIo, message: &str);
+
+        #[wasm_bindgen(method, js_name = input)]
+        fn js_input(this: &JsBrowserIo, prompt: &str) -> Promise;
+
+        #[wasm_bindgen(method, js_name = select)]
+        fn js_select(this: &JsBrowserIo, items: JsValue) -> Promise;
+
+        #[wasm_bindgen(method, js_name = confirm)]
+        fn js_confirm(this: &JsBrowserIo, prompt: &str) -> Promise;
+
+        #[wasm_bindgen(method, js_name = secret)]
+        fn js_secret(this: &JsBrowserIo, prompt: &str) -> Promise;
+
+        #[wasm_bindgen(method, js_name = approve)]
+        fn js_approve(this: &JsBrowserIo, info: &str, activity: JsValue) -> Promise;
+    }
+
+    unsafe impl Send for JsBrowserIo {}
+    unsafe impl Sync for JsBrowserIo {}
+
+    async fn resolve<T: serde::de::DeserializeOwned>(promise: Promise) -> Result<T, String> {
+        let value = JsFutureSync::from(promise)
+            .await
+            .map_err(|e| format!("input failed: {:?}", e))?;
+        from_value(value).map_err(|e| format!("invalid input: {}", e))
+    }
+
+    #[async_trait::async_trait]
+    impl BrowserIo for JsBrowserIo {
+        async fn print(&self, message: &str) {
+            self.js_print(message);
+        }
+
+        async fn input(&self, prompt: &str) -> Result<String, String> {
+            resolve(self.js_input(prompt)).await
+        }
+
+        async fn select(&self, items: &[String]) -> Result<usize, String> {
+            let promise = {
+                let items = to_value(items).map_err(|e| e.to_string())?;
+                self.js_select(items)
+            };
+            resolve(promise).await
+        }
+
+        async fn confirm(&self, prompt: &str) -> Result<bool, String> {
+            resolve(self.js_confirm(prompt)).await
+        }
+
+        async fn secret(&self, prompt: &str) -> Result<String, String> {
+            resolve(self.js_secret(prompt)).await
+        }
+
+        async fn approve(&self, info: &str, activity: &Value) -> Result<bool, String> {
+            let promise = {
+                let activity = to_value(activity).map_err(|e| e.to_string())?;
+                self.js_approve(info, activity)
+            };
+            resolve(promise).await
+        }
+    }
+}
+
+pub use js::JsBrowserIo;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_input() {
+        let mut reader = "\n  \n value \n".as_bytes();
+        let mut writer = vec![];
+        assert_eq!(read_input("Enter:", &mut reader, &mut writer).unwrap(), "value");
+        assert_eq!(String::from_utf8(writer).unwrap(), "Enter:\nEnter:\nEnter:\n");
+        assert!(read_input("Enter:", &mut reader, &mut vec![]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_scripted_io() {
+        let io: SharedBrowserIo = Arc::new(ScriptedIo::new(vec!["abc", "12", "y", "2", "x"]));
+        let value = input_valid(io.as_ref(), "Enter number", |val| {
+            val.parse::<u32>().map(|_| ()).map_err(|e| e.to_string())
+        }).await.unwrap();
+        assert_eq!(value, "12");
+        assert!(io.confirm("Continue?").await.unwrap());
+        let items = vec!["one".to_owned(), "two".to_owned()];
+        assert_eq!(io.select(&items).await.unwrap(), 1);
+        assert!(io.select(&items).await.is_err());
+        assert!(io.input("more").await.is_err());
+
+        let scripted = ScriptedIo::new(vec!["n"]);
+        scripted.print("hello").await;
+        assert!(!scripted.approve("transfer 1 token", &Value::Null).await.unwrap());
+        assert_eq!(scripted.output(), vec![
+            "hello".to_owned(),
+            "transfer 1 token".to_owned(),
+            "Confirm the transaction?".to_owned(),
+        ]);
+    }
+}
@@ -11,12 +11,18 @@
 * limitations under the License.
 */
 use super::term_signing_box::TerminalSigningBox;
-use super::{action_input, input, terminal_input, ChainProcessor, ProcessorError};
+use super::{ChainLink, ChainProcessor, ProcessorError};
+use crate::browser_io::SharedBrowserIo;
+use crate::config::SharedUserSettings;
 use crate::convert::convert_u64_to_tokens;
+use crate::events::{BrowserEvent, EventEmitter};
 use crate::helpers::TonClient;
+use crate::interfaces::dinterface::Printer;
+use crate::run_report::{Approval, DecodedCall};
+use log::error;
 use std::collections::VecDeque;
-use std::io;
 use std::sync::{Arc, RwLock};
+use ton_client::abi::{decode_message, ParamsOfDecodeMessage};
 use ton_client::crypto::SigningBoxHandle;
 use ton_client::debot::{BrowserCallbacks, DAction, DebotActivity, STATE_EXIT};
 use ton_client::error::ClientResult;
@@ -26,63 +32,79 @@ struct ActiveState {
     state_id: u8,
     active_actions: Vec<DAction>,
     msg_queue: VecDeque<String>,
+    /// Pending invocations: target DeBot address and action.
+    invokes: VecDeque<(String, DAction)>,
+    /// Onchain call approve requests since the last `take_approvals` call.
+    approvals: Vec<Approval>,
 }
 
 pub(super) struct Callbacks {
     client: TonClient,
     state: Arc<RwLock<ActiveState>>,
     processor: Arc<tokio::sync::RwLock<ChainProcessor>>,
+    io: SharedBrowserIo,
+    printer: Printer,
+    events: EventEmitter,
+    settings: SharedUserSettings,
 }
 
 impl Callbacks {
     pub fn new(
         client: TonClient,
         processor: Arc<tokio::sync::RwLock<ChainProcessor>>,
+        io: SharedBrowserIo,
+        events: EventEmitter,
+        settings: SharedUserSettings,
     ) -> Self {
+        let printer = Printer::new(processor.clone(), io.clone());
         Self {
             client,
             processor,
+            io,
+            printer,
+            events,
+            settings,
             state: Arc::new(RwLock::new(ActiveState::default())),
         }
     }
 
-    pub fn select_action(&self) -> Option<DAction> {
-        let state = self.state.read().unwrap();
-        if state.state_id == STATE_EXIT {
-            return None;
-        }
-        if state.active_actions.len() == 0 {
-            //debug!("no more actions, exit loop");
-            return None;
-        }
-
-        loop {
-            let res = action_input(state.active_actions.len());
-            if res.is_err() {
-                println!("{}", res.unwrap_err());
-                continue;
-            }
-            let (n, _, _) = res.unwrap();
-            let act = state.active_actions.get(n - 1);
-            if act.is_none() {
-                println!("Invalid action. Try again.");
-                continue;
-            }
-            return act.map(|a| a.clone());
-        }
-    }
-
     pub fn take_messages(&self, common_queue: &mut VecDeque<String>) {
         let new_msgs = &mut self.state.write().unwrap().msg_queue;
         common_queue.append(new_msgs);
     }
+
+    /// Returns invocations requested by DeBots since the last call.
+    pub fn take_invokes(&self) -> VecDeque<(String, DAction)> {
+        std::mem::take(&mut self.state.write().unwrap().invokes)
+    }
+
+    /// Returns approve requests made since the last call.
+    pub fn take_approvals(&self) -> Vec<Approval> {
+        std::mem::take(&mut self.state.write().unwrap().approvals)
+    }
+
+    /// Decodes function call from the message if ABI of the destination
+    /// contract is known.
+    async fn decode_call(&self, dst: &str, message: &str) -> Option<DecodedCall> {
+        let abi = self.processor.read().await.contract_abi(dst)?;
+        let decoded = decode_message(
+            self.client.clone(),
+            ParamsOfDecodeMessage { abi, message: message.to_owned() },
+        )
+        .await
+        .ok()?;
+        Some(DecodedCall {
+            function: decoded.name,
+            args: decoded.value.unwrap_or(serde_json::json!({})),
+        })
+    }
 }
 
 #[async_trait::async_trait]
 impl BrowserCallbacks for Callbacks {
     /// Debot asks browser to print message to user
     async fn log(&self, msg: String) {
-        self.processor.read().await.print(&format!("{}", msg));
+        self.printer.print(&format!("{}", msg)).await;
     }
 
     /// Debot is switched to another context.
@@ -101,25 +123,34 @@ impl BrowserCallbacks for Callbacks {
 
     /// Debot asks browser to show user an action from the context
     async fn show_action(&self, act: DAction) {
-        let mut state = self.state.write().unwrap();
-        println!("{}) {}", state.active_actions.len() + 1, act.desc);
-        state.active_actions.push(act);
+        let line = {
+            let mut state = self.state.write().unwrap();
+            let line = format!("{}) {}", state.active_actions.len() + 1, act.desc);
+            state.active_actions.push(act);
+            line
+        };
+        self.io.print(&line).await;
     }
 
     // Debot engine asks user to enter argument for an action.
     async fn input(&self, prefix: &str, value: &mut String) {
-        let stdio = io::stdin();
-        let mut reader = stdio.lock();
-        let mut writer = io::stdout();
-        *value = input(prefix, &mut reader, &mut writer);
+        *value = match self.io.input(prefix).await {
+            Ok(input) => input,
+            Err(e) => {
+                error!("failed to read input for \"{}\": {}", prefix, e);
+                String::new()
+            }
+        };
     }
 
     /// Debot engine requests keys to sign something
     async fn get_signing_box(&self) -> Result<SigningBoxHandle, String> {
         let result = self.processor.write().await.next_signing_box();
+        self.events.emit(BrowserEvent::SigningBoxRequested { handle: result.as_ref().ok().cloned() });
         let handle = match result {
             Err(ProcessorError::InterfaceCallNeeded) => {
-                TerminalSigningBox::new::<&[u8]>(self.client.clone(), vec![], None)
+                let mnemonic = self.settings.read().await.mnemonic.clone();
+                TerminalSigningBox::new(self.client.clone(), vec![], self.io.as_ref(), None, &mnemonic)
                     .await?
                     .leak()
                     .0
@@ -130,8 +161,10 @@ impl BrowserCallbacks for Callbacks {
         Ok(SigningBoxHandle(handle))
     }
 
-    /// Debot asks to run action of another debot
-    async fn invoke_debot(&self, _debot: String, _action: DAction) -> Result<(), String> {
+    /// Debot asks to run action of another debot. Invocation is executed by
+    /// browser right after the current DeBot call is completed.
+    async fn invoke_debot(&self, debot: String, action: DAction) -> Result<(), String> {
+        self.state.write().unwrap().invokes.push_back((debot, action));
         Ok(())
     }
 
@@ -141,16 +174,19 @@ impl BrowserCallbacks for Callbacks {
     }
 
     async fn approve(&self, activity: DebotActivity) -> ClientResult<bool> {
-        let mut approved = false;
-        let result = self.processor.write().await.next_approve(&activity);
+        let (result, rule) = {
+            let mut processor = self.processor.write().await;
+            let result = processor.next_approve(&activity);
+            (result, processor.take_policy_rule())
+        };
+        let mut activity_json = serde_json::to_value(&activity).unwrap_or_default();
         let mut info = String::new();
         info += "--------------------\n";
         info += "[Permission Request]\n";
         info += "--------------------\n";
-        let prompt;
-        match activity {
+        let mut approval = match activity {
             DebotActivity::Transaction {
-                msg: _,
+                msg,
                 dst,
                 out,
                 fee,
@@ -158,16 +194,21 @@ impl BrowserCallbacks for Callbacks {
                 signkey,
                 signing_box_handle: _,
             } => {
+                let call = self.decode_call(&dst, &msg).await;
                 info += "DeBot is going to create an onchain transaction.\n";
                 info += "Details:\n";
                 info += &format!("  account: {}\n", dst);
+                if let Some(call) = &call {
+                    info += &format!("  function: {}\n", call.function);
+                    info += &format!("  arguments: {}\n", call.args);
+                }
                 info += &format!(
                     "  Transaction fees: {} tokens\n",
                     convert_u64_to_tokens(fee)
                 );
                 if out.len() > 0 {
                     info += "  Outgoing transfers from the account:\n";
-                    for spending in out {
+                    for spending in &out {
                         info += &format!(
                             "    recipient: {}, amount: {} tokens\n",
                             spending.dst,
@@ -182,25 +223,167 @@ impl BrowserCallbacks for Callbacks {
                     info +=
                         "  Warning: the transaction will change the account's code\n";
                 }
-                prompt = "Confirm the transaction (y/n)?";
+                Approval {
+                    dst,
+                    amount: out.iter().fold(0, |total, s| total.saturating_add(s.amount)),
+                    fee,
+                    call,
+                    rule,
+                    approved: false,
+                }
             }
+        };
+        if let (Some(call), Some(obj)) = (&approval.call, activity_json.as_object_mut()) {
+            obj.insert("call".to_owned(), serde_json::to_value(call).unwrap_or_default());
         }
-        self.processor.read().await.print(&info);
-        approved = match result {
-            Err(ProcessorError::InteractiveApproveNeeded) => {
-                let _ = terminal_input(prompt, |val| {
-                    approved = match val.as_str() {
-                        "y" => true,
-                        "n" => false,
-                        _ => Err(format!("invalid enter"))?,
-                    };
-                    Ok(())
-                });
-                approved
+        self.events.emit(BrowserEvent::ApprovalRequested { activity: activity_json.clone() });
+        approval.approved = if let Err(ProcessorError::InteractiveApproveNeeded) = result {
+            let approved = self.io.approve(&info, &activity_json).await.unwrap_or(false);
+            let mut processor = self.processor.write().await;
+            if approved {
+                processor.record_spending(approval.amount);
             }
-            Err(_) => false,
-            Ok(res) => res,
+            processor.record(ChainLink::OnchainCall { approve: approved, iflq: None, ifeq: None });
+            approved
+        } else {
+            self.printer.print(&info).await;
+            result.unwrap_or(false)
         };
+        let approved = approval.approved;
+        self.events.emit(BrowserEvent::ApprovalDecided { approved, rule: approval.rule.clone() });
+        self.state.write().unwrap().approvals.push(approval);
         Ok(approved)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::browser_io::ScriptedIo;
+    use crate::config::{make_shared_settings, UserSettings};
+    use crate::helpers::create_client_local;
+    use crate::{ChainLink, DebotManifest};
+    use ton_client::debot::Spending;
+
+    #[tokio::test]
+    async fn test_approve_is_recorded() {
+        let mut manifest = DebotManifest::new();
+        manifest.quiet = true;
+        manifest.chain = vec![
+            ChainLink::OnchainCall { approve: true, iflq: None, ifeq: None },
+            ChainLink::OnchainCall { approve: false, iflq: None, ifeq: None },
+        ];
+        let mut processor = ChainProcessor::new();
+        processor.load_manifest(manifest);
+        let io = Arc::new(ScriptedIo::new(Vec::<String>::new()));
+        let callbacks = Callbacks::new(
+            create_client_local().unwrap(),
+            Arc::new(tokio::sync::RwLock::new(processor)),
+            io,
+            EventEmitter::default(),
+            make_shared_settings(UserSettings::default()),
+        );
+        let activity = || DebotActivity::Transaction {
+            msg: String::new(),
+            dst: "0:11".to_owned(),
+            out: vec![
+                Spending { amount: 300, dst: "0:22".to_owned() },
+                Spending { amount: 200, dst: "0:22".to_owned() },
+            ],
+            fee: 10,
+            setcode: false,
+            signkey: String::new(),
+            signing_box_handle: 0,
+        };
+        assert!(callbacks.approve(activity()).await.unwrap());
+        assert!(!callbacks.approve(activity()).await.unwrap());
+
+        let approvals = callbacks.take_approvals();
+        assert_eq!(approvals.len(), 2);
+        assert_eq!(approvals[0].dst, "0:11");
+        assert_eq!(approvals[0].amount, 500);
+        assert_eq!(approvals[0].call, None);
+        assert_eq!(approvals[0].rule, None);
+        assert!(approvals[0].approved);
+        assert!(!approvals[1].approved);
+        assert!(callbacks.take_approvals().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_approve_decodes_call() {
+        use serde_json::json;
+        use ton_client::abi::{encode_message, Abi, CallSet, ParamsOfEncodeMessage, Signer};
+
+        const WALLET_ABI: &str = r#"{
+            "ABI version": 2,
+            "version": "2.2",
+            "header": ["time"],
+            "functions": [
+                {
+                    "name": "sendTransaction",
+                    "inputs": [
+                        {"name":"dest","type":"address"},
+                        {"name":"value","type":"uint128"},
+                        {"name":"bounce","type":"bool"}
+                    ],
+                    "outputs": []
+                }
+            ],
+            "data": [],
+            "events": []
+        }"#;
+        let wallet = "0:1111111111111111111111111111111111111111111111111111111111111111";
+        let recipient = "0:2222222222222222222222222222222222222222222222222222222222222222";
+        let client = create_client_local().unwrap();
+        let args = json!({ "dest": recipient, "value": "500", "bounce": false });
+        let msg = encode_message(
+            client.clone(),
+            ParamsOfEncodeMessage {
+                abi: Abi::Json(WALLET_ABI.to_owned()),
+                address: Some(wallet.to_owned()),
+                deploy_set: None,
+                call_set: CallSet::some_with_function_and_input("sendTransaction", args.clone()),
+                signer: Signer::None,
+                processing_try_index: None,
+            },
+        )
+        .await
+        .unwrap()
+        .message;
+
+        let mut manifest = DebotManifest::new();
+        manifest.contract_abis.insert(wallet.to_owned(), json!(WALLET_ABI));
+        let mut processor = ChainProcessor::new();
+        processor.load_manifest(manifest);
+        let io = Arc::new(ScriptedIo::new(vec!["y"]));
+        let callbacks = Callbacks::new(
+            client,
+            Arc::new(tokio::sync::RwLock::new(processor)),
+            io.clone(),
+            EventEmitter::default(),
+            make_shared_settings(UserSettings::default()),
+        );
+        let activity = DebotActivity::Transaction {
+            msg,
+            dst: wallet.to_owned(),
+            out: vec![Spending { amount: 500, dst: recipient.to_owned() }],
+            fee: 10,
+            setcode: false,
+            signkey: String::new(),
+            signing_box_handle: 0,
+        };
+        assert!(callbacks.approve(activity).await.unwrap());
+
+        let info = &io.output()[0];
+        assert!(info.contains("[Permission Request]"), "{}", info);
+        assert!(info.contains("  function: sendTransaction\n"), "{}", info);
+        assert!(info.contains(&format!("  arguments: {}\n", args)), "{}", info);
+        let approvals = callbacks.take_approvals();
+        assert_eq!(approvals.len(), 1);
+        assert_eq!(approvals[0].call, Some(DecodedCall {
+            function: "sendTransaction".to_owned(),
+            args,
+        }));
+        assert!(approvals[0].approved);
+    }
+}
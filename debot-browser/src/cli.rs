@@ -0,0 +1,674 @@
+/*
+* Copyright 2018-2021 TON DEV SOLUTIONS LTD.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific TON DEV software governing permissions and
+* limitations under the License.
+*/
+use crate::browser_io::{default_io, BrowserIo, ScriptedIo};
+use crate::debot_cache::{DebotCache, FileStorage};
+use crate::config::{resolve_endpoints, Config, MnemonicConfig, UserSettings};
+use crate::crypto::load_keypair;
+use crate::helpers::create_client;
+use crate::keystore::KeyStore;
+use crate::local_engine::{LocalDebotParams, LocalDebots};
+use crate::term_signing_box::TerminalSigningBox;
+use crate::term_browser::BrowserOptions;
+use crate::events::{JsonLinesListener, SharedEventListener};
+use crate::policy::ApprovePolicy;
+use crate::trust::TrustList;
+use crate::{ChainLink, DebotManifest, TerminalBrowser};
+use clap::{App, AppSettings, Arg, ArgMatches, ErrorKind, SubCommand};
+use std::ffi::OsString;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+const DEFAULT_CONFIG: &str = "tonos-cli.conf.json";
+
+/// Process exit codes returned by cli browser.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExitCode {
+    Success = 0,
+    /// Invalid command line arguments, config or manifest.
+    InvalidArgs = 1,
+    /// Failed to connect to network or to load DeBot.
+    LoadError = 2,
+    /// DeBot failed during execution.
+    RunError = 3,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct RunArgs {
+    pub addr: Option<String>,
+    pub url: Option<String>,
+    pub manifest: Option<String>,
+    pub keys: Option<String>,
+    pub wallet: Option<String>,
+    pub pubkey: Option<String>,
+    pub config: Option<String>,
+    pub local: Vec<String>,
+    pub cache: Option<String>,
+    pub trust: Option<String>,
+    pub policy: Option<String>,
+    pub events: Option<String>,
+    pub record: Option<String>,
+    pub keystore: Option<String>,
+    pub key: Option<String>,
+    pub hd_path: Option<String>,
+    pub dictionary: Option<String>,
+    pub word_count: Option<String>,
+}
+
+fn create_app<'a, 'b>() -> App<'a, 'b> {
+    App::new("debot-browser")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Standalone cli DeBot Browser")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(SubCommand::with_name("run")
+            .about("Runs DeBot. Exit argument is printed as JSON.")
+            .arg(Arg::with_name("ADDRESS")
+                .help("DeBot address. Can be omitted if manifest is used."))
+            .arg(Arg::with_name("URL")
+                .long("url")
+                .short("u")
+                .takes_value(true)
+                .help("Network url or name (e.g. net.ton.dev)."))
+            .arg(Arg::with_name("MANIFEST")
+                .long("manifest")
+                .short("m")
+                .takes_value(true)
+                .help("Path to DeBot manifest file."))
+            .arg(Arg::with_name("KEYS")
+                .long("keys")
+                .short("k")
+                .takes_value(true)
+                .help("Path to keypair file or seed phrase used to sign messages."))
+            .arg(Arg::with_name("WALLET")
+                .long("wallet")
+                .takes_value(true)
+                .help("User wallet address returned by UserInfo interface."))
+            .arg(Arg::with_name("PUBKEY")
+                .long("pubkey")
+                .takes_value(true)
+                .help("User public key returned by UserInfo interface."))
+            .arg(Arg::with_name("CONFIG")
+                .long("config")
+                .short("c")
+                .takes_value(true)
+                .help("Path to tonos-cli config file."))
+            .arg(Arg::with_name("LOCAL")
+                .long("local")
+                .short("l")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("Path to local DeBot description. Local DeBots are executed without network."))
+            .arg(Arg::with_name("CACHE")
+                .long("cache")
+                .takes_value(true)
+                .help("Path to file with cache of DeBot info and ABI."))
+            .arg(Arg::with_name("TRUST")
+                .long("trust")
+                .takes_value(true)
+                .help("Path to trust list file. DeBots with unexpected code hash are refused."))
+            .arg(Arg::with_name("POLICY")
+                .long("policy")
+                .takes_value(true)
+                .help("Path to approve policy file. Used if manifest has no policy."))
+            .arg(Arg::with_name("EVENTS")
+                .long("events")
+                .takes_value(true)
+                .help("Path to file where browser events are written as JSON lines. Use '-' for stderr."))
+            .arg(Arg::with_name("RECORD")
+                .long("record")
+                .takes_value(true)
+                .help("Path to file where manifest reproducing the session is written."))
+            .arg(Arg::with_name("KEYSTORE")
+                .long("keystore")
+                .takes_value(true)
+                .help("Path to keystore file. Keystore keys can be entered by name or public key."))
+            .arg(Arg::with_name("KEY")
+                .long("key")
+                .takes_value(true)
+                .help("Name or public key of keystore entry returned by UserInfo interface."))
+            .arg(Arg::with_name("HD_PATH")
+                .long("hd-path")
+                .takes_value(true)
+                .help("HD derivation path used to get keys from seed phrase."))
+            .arg(Arg::with_name("DICTIONARY")
+                .long("dictionary")
+                .takes_value(true)
+                .help("Seed phrase dictionary, 1 is English."))
+            .arg(Arg::with_name("WORD_COUNT")
+                .long("word-count")
+                .takes_value(true)
+                .help("Number of words in seed phrase. Detected from phrase by default.")))
+        .subcommand(SubCommand::with_name("keystore")
+            .about("Manages encrypted keystore.")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .arg(Arg::with_name("FILE")
+                .required(true)
+                .help("Path to keystore file."))
+            .subcommand(SubCommand::with_name("add")
+                .about("Encrypts keypair with passphrase and adds it to keystore. Keys are read from terminal.")
+                .arg(Arg::with_name("NAME")
+                    .required(true)
+                    .help("Name of the key."))
+                .arg(Arg::with_name("HD_PATH")
+                    .long("hd-path")
+                    .takes_value(true)
+                    .help("HD derivation path used to get keys from seed phrase.")))
+            .subcommand(SubCommand::with_name("list")
+                .about("Prints names and public keys of keystore entries."))
+            .subcommand(SubCommand::with_name("unlock")
+                .about("Checks passphrase of the key.")
+                .arg(Arg::with_name("NAME")
+                    .required(true)
+                    .help("Name or public key of the key."))))
+}
+
+/// Parsed command line of cli browser.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Run(RunArgs),
+    Keystore(KeystoreArgs),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum KeystoreAction {
+    /// Adds keypair (path to keypair file or seed phrase) under the name.
+    Add { name: String, hd_path: Option<String> },
+    List,
+    /// Checks passphrase of the entry.
+    Unlock { name: String },
+}
+
+#[derive(Debug, PartialEq)]
+pub struct KeystoreArgs {
+    pub path: String,
+    pub action: KeystoreAction,
+}
+
+fn run_args(m: &ArgMatches) -> RunArgs {
+    let value = |name: &str| m.value_of(name).map(|x| x.to_owned());
+    RunArgs {
+        addr: value("ADDRESS"),
+        url: value("URL"),
+        manifest: value("MANIFEST"),
+        keys: value("KEYS"),
+        wallet: value("WALLET"),
+        pubkey: value("PUBKEY"),
+        config: value("CONFIG"),
+        local: m
+            .values_of("LOCAL")
+            .map(|values| values.map(|x| x.to_owned()).collect())
+            .unwrap_or_default(),
+        cache: value("CACHE"),
+        trust: value("TRUST"),
+        policy: value("POLICY"),
+        events: value("EVENTS"),
+        record: value("RECORD"),
+        keystore: value("KEYSTORE"),
+        key: value("KEY"),
+        hd_path: value("HD_PATH"),
+        dictionary: value("DICTIONARY"),
+        word_count: value("WORD_COUNT"),
+    }
+}
+
+fn keystore_args(m: &ArgMatches) -> KeystoreArgs {
+    let value = |m: &ArgMatches, name: &str| m.value_of(name).unwrap_or_default().to_owned();
+    let action = match m.subcommand() {
+        ("add", Some(m)) => KeystoreAction::Add {
+            name: value(m, "NAME"),
+            hd_path: m.value_of("HD_PATH").map(|x| x.to_owned()),
+        },
+        ("unlock", Some(m)) => KeystoreAction::Unlock { name: value(m, "NAME") },
+        _ => KeystoreAction::List,
+    };
+    KeystoreArgs { path: value(m, "FILE"), action }
+}
+
+pub fn parse_command<I, T>(args: I) -> Result<Command, clap::Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let matches = create_app().get_matches_from_safe(args)?;
+    Ok(match matches.subcommand() {
+        ("keystore", Some(m)) => Command::Keystore(keystore_args(m)),
+        ("run", Some(m)) => Command::Run(run_args(m)),
+        _ => Command::Run(RunArgs::default()),
+    })
+}
+
+/// Parses arguments of `run` command. Other commands are rejected.
+pub fn parse_args<I, T>(args: I) -> Result<RunArgs, clap::Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    match parse_command(args)? {
+        Command::Run(args) => Ok(args),
+        Command::Keystore(_) => Err(clap::Error::with_description(
+            "expected `run` command, found `keystore`",
+            ErrorKind::InvalidSubcommand,
+        )),
+    }
+}
+
+fn load_manifest(args: &RunArgs) -> Result<DebotManifest, String> {
+    let mut manifest = match &args.manifest {
+        Some(path) => {
+            let text = std::fs::read_to_string(path)
+                .map_err(|e| format!("failed to read manifest: {}", e))?;
+            serde_json::from_str(&text).map_err(|e| format!("invalid manifest: {}", e))?
+        },
+        None => DebotManifest::new(),
+    };
+    if let Some(addr) = &args.addr {
+        manifest.debot_address = addr.clone();
+    }
+    if manifest.debot_address.is_empty() {
+        return Err(format!("DeBot address is not defined"));
+    }
+    Ok(manifest)
+}
+
+fn load_config(args: &RunArgs) -> Result<Config, String> {
+    let mut config = match &args.config {
+        Some(path) => Config::from_file(path).ok_or(format!("failed to load config {}", path))?,
+        None => Config::from_file(DEFAULT_CONFIG).unwrap_or(Config::new()),
+    };
+    if let Some(url) = &args.url {
+        config.url = Some(url.clone());
+    }
+    config.endpoints = match config.url.take() {
+        Some(url) => resolve_endpoints(&url),
+        // local DeBots can run without network
+        None if !args.local.is_empty() => vec![],
+        None => return Err(format!("network url is not defined")),
+    };
+    if args.wallet.is_some() {
+        config.wallet = args.wallet.clone();
+    }
+    if args.pubkey.is_some() {
+        config.pubkey = args.pubkey.clone();
+    }
+    if args.keys.is_some() {
+        config.keys_path = args.keys.clone();
+    }
+    if let Some(hd_path) = &args.hd_path {
+        config.mnemonic.hd_path = hd_path.clone();
+    }
+    if let Some(dictionary) = &args.dictionary {
+        config.mnemonic.dictionary = dictionary
+            .parse()
+            .map_err(|e| format!("invalid dictionary: {}", e))?;
+    }
+    if let Some(word_count) = &args.word_count {
+        config.mnemonic.word_count = Some(
+            word_count.parse().map_err(|e| format!("invalid word count: {}", e))?
+        );
+    }
+    Ok(config)
+}
+
+fn load_trust_list(path: &str) -> Result<TrustList, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read trust list: {}", e))?;
+    serde_json::from_str(&text).map_err(|e| format!("invalid trust list: {}", e))
+}
+
+fn load_policy(path: &str) -> Result<ApprovePolicy, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read approve policy: {}", e))?;
+    serde_json::from_str(&text).map_err(|e| format!("invalid approve policy: {}", e))
+}
+
+fn create_event_listener(path: &str) -> Result<SharedEventListener, String> {
+    if path == "-" {
+        return Ok(Arc::new(JsonLinesListener::new(std::io::stderr())));
+    }
+    let file = std::fs::File::create(path)
+        .map_err(|e| format!("failed to create events file: {}", e))?;
+    Ok(Arc::new(JsonLinesListener::new(file)))
+}
+
+fn save_recorded_manifest(manifest: Option<DebotManifest>, path: &str) -> Result<(), String> {
+    let manifest = manifest.ok_or(format!("session is not recorded"))?;
+    let text = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| format!("failed to serialize recorded manifest: {}", e))?;
+    std::fs::write(path, text).map_err(|e| format!("failed to write recorded manifest: {}", e))
+}
+
+/// Loads local DeBot description from json file. `state`, `tvc` and `abi`
+/// fields are paths to files relative to the description file.
+fn load_local_debot(path: &str) -> Result<LocalDebotParams, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read local DeBot {}: {}", path, e))?;
+    let mut params: LocalDebotParams = serde_json::from_str(&text)
+        .map_err(|e| format!("invalid local DeBot {}: {}", path, e))?;
+    let dir = Path::new(path).parent().unwrap_or(Path::new(""));
+    let read_boc = |file: &String| {
+        std::fs::read(dir.join(file))
+            .map(|data| base64::encode(&data))
+            .map_err(|e| format!("failed to read {}: {}", file, e))
+    };
+    params.state = params.state.as_ref().map(read_boc).transpose()?;
+    params.tvc = params.tvc.as_ref().map(read_boc).transpose()?;
+    let abi_path = params
+        .abi
+        .as_str()
+        .ok_or(format!("invalid local DeBot {}: abi must be a path to ABI file", path))?;
+    let abi = std::fs::read_to_string(dir.join(abi_path))
+        .map_err(|e| format!("failed to read {}: {}", abi_path, e))?;
+    params.abi = serde_json::Value::String(abi);
+    Ok(params)
+}
+
+/// Runs DeBot according to command line arguments. Returns DeBot exit argument.
+pub async fn run(args: RunArgs) -> Result<Option<serde_json::Value>, (ExitCode, String)> {
+    let invalid_args = |e: String| (ExitCode::InvalidArgs, e);
+    let load_error = |e: String| (ExitCode::LoadError, e);
+
+    let config = load_config(&args).map_err(invalid_args)?;
+    let client = create_client(&config).map_err(load_error)?;
+
+    let mut local_debots = LocalDebots::new();
+    for path in &args.local {
+        let params = load_local_debot(path).map_err(invalid_args)?;
+        local_debots.add(client.clone(), params).await.map_err(load_error)?;
+    }
+    let mut args = args;
+    if args.addr.is_none() && args.manifest.is_none() && args.local.len() == 1 {
+        args.addr = local_debots.first_address();
+    }
+    let mut manifest = load_manifest(&args).map_err(invalid_args)?;
+
+    let mut user_settings = UserSettings::default();
+    user_settings.wallet = config.wallet.clone();
+    user_settings.pubkey = config.pubkey.clone();
+    user_settings.mnemonic = config.mnemonic.clone();
+    if let Some(path) = &args.keystore {
+        user_settings.keystore = Some(KeyStore::from_file(path).map_err(invalid_args)?);
+        user_settings.key = args.key.clone();
+    }
+    if let Some(keys) = &config.keys_path {
+        let io = ScriptedIo::new(vec![keys.clone()]);
+        let mut sbox = TerminalSigningBox::new(client.clone(), vec![], &io, None, &config.mnemonic)
+            .await
+            .map_err(invalid_args)?;
+        let sbox_handle = sbox.leak();
+        for link in manifest.chain.iter_mut() {
+            match link {
+                ChainLink::SigningBox { handle } => *handle = sbox_handle.0,
+                ChainLink::EncryptionBox { handle: None, keys: link_keys @ None } => {
+                    *link_keys = Some(keys.clone());
+                },
+                _ => {},
+            }
+        }
+        if keys.find(' ').is_none() {
+            user_settings.keys_path = Some(keys.clone());
+        }
+    }
+
+    let addr = manifest.debot_address.clone();
+    let mut options = BrowserOptions { local_debots, ..Default::default() };
+    if let Some(path) = &args.cache {
+        let mut cache = DebotCache::new();
+        cache.set_storage(Box::new(FileStorage::new(path))).map_err(invalid_args)?;
+        options.cache = Arc::new(RwLock::new(cache));
+    }
+    if let Some(path) = &args.trust {
+        options.trust_list = load_trust_list(path).map_err(invalid_args)?;
+    }
+    if let Some(path) = &args.policy {
+        options.policy = Some(load_policy(path).map_err(invalid_args)?);
+    }
+    if let Some(path) = &args.events {
+        options.listeners.push(create_event_listener(path).map_err(invalid_args)?);
+    }
+    options.record = args.record.is_some();
+    let mut browser = TerminalBrowser::new_with_options(client, user_settings, addr, options)
+        .await
+        .map_err(load_error)?;
+    let result = browser
+        .run_manifest(manifest)
+        .await
+        .map_err(|e| (ExitCode::RunError, e));
+    // Manifest is saved even if DeBot failed: it reproduces the failure.
+    if let Some(path) = &args.record {
+        let saved = save_recorded_manifest(browser.recorded_manifest().await, path);
+        if let (Ok(_), Err(e)) = (&result, saved) {
+            return Err((ExitCode::RunError, e));
+        }
+    }
+    result
+}
+
+/// Executes keystore command. Passphrases are read with `io`.
+pub async fn run_keystore(args: KeystoreArgs, io: &(dyn BrowserIo + Send + Sync)) -> Result<(), String> {
+    let exists = Path::new(&args.path).exists();
+    let mut keystore = if exists { KeyStore::from_file(&args.path)? } else { KeyStore::new() };
+    match args.action {
+        KeystoreAction::Add { name, hd_path } => {
+            let mut mnemonic = MnemonicConfig::default();
+            if let Some(hd_path) = hd_path {
+                mnemonic.hd_path = hd_path;
+            }
+            let keys = io.secret("enter seed phrase or path to keypair file").await?;
+            let keys = load_keypair(keys.trim(), &mnemonic)?;
+            let passphrase = io.secret("enter passphrase").await?;
+            if io.secret("repeat passphrase").await? != passphrase {
+                return Err(format!("passphrases do not match"));
+            }
+            keystore.create(&name, &keys, &passphrase)?;
+            keystore.to_file(&args.path)?;
+            io.print(&format!("key \"{}\" added: {}", name, keys.public)).await;
+        },
+        KeystoreAction::List => {
+            for (name, public) in keystore.list() {
+                io.print(&format!("{} {}", name, public)).await;
+            }
+        },
+        KeystoreAction::Unlock { name } => {
+            let keys = keystore.unlock_interactive(&name, io, 1).await?;
+            io.print(&format!("key \"{}\" unlocked: {}", name, keys.public)).await;
+        },
+    }
+    Ok(())
+}
+
+/// Entry point of cli browser. Returns process exit code.
+pub async fn main_with_args<I, T>(args: I) -> i32
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let args = match parse_command(args) {
+        Ok(args) => args,
+        Err(e) if e.use_stderr() => {
+            eprintln!("{}", e.message);
+            return ExitCode::InvalidArgs as i32;
+        },
+        Err(e) => {
+            println!("{}", e.message);
+            return ExitCode::Success as i32;
+        },
+    };
+    let args = match args {
+        Command::Run(args) => args,
+        Command::Keystore(args) => {
+            return match run_keystore(args, default_io().as_ref()).await {
+                Ok(()) => ExitCode::Success as i32,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    ExitCode::InvalidArgs as i32
+                },
+            };
+        },
+    };
+    match run(args).await {
+        Ok(exit_arg) => {
+            let exit_arg = exit_arg.unwrap_or(serde_json::Value::Null);
+            println!("{}", serde_json::to_string_pretty(&exit_arg).unwrap_or_default());
+            ExitCode::Success as i32
+        },
+        Err((code, e)) => {
+            eprintln!("Error: {}", e);
+            code as i32
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_args() {
+        let args = parse_args(vec![
+            "debot-browser", "run", "0:1234", "--url", "net.ton.dev", "-m", "manifest.json",
+            "--keys", "keys.json", "--cache", "cache.json", "--events", "-",
+        ]).unwrap();
+        assert_eq!(args, RunArgs {
+            addr: Some("0:1234".to_owned()),
+            url: Some("net.ton.dev".to_owned()),
+            manifest: Some("manifest.json".to_owned()),
+            keys: Some("keys.json".to_owned()),
+            cache: Some("cache.json".to_owned()),
+            events: Some("-".to_owned()),
+            ..Default::default()
+        });
+
+        let args = parse_args(vec![
+            "debot-browser", "run", "--local", "debot.json", "-l", "other.json",
+        ]).unwrap();
+        assert_eq!(args.addr, None);
+        assert_eq!(args.local, vec!["debot.json".to_owned(), "other.json".to_owned()]);
+
+        assert!(parse_args(vec!["debot-browser"]).is_err());
+        assert!(parse_args(vec!["debot-browser", "run", "--unknown"]).is_err());
+        let err = parse_args(vec!["debot-browser", "keystore", "keys.json", "list"]).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidSubcommand);
+    }
+
+    #[test]
+    fn test_load_manifest() {
+        let args = RunArgs { addr: Some("0:1234".to_owned()), ..Default::default() };
+        let manifest = load_manifest(&args).unwrap();
+        assert_eq!(manifest.debot_address, "0:1234");
+        assert_eq!(manifest.init_method, "start");
+        assert!(!manifest.quiet);
+
+        assert!(load_manifest(&RunArgs::default()).is_err());
+        let args = RunArgs { manifest: Some("not-exists.json".to_owned()), ..Default::default() };
+        assert!(load_manifest(&args).is_err());
+    }
+
+    #[test]
+    fn test_load_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        let path_str = path.to_str().unwrap().to_owned();
+        let missing = dir.path().join("not-exists.json");
+        std::fs::write(&path, r#"{"url": "https://example.com", "wallet": "0:2222"}"#).unwrap();
+
+        let args = RunArgs {
+            url: Some("net.ton.dev".to_owned()),
+            wallet: Some("0:1111".to_owned()),
+            config: Some(missing.to_str().unwrap().to_owned()),
+            ..Default::default()
+        };
+        assert!(load_config(&args).is_err());
+
+        let args = RunArgs { config: Some(path_str.clone()), ..args };
+        let config = load_config(&args).unwrap();
+        assert!(config.url.is_none());
+        assert_eq!(config.endpoints.len(), 2);
+        assert_eq!(config.wallet, Some("0:1111".to_owned()));
+        let args = RunArgs { url: None, wallet: None, ..args };
+        let config = load_config(&args).unwrap();
+        assert_eq!(config.endpoints, vec!["https://example.com".to_owned()]);
+        assert_eq!(config.wallet, Some("0:2222".to_owned()));
+
+        std::fs::write(&path, "{}").unwrap();
+        assert!(load_config(&args).is_err());
+        let args = RunArgs { local: vec!["debot.json".to_owned()], ..args };
+        assert!(load_config(&args).unwrap().endpoints.is_empty());
+
+        let args = parse_args(vec![
+            "debot-browser", "run", "0:1234", "-u", "net.ton.dev", "--config", &path_str,
+            "--hd-path", "m/44'/396'/0'/0/1", "--word-count", "24",
+        ]).unwrap();
+        let config = load_config(&args).unwrap();
+        assert_eq!(config.mnemonic.hd_path, "m/44'/396'/0'/0/1");
+        assert_eq!(config.mnemonic.word_count, Some(24));
+        assert_eq!(config.mnemonic.dictionary, 1);
+        let args = RunArgs { dictionary: Some("english".to_owned()), ..args };
+        assert!(load_config(&args).is_err());
+    }
+
+    #[test]
+    fn test_load_local_debot() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        std::fs::write(dir.join("debot.tvc"), [0xb5u8, 0xee, 0x9c, 0x72]).unwrap();
+        std::fs::write(dir.join("debot.abi.json"), r#"{"version": "2.2"}"#).unwrap();
+        let path = dir.join("debot.json");
+        std::fs::write(&path, r#"{"tvc": "debot.tvc", "abi": "debot.abi.json", "initialData": {}}"#).unwrap();
+
+        let params = load_local_debot(path.to_str().unwrap()).unwrap();
+        assert_eq!(params.tvc, Some("te6ccg==".to_owned()));
+        assert_eq!(params.state, None);
+        assert_eq!(params.abi, serde_json::json!(r#"{"version": "2.2"}"#));
+        assert!(params.initial_data.is_some());
+
+        std::fs::write(&path, r#"{"tvc": "missing.tvc", "abi": "debot.abi.json"}"#).unwrap();
+        assert!(load_local_debot(path.to_str().unwrap()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_keystore_command() {
+        let command = parse_command(vec![
+            "debot-browser", "keystore", "keys.json", "add", "main",
+        ]).unwrap();
+        assert_eq!(command, Command::Keystore(KeystoreArgs {
+            path: "keys.json".to_owned(),
+            action: KeystoreAction::Add {
+                name: "main".to_owned(),
+                hd_path: None,
+            },
+        }));
+        assert!(parse_command(vec!["debot-browser", "keystore", "keys.json"]).is_err());
+        assert!(parse_command(vec![
+            "debot-browser", "keystore", "keys.json", "add", "main", "seed.txt",
+        ]).is_err());
+        let args = parse_args(vec![
+            "debot-browser", "run", "0:1234", "--keystore", "keys.json", "--key", "main",
+        ]).unwrap();
+        assert_eq!(args.keystore, Some("keys.json".to_owned()));
+        assert_eq!(args.key, Some("main".to_owned()));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keystore.json").to_str().unwrap().to_owned();
+        std::fs::write(&path, r#"{"version": 1, "entries": [
+            {"name": "main", "public": "11", "secret": "", "salt": "", "nonce": ""}
+        ]}"#).unwrap();
+        let io = ScriptedIo::default();
+        run_keystore(KeystoreArgs { path: path.clone(), action: KeystoreAction::List }, &io)
+            .await
+            .unwrap();
+        assert_eq!(io.output(), vec!["main 11".to_owned()]);
+        let unlock = KeystoreArgs { path: path.clone(), action: KeystoreAction::Unlock { name: "other".to_owned() } };
+        assert!(run_keystore(unlock, &io).await.is_err());
+    }
+}
@@ -10,7 +10,9 @@
  * See the License for the specific TON DEV software governing permissions and
  * limitations under the License.
  */
-use crate::SigningBoxHandle;
+use crate::helpers::{phrase_word_count, HD_PATH};
+use crate::keystore::KeyStore;
+use crate::{EncryptionBoxHandle, SigningBoxHandle};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::sync::Arc;
@@ -48,6 +50,44 @@ fn default_endpoints() -> Vec<String> {
     return vec!();
 }
 
+fn default_dictionary() -> u8 {
+    1
+}
+
+fn default_hd_path() -> String {
+    HD_PATH.to_owned()
+}
+
+/// Parameters used to derive keys from seed phrase.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MnemonicConfig {
+    /// BIP-39 dictionary (see TON SDK `MnemonicDictionary`), 1 is English.
+    #[serde(default = "default_dictionary")]
+    pub dictionary: u8,
+    /// Number of words in seed phrase. Detected from phrase if not defined.
+    pub word_count: Option<u8>,
+    /// HD derivation path.
+    #[serde(default = "default_hd_path")]
+    pub hd_path: String,
+}
+
+impl Default for MnemonicConfig {
+    fn default() -> Self {
+        Self {
+            dictionary: default_dictionary(),
+            word_count: None,
+            hd_path: default_hd_path(),
+        }
+    }
+}
+
+impl MnemonicConfig {
+    /// Returns configured word count or word count detected from the phrase.
+    pub fn word_count_of(&self, phrase: &str) -> u8 {
+        self.word_count.unwrap_or_else(|| phrase_word_count(phrase))
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Config {
     pub url: Option<String>,
@@ -78,6 +118,8 @@ pub struct Config {
     pub async_call: bool,
     #[serde(default = "default_endpoints")]
     pub endpoints: Vec<String>,
+    #[serde(default)]
+    pub mnemonic: MnemonicConfig,
 }
 
 #[derive(Deserialize, Clone, Default)]
@@ -85,8 +127,20 @@ pub struct UserSettings {
     pub wallet: Option<String>,
     pub pubkey: Option<String>,
     pub signing_box: Option<SigningBoxHandle>,
+    /// Encryption boxes returned by EncryptionBoxInput interface instead of
+    /// asking user for keys: [algorithm] -> handle. Algorithms are `NaclBox`,
+    /// `NaclSecretBox` and `ChaCha20`.
+    #[serde(default)]
+    pub encryption_boxes: BTreeMap<String, EncryptionBoxHandle>,
     /// For compatibility with tonos-cli. Remove when possible.
     pub keys_path: Option<String>,
+    /// Encrypted keys offered to DeBots by name or public key.
+    pub keystore: Option<KeyStore>,
+    /// Name or public key of the keystore entry returned by UserInfo interface.
+    pub key: Option<String>,
+    /// Used to derive keys from seed phrases entered by user.
+    #[serde(default)]
+    pub mnemonic: MnemonicConfig,
 }
 
 pub type SharedUserSettings = Arc<RwLock<UserSettings>>;
@@ -122,6 +176,7 @@ impl Config {
             local_run: default_false(),
             async_call: default_false(),
             endpoints,
+            mnemonic: MnemonicConfig::default(),
         }
     }
 
@@ -218,7 +273,7 @@ impl FullConfig {
 }
 #[cfg(test)]
 mod tests {
-    use super::{resolve_net_name};
+    use super::{resolve_net_name, MnemonicConfig, UserSettings};
 
     #[test]
     fn test_endpoints_resolver() {
@@ -251,4 +306,27 @@ mod tests {
         assert_eq!(resolve_net_name("net.ton.dev"), Some("net.ton.dev".to_owned()));
         assert_eq!(resolve_net_name("net.ton.com"), None);
     }
+
+    #[test]
+    fn test_mnemonic_word_count() {
+        let phrase12 = "multiply extra monitor fog rocket defy attack right night jaguar hollow enlist";
+        let phrase24 = format!("{} {}", phrase12, phrase12);
+        let config = MnemonicConfig::default();
+        assert_eq!(config.word_count_of(phrase12), 12);
+        assert_eq!(config.word_count_of(&phrase24), 24);
+        assert_eq!(config.word_count_of("extra"), 12);
+        assert_eq!(config.word_count_of(""), 12);
+
+        let config = MnemonicConfig { word_count: Some(24), ..Default::default() };
+        assert_eq!(config.word_count_of(phrase12), 24);
+
+        let settings: UserSettings = serde_json::from_str(
+            r#"{"mnemonic": {"word_count": 24, "hd_path": "m/44'/396'/0'/0/1"}}"#
+        ).unwrap();
+        assert_eq!(settings.mnemonic.dictionary, 1);
+        assert_eq!(settings.mnemonic.word_count, Some(24));
+        assert_eq!(settings.mnemonic.hd_path, "m/44'/396'/0'/0/1");
+        let settings: UserSettings = serde_json::from_str("{}").unwrap();
+        assert_eq!(settings.mnemonic, MnemonicConfig::default());
+    }
 }
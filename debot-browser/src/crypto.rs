@@ -10,7 +10,8 @@
  * See the License for the specific TON DEV software governing permissions and
  * limitations under the License.
  */
-use crate::helpers::{create_client_local, WORD_COUNT, HD_PATH};
+use crate::config::MnemonicConfig;
+use crate::helpers::{create_client_local, WORD_COUNT};
 #[cfg(not(target_arch = "wasm32"))]
 use crate::helpers::read_keys;
 use ton_client::crypto::{
@@ -27,29 +28,40 @@ use ton_client::crypto::{
     ParamsOfMnemonicFromRandom
 };
 
+/// Returns true if keys are seed phrase, not path to keypair file.
 #[cfg(not(target_arch = "wasm32"))]
-pub fn load_keypair(keys: &str) -> Result<KeyPair, String> {
-    if keys.find(' ').is_none() {
+pub fn is_mnemonic(keys: &str) -> bool {
+    keys.find(' ').is_some()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn is_mnemonic(_keys: &str) -> bool {
+    true
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_keypair(keys: &str, mnemonic: &MnemonicConfig) -> Result<KeyPair, String> {
+    if !is_mnemonic(keys) {
         let keys = read_keys(&keys)?;
         Ok(keys)
     } else {
-        generate_keypair_from_mnemonic(&keys)
+        generate_keypair_from_mnemonic(&keys, mnemonic)
     }
 }
 
 #[cfg(target_arch = "wasm32")]
-pub fn load_keypair(keys: &str) -> Result<KeyPair, String> {
-    generate_keypair_from_mnemonic(&keys)
+pub fn load_keypair(keys: &str, mnemonic: &MnemonicConfig) -> Result<KeyPair, String> {
+    generate_keypair_from_mnemonic(&keys, mnemonic)
 }
 
 #[allow(dead_code)]
-pub fn gen_seed_phrase() -> Result<String, String> {
+pub fn gen_seed_phrase(mnemonic: &MnemonicConfig) -> Result<String, String> {
     let client = create_client_local()?;
     mnemonic_from_random(
         client,
         ParamsOfMnemonicFromRandom {
-            dictionary: Some(1),
-            word_count: Some(WORD_COUNT),
+            dictionary: Some(mnemonic.dictionary),
+            word_count: Some(mnemonic.word_count.unwrap_or(WORD_COUNT)),
             ..Default::default()
         },
     )
@@ -57,14 +69,14 @@ pub fn gen_seed_phrase() -> Result<String, String> {
     .map(|r| r.phrase)
 }
 
-pub fn generate_keypair_from_mnemonic(mnemonic: &str) -> Result<KeyPair, String> {
+pub fn generate_keypair_from_mnemonic(phrase: &str, mnemonic: &MnemonicConfig) -> Result<KeyPair, String> {
     let client = create_client_local()?;
     let hdk_master = hdkey_xprv_from_mnemonic(
         client.clone(),
         ParamsOfHDKeyXPrvFromMnemonic {
-            dictionary: Some(1),
-            word_count: Some(WORD_COUNT),
-            phrase: mnemonic.to_string(),
+            dictionary: Some(mnemonic.dictionary),
+            word_count: Some(mnemonic.word_count_of(phrase)),
+            phrase: phrase.to_string(),
             ..Default::default()
         },
     ).map_err(|e| format!("{}", e))?;
@@ -73,7 +85,7 @@ pub fn generate_keypair_from_mnemonic(mnemonic: &str) -> Result<KeyPair, String>
         client.clone(),
         ParamsOfHDKeyDeriveFromXPrvPath {
             xprv: hdk_master.xprv,
-            path: HD_PATH.to_string(),
+            path: mnemonic.hd_path.clone(),
             ..Default::default()
         },
     ).map_err(|e| format!("{}", e))?;
@@ -110,12 +122,12 @@ mod tests {
     #[test]
     fn test_generate_keypair() {
         let mnemonic = "multiply extra monitor fog rocket defy attack right night jaguar hollow enlist";
-        let keypair = generate_keypair_from_mnemonic(mnemonic).unwrap();
+        let keypair = generate_keypair_from_mnemonic(mnemonic, &MnemonicConfig::default()).unwrap();
         assert_eq!(&keypair.public, "757221fe3d4992e44632e75e700aaf205d799cb7373ee929273daf26adf29e56");
         assert_eq!(&keypair.secret, "30e3bc5e67af2b0a72971bcc11256e83d052c6cb861a69a19a8af88922fadf3a");
 
         let mnemonic = "penalty nut enrich input palace flame safe session torch depth various hunt";
-        let keypair = generate_keypair_from_mnemonic(mnemonic).unwrap();
+        let keypair = generate_keypair_from_mnemonic(mnemonic, &MnemonicConfig::default()).unwrap();
         assert_eq!(&keypair.public, "8cf557aab2666867a1174e3147d89ddf28c2041a7322522276cd1cf1df47ae73");
         assert_eq!(&keypair.secret, "f63d3d11e0dc91f730f22d5397f269e01f1a5f984879c8581ac87f099bfd3b3a");
     }
@@ -141,8 +153,24 @@ mod tests {
         ];
 
         for phrase in invalid_phrases {
-            assert!(generate_keypair_from_mnemonic(phrase).is_err());
+            assert!(generate_keypair_from_mnemonic(phrase, &MnemonicConfig::default()).is_err());
         }
     }
 
+    #[test]
+    fn test_mnemonic_config() {
+        let phrase = "multiply extra monitor fog rocket defy attack right night jaguar hollow enlist";
+        let default = generate_keypair_from_mnemonic(phrase, &MnemonicConfig::default()).unwrap();
+        let config = MnemonicConfig { hd_path: "m/44'/396'/0'/0/1".to_owned(), ..Default::default() };
+        let keypair = generate_keypair_from_mnemonic(phrase, &config).unwrap();
+        assert_ne!(keypair.public, default.public);
+
+        let config = MnemonicConfig { word_count: Some(24), ..Default::default() };
+        assert!(generate_keypair_from_mnemonic(phrase, &config).is_err());
+
+        let phrase = gen_seed_phrase(&MnemonicConfig { word_count: Some(24), ..Default::default() }).unwrap();
+        assert_eq!(phrase.split(' ').count(), 24);
+        assert!(generate_keypair_from_mnemonic(&phrase, &MnemonicConfig::default()).is_ok());
+    }
+
 }
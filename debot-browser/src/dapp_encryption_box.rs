@@ -0,0 +1,56 @@
+use crate::dapp_signing_box::{sdk_mapper, JsFutureSync, Promise};
+use serde_wasm_bindgen::from_value;
+use std::sync::Arc;
+use ton_client::crypto::{EncryptionBox, EncryptionBoxInfo};
+use ton_client::error::ClientResult;
+use ton_client::ClientContext;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    pub type DAppEncryptionBox;
+
+    /// Resolves to object with optional `hdpath`, `algorithm`, `options`
+    /// and `public` fields.
+    #[wasm_bindgen(method)]
+    pub fn get_info(this: &DAppEncryptionBox) -> Promise;
+
+    /// Resolves to base64 encoded encrypted data. `data` is base64 encoded.
+    #[wasm_bindgen(method)]
+    pub fn encrypt(this: &DAppEncryptionBox, data: &str) -> Promise;
+
+    /// Resolves to base64 encoded decrypted data. `data` is base64 encoded.
+    #[wasm_bindgen(method)]
+    pub fn decrypt(this: &DAppEncryptionBox, data: &str) -> Promise;
+}
+
+unsafe impl Sync for DAppEncryptionBox {}
+unsafe impl Send for DAppEncryptionBox {}
+
+fn convert_jsvalue_to_string(val: JsValue) -> ClientResult<String> {
+    from_value(val).map_err(sdk_mapper("failed to decode JsValue"))
+}
+
+#[async_trait::async_trait]
+impl EncryptionBox for DAppEncryptionBox {
+    async fn get_info(&self, _context: Arc<ClientContext>) -> ClientResult<EncryptionBoxInfo> {
+        let val = JsFutureSync::from(self.get_info())
+            .await
+            .map_err(sdk_mapper("failed to get encryption box info"))?;
+        from_value(val).map_err(sdk_mapper("failed to decode encryption box info"))
+    }
+
+    async fn encrypt(&self, _context: Arc<ClientContext>, data: &String) -> ClientResult<String> {
+        let val = JsFutureSync::from(self.encrypt(data))
+            .await
+            .map_err(sdk_mapper("failed to encrypt"))?;
+        convert_jsvalue_to_string(val)
+    }
+
+    async fn decrypt(&self, _context: Arc<ClientContext>, data: &String) -> ClientResult<String> {
+        let val = JsFutureSync::from(self.decrypt(data))
+            .await
+            .map_err(sdk_mapper("failed to decrypt"))?;
+        convert_jsvalue_to_string(val)
+    }
+}
@@ -111,7 +111,7 @@ unsafe impl Send for DAppSigningBox {}
 unsafe impl Send for JsFutureSync {}
 unsafe impl Send for Promise {}
 
-fn sdk_mapper<E>(message: &str) -> impl FnOnce(E) -> ClientError {
+pub(crate) fn sdk_mapper<E>(message: &str) -> impl FnOnce(E) -> ClientError {
     let message = message.to_string();
     move |_e| ClientError::with_code_message(0, message)
 }
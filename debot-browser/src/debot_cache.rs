@@ -0,0 +1,247 @@
+/*
+* Copyright 2018-2021 TON DEV SOLUTIONS LTD.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific TON DEV software governing permissions and
+* limitations under the License.
+*/
+use crate::helpers::TonClient;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use ton_client::abi::Abi;
+use ton_client::debot::DebotInfo;
+use ton_client::net::{query_collection, ParamsOfQueryCollection};
+use tokio::sync::RwLock;
+
+/// Persistent storage of the DeBot cache.
+pub trait CacheStorage {
+    /// Returns previously saved cache data.
+    fn load(&self) -> Result<Option<String>, String>;
+    fn save(&self, data: &str) -> Result<(), String>;
+}
+
+/// Stores cache in a file.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct FileStorage {
+    path: String,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileStorage {
+    pub fn new(path: &str) -> Self {
+        Self { path: path.to_owned() }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl CacheStorage for FileStorage {
+    fn load(&self) -> Result<Option<String>, String> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("failed to read DeBot cache: {}", e)),
+        }
+    }
+
+    fn save(&self, data: &str) -> Result<(), String> {
+        std::fs::write(&self.path, data).map_err(|e| format!("failed to write DeBot cache: {}", e))
+    }
+}
+
+/// Stores cache in browser `localStorage` under the `key`.
+#[cfg(target_arch = "wasm32")]
+pub struct LocalStorage {
+    key: String,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl LocalStorage {
+    pub fn new(key: &str) -> Self {
+        Self { key: key.to_owned() }
+    }
+
+    fn storage() -> Result<web_sys::Storage, String> {
+        web_sys::window()
+            .ok_or(format!("window is not available"))?
+            .local_storage()
+            .map_err(|e| format!("localStorage is not available: {:?}", e))?
+            .ok_or(format!("localStorage is not available"))
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl CacheStorage for LocalStorage {
+    fn load(&self) -> Result<Option<String>, String> {
+        Self::storage()?
+            .get_item(&self.key)
+            .map_err(|e| format!("failed to read DeBot cache: {:?}", e))
+    }
+
+    fn save(&self, data: &str) -> Result<(), String> {
+        Self::storage()?
+            .set_item(&self.key, data)
+            .map_err(|e| format!("failed to write DeBot cache: {:?}", e))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct CacheEntry {
+    code_hash: String,
+    info: DebotInfo,
+    abi: Abi,
+}
+
+/// Cache of DeBot metadata and parsed ABI: [addr] -> entry.
+/// Entry is valid while DeBot code hash is not changed.
+#[derive(Default)]
+pub struct DebotCache {
+    entries: HashMap<String, CacheEntry>,
+    storage: Option<Box<dyn CacheStorage + Send + Sync>>,
+}
+
+pub type SharedDebotCache = Arc<RwLock<DebotCache>>;
+
+lazy_static! {
+    static ref DEFAULT_CACHE: SharedDebotCache = Arc::new(RwLock::new(DebotCache::new()));
+}
+
+/// Returns in-memory cache shared by all browsers of the process.
+pub fn default_cache() -> SharedDebotCache {
+    DEFAULT_CACHE.clone()
+}
+
+impl DebotCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Attaches persistent storage to the cache and loads entries from it.
+    /// Loaded entries are merged with the existing ones.
+    pub fn set_storage(&mut self, storage: Box<dyn CacheStorage + Send + Sync>) -> Result<(), String> {
+        if let Some(data) = storage.load()? {
+            let entries: HashMap<String, CacheEntry> = serde_json::from_str(&data)
+                .map_err(|e| format!("invalid DeBot cache: {}", e))?;
+            for (addr, entry) in entries {
+                self.entries.entry(addr).or_insert(entry);
+            }
+        }
+        self.storage = Some(storage);
+        Ok(())
+    }
+
+    /// Returns DeBot info and ABI if DeBot code is not changed since
+    /// they were cached. Outdated entry is removed.
+    pub fn get(&mut self, addr: &str, code_hash: &str) -> Option<(DebotInfo, Abi)> {
+        match self.entries.get(addr) {
+            Some(entry) if entry.code_hash == code_hash => {
+                Some((entry.info.clone(), entry.abi.clone()))
+            },
+            Some(_) => {
+                self.entries.remove(addr);
+                None
+            },
+            None => None,
+        }
+    }
+
+    pub fn insert(&mut self, addr: &str, code_hash: &str, info: DebotInfo, abi: Abi) -> Result<(), String> {
+        self.entries.insert(
+            addr.to_owned(),
+            CacheEntry { code_hash: code_hash.to_owned(), info, abi },
+        );
+        self.save()
+    }
+
+    pub fn clear(&mut self) -> Result<(), String> {
+        self.entries.clear();
+        self.save()
+    }
+
+    fn save(&self) -> Result<(), String> {
+        if let Some(storage) = &self.storage {
+            let data = serde_json::to_string(&self.entries)
+                .map_err(|e| format!("failed to serialize DeBot cache: {}", e))?;
+            storage.save(&data)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns `code_hash` and `data` fields of the account. `None` if account
+/// is not found or network is not available.
+pub(crate) async fn query_account(client: TonClient, addr: &str) -> Option<Value> {
+    let accounts = query_collection(
+        client,
+        ParamsOfQueryCollection {
+            collection: "accounts".to_owned(),
+            filter: Some(json!({ "id": { "eq": addr } })),
+            result: "code_hash data".to_owned(),
+            order: None,
+            limit: Some(1),
+        },
+    )
+    .await
+    .ok()?
+    .result;
+    accounts.into_iter().next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::load_abi;
+
+    const ADDR: &str = "0:1111111111111111111111111111111111111111111111111111111111111111";
+
+    fn info(name: &str) -> DebotInfo {
+        let mut info = DebotInfo::default();
+        info.name = Some(name.to_owned());
+        info
+    }
+
+    #[test]
+    fn test_invalidation() {
+        let mut cache = DebotCache::new();
+        let abi = load_abi(r#"{"version": "2.2", "functions": []}"#).unwrap();
+        assert!(cache.get(ADDR, "aa").is_none());
+        cache.insert(ADDR, "aa", info("v1"), abi.clone()).unwrap();
+        let (cached, _) = cache.get(ADDR, "aa").unwrap();
+        assert_eq!(cached.name, Some("v1".to_owned()));
+
+        assert!(cache.get(ADDR, "bb").is_none());
+        assert!(cache.get(ADDR, "aa").is_none());
+        cache.insert(ADDR, "bb", info("v2"), abi).unwrap();
+        assert_eq!(cache.get(ADDR, "bb").unwrap().0.name, Some("v2".to_owned()));
+    }
+
+    #[test]
+    fn test_file_storage() {
+        let path = std::env::temp_dir().join("debot-browser-test-cache.json");
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+        let abi = load_abi(r#"{"version": "2.2", "functions": []}"#).unwrap();
+
+        let mut cache = DebotCache::new();
+        cache.set_storage(Box::new(FileStorage::new(path))).unwrap();
+        cache.insert(ADDR, "aa", info("Hello"), abi).unwrap();
+
+        let mut loaded = DebotCache::new();
+        loaded.set_storage(Box::new(FileStorage::new(path))).unwrap();
+        assert_eq!(loaded.get(ADDR, "aa").unwrap().0.name, Some("Hello".to_owned()));
+
+        loaded.clear().unwrap();
+        let mut loaded = DebotCache::new();
+        loaded.set_storage(Box::new(FileStorage::new(path))).unwrap();
+        assert!(loaded.get(ADDR, "aa").is_none());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,199 @@
+/*
+* Copyright 2018-2021 TON DEV SOLUTIONS LTD.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific TON DEV software governing permissions and
+* limitations under the License.
+*/
+use serde::Serialize;
+use serde_json::Value;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+/// Event of the browser run. Serialized with `type` field equal to variant name.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type")]
+pub enum BrowserEvent {
+    #[serde(rename_all = "camelCase")]
+    DebotFetched {
+        address: String,
+        name: Option<String>,
+        abi_version: String,
+        /// True if DeBot info was taken from cache.
+        cached: bool,
+    },
+    #[serde(rename_all = "camelCase")]
+    InterfaceCall {
+        debot: String,
+        interface: String,
+        /// Function name and arguments. `None` if message can not be decoded.
+        function: Option<String>,
+        args: Option<Value>,
+    },
+    #[serde(rename_all = "camelCase")]
+    InterfaceAnswer {
+        debot: String,
+        interface: String,
+        /// Id of DeBot function called with the answer. 0 if there is no answer.
+        answer_id: u32,
+        result: Value,
+    },
+    #[serde(rename_all = "camelCase")]
+    InterfaceError {
+        debot: String,
+        interface: String,
+        error: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    MessageRouted {
+        src: String,
+        dst: String,
+        depth: u32,
+    },
+    #[serde(rename_all = "camelCase")]
+    ApprovalRequested {
+        activity: Value,
+    },
+    #[serde(rename_all = "camelCase")]
+    ApprovalDecided {
+        approved: bool,
+        /// Policy rule which decided the approve.
+        rule: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    SigningBoxRequested {
+        /// Handle from manifest chain. `None` if user is asked for keys.
+        handle: Option<u32>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Exit {
+        value: Option<Value>,
+        error: Option<String>,
+    },
+}
+
+/// Receiver of browser events.
+pub trait EventListener {
+    fn on_event(&self, event: &BrowserEvent);
+}
+
+pub type SharedEventListener = Arc<dyn EventListener + Send + Sync>;
+
+/// Sends events to all subscribed listeners.
+#[derive(Clone, Default)]
+pub struct EventEmitter {
+    listeners: Arc<Vec<SharedEventListener>>,
+}
+
+impl EventEmitter {
+    pub fn new(listeners: Vec<SharedEventListener>) -> Self {
+        Self { listeners: Arc::new(listeners) }
+    }
+
+    /// True if anybody listens. Used to skip event preparation.
+    pub fn enabled(&self) -> bool {
+        !self.listeners.is_empty()
+    }
+
+    pub fn emit(&self, event: BrowserEvent) {
+        for listener in self.listeners.iter() {
+            listener.on_event(&event);
+        }
+    }
+}
+
+/// Writes events as JSON lines.
+pub struct JsonLinesListener<W: Write + Send> {
+    writer: Mutex<W>,
+}
+
+impl<W: Write + Send> JsonLinesListener<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer: Mutex::new(writer) }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer.into_inner().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<W: Write + Send> EventListener for JsonLinesListener<W> {
+    fn on_event(&self, event: &BrowserEvent) {
+        if let (Ok(line), Ok(mut writer)) = (serde_json::to_string(event), self.writer.lock()) {
+            let _ = writeln!(writer, "{}", line);
+            let _ = writer.flush();
+        }
+    }
+}
+
+/// Calls JavaScript function with every event object.
+pub struct JsEventListener {
+    callback: js_sys::Function,
+}
+
+impl JsEventListener {
+    pub fn new(callback: js_sys::Function) -> Self {
+        Self { callback }
+    }
+}
+
+unsafe impl Send for JsEventListener {}
+unsafe impl Sync for JsEventListener {}
+
+impl EventListener for JsEventListener {
+    fn on_event(&self, event: &BrowserEvent) {
+        let serializer = serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+        if let Ok(value) = event.serialize(&serializer) {
+            let _ = self.callback.call1(&wasm_bindgen::JsValue::NULL, &value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_json_lines() {
+        let listener = Arc::new(JsonLinesListener::new(Vec::<u8>::new()));
+        let events = EventEmitter::new(vec![listener.clone()]);
+        assert!(events.enabled());
+        assert!(!EventEmitter::default().enabled());
+
+        events.emit(BrowserEvent::MessageRouted {
+            src: "0:11".to_owned(),
+            dst: "-31:22".to_owned(),
+            depth: 0,
+        });
+        events.emit(BrowserEvent::InterfaceAnswer {
+            debot: "0:11".to_owned(),
+            interface: "22".to_owned(),
+            answer_id: 7,
+            result: json!({"value": "1"}),
+        });
+        drop(events);
+
+        let output = Arc::try_unwrap(listener).ok().unwrap().into_inner();
+        let lines: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines, vec![
+            json!({"type": "MessageRouted", "src": "0:11", "dst": "-31:22", "depth": 0}),
+            json!({
+                "type": "InterfaceAnswer",
+                "debot": "0:11",
+                "interface": "22",
+                "answerId": 7,
+                "result": {"value": "1"}
+            }),
+        ]);
+    }
+}
@@ -15,6 +15,7 @@ use std::sync::Arc;
 use ton_client::abi::{
     Abi, AbiConfig, AbiContract};
 use ton_client::crypto::{CryptoConfig};
+use ton_client::encoding::decode_abi_number;
 use ton_client::{ClientConfig, ClientContext};
 
 pub const HD_PATH: &str = "m/44'/396'/0'/0/0";
@@ -37,6 +38,70 @@ pub fn load_ton_address(addr: &str, wc: i32) -> Result<String, String> {
     Ok(addr)
 }
 
+/// Returns address in raw form `wc:hex` with lower case hex. Accepts raw
+/// address, user-friendly address and hex without workchain (workchain 0).
+pub fn normalize_address(addr: &str) -> String {
+    let addr = addr.trim();
+    friendly_to_raw(addr)
+        .unwrap_or_else(|| load_ton_address(addr, 0).unwrap_or_else(|_| addr.to_owned()))
+        .to_lowercase()
+}
+
+/// Checks if two addresses are equal regardless of their form.
+pub fn same_address(a: &str, b: &str) -> bool {
+    normalize_address(a) == normalize_address(b)
+}
+
+/// Checks if value is a raw or user-friendly address.
+pub fn is_address(value: &str) -> bool {
+    value.contains(':') || friendly_to_raw(value.trim()).is_some()
+}
+
+/// Converts user-friendly address (base64 or base64url of flags, workchain,
+/// hash and CRC16) to raw form.
+fn friendly_to_raw(addr: &str) -> Option<String> {
+    if addr.len() != 48 {
+        return None;
+    }
+    let bytes = base64::decode(&addr.replace('-', "+").replace('_', "/")).ok()?;
+    if bytes.len() != 36 || crc16(&bytes[..34]).to_be_bytes() != bytes[34..] {
+        return None;
+    }
+    Some(format!("{}:{}", bytes[1] as i8, hex::encode(&bytes[2..34])))
+}
+
+/// CRC16-CCITT (XMODEM) used in user-friendly addresses.
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for byte in data {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// Normalizes hex string (public key or hash) for comparison.
+pub fn normalize_key(key: &str) -> String {
+    key.trim_start_matches("0x").to_lowercase()
+}
+
+/// Returns number of words in seed phrase if it is one of supported lengths,
+/// otherwise default word count.
+pub fn phrase_word_count(phrase: &str) -> u8 {
+    match phrase.split_whitespace().count() {
+        n @ 12 | n @ 15 | n @ 18 | n @ 21 | n @ 24 => n as u8,
+        _ => WORD_COUNT,
+    }
+}
+
+/// Parses amount in nanotokens (decimal or hex). `name` is used in error message.
+pub fn parse_amount(value: &str, name: &str) -> Result<u64, String> {
+    decode_abi_number::<u64>(value)
+        .map_err(|e| format!("invalid \"{}\" value \"{}\": {}", name, value, e))
+}
+
 pub type TonClient = Arc<ClientContext>;
 
 pub fn create_client_local() -> Result<TonClient, String> {
@@ -53,9 +118,9 @@ pub fn create_client(conf: &Config) -> Result<TonClient, String> {
             message_expiration_timeout_grow_factor: 1.3,
         },
         crypto: CryptoConfig {
-            mnemonic_dictionary: 1,
-            mnemonic_word_count: WORD_COUNT,
-            hdkey_derivation_path: HD_PATH.to_string(),
+            mnemonic_dictionary: conf.mnemonic.dictionary,
+            mnemonic_word_count: conf.mnemonic.word_count.unwrap_or(WORD_COUNT),
+            hdkey_derivation_path: conf.mnemonic.hd_path.clone(),
         },
         network: ton_client::net::NetworkConfig {
             server_address: conf.url.clone(),
@@ -0,0 +1,351 @@
+/*
+* Copyright 2018-2021 TON DEV SOLUTIONS LTD.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific TON DEV software governing permissions and
+* limitations under the License.
+*/
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Outgoing HTTP request made by `Network` interface.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HttpRequest {
+    pub method: String,
+    pub url: String,
+    /// Headers in `Name: value` form.
+    pub headers: Vec<String>,
+    pub body: Option<String>,
+}
+
+/// Response returned to DeBot by `Network` interface.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HttpResponse {
+    pub status: i32,
+    /// Headers in `Name: value` form.
+    #[serde(default)]
+    pub headers: Vec<String>,
+    #[serde(default)]
+    pub content: String,
+}
+
+/// HTTP transport used by DeBot Browser. Can be replaced by user.
+#[async_trait::async_trait]
+pub trait HttpClient {
+    async fn fetch(&self, request: HttpRequest) -> Result<HttpResponse, String>;
+}
+
+pub type SharedHttpClient = Arc<RwLock<Box<dyn HttpClient + Send + Sync>>>;
+
+pub fn make_shared_http_client(client: Box<dyn HttpClient + Send + Sync>) -> SharedHttpClient {
+    Arc::new(RwLock::new(client))
+}
+
+/// Returns transport used by default: `fetch` in wasm and `ureq` in native
+/// build. Native build without `native-http` feature has no transport, so it
+/// must be set by user.
+pub fn default_http_client() -> Box<dyn HttpClient + Send + Sync> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        Box::new(FetchHttpClient {})
+    }
+    #[cfg(all(not(target_arch = "wasm32"), feature = "native-http"))]
+    {
+        Box::new(UreqHttpClient::new())
+    }
+    #[cfg(all(not(target_arch = "wasm32"), not(feature = "native-http")))]
+    {
+        Box::new(NoHttpClient {})
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub struct NoHttpClient {}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait::async_trait]
+impl HttpClient for NoHttpClient {
+    async fn fetch(&self, request: HttpRequest) -> Result<HttpResponse, String> {
+        Err(format!("HTTP client is not configured, failed to fetch {}", request.url))
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "native-http"))]
+mod native {
+    use super::{HttpClient, HttpRequest, HttpResponse};
+    use std::time::Duration;
+
+    const TIMEOUT_SEC: u64 = 60;
+
+    /// Splits `Name: value` header.
+    pub(super) fn split_header(header: &str) -> Option<(&str, &str)> {
+        let pos = header.find(':').filter(|pos| *pos > 0)?;
+        Some((header[..pos].trim(), header[pos + 1..].trim()))
+    }
+
+    /// Transport based on blocking `ureq` client.
+    pub struct UreqHttpClient {
+        agent: ureq::Agent,
+    }
+
+    impl UreqHttpClient {
+        pub fn new() -> Self {
+            let agent = ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(TIMEOUT_SEC))
+                .build();
+            Self { agent }
+        }
+    }
+
+    fn send(agent: &ureq::Agent, request: &HttpRequest) -> Result<HttpResponse, String> {
+        let mut req = agent.request(&request.method.to_uppercase(), &request.url);
+        for (name, value) in request.headers.iter().filter_map(|h| split_header(h)) {
+            req = req.set(name, value);
+        }
+        let result = match &request.body {
+            Some(body) => req.send_string(body),
+            None => req.call(),
+        };
+        // responses with error status are returned to DeBot as is
+        let resp = match result {
+            Ok(resp) | Err(ureq::Error::Status(_, resp)) => resp,
+            Err(e) => return Err(format!("failed to fetch {}: {}", request.url, e)),
+        };
+        let status = resp.status() as i32;
+        let headers = resp
+            .headers_names()
+            .iter()
+            .filter_map(|name| resp.header(name).map(|value| format!("{}: {}", name, value)))
+            .collect();
+        let content = resp
+            .into_string()
+            .map_err(|e| format!("failed to read response of {}: {}", request.url, e))?;
+        Ok(HttpResponse { status, headers, content })
+    }
+
+    #[async_trait::async_trait]
+    impl HttpClient for UreqHttpClient {
+        /// Request is sent in blocking thread pool, so the browser keeps
+        /// running (and checking time limit) while waiting for response.
+        async fn fetch(&self, request: HttpRequest) -> Result<HttpResponse, String> {
+            let agent = self.agent.clone();
+            tokio::task::spawn_blocking(move || send(&agent, &request))
+                .await
+                .map_err(|e| format!("HTTP request failed: {}", e))?
+        }
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "native-http"))]
+pub use native::UreqHttpClient;
+
+#[cfg(target_arch = "wasm32")]
+mod fetch {
+    use super::{HttpClient, HttpRequest, HttpResponse};
+    use crate::dapp_signing_box::{JsFutureSync, Promise};
+    use serde_wasm_bindgen::{from_value, to_value};
+    use wasm_bindgen::prelude::*;
+
+    #[wasm_bindgen(inline_js = r#"
+export function debot_fetch(method, url, headers, body) {
+    const init = { method: method, headers: {} };
+    for (const header of headers) {
+        const pos = header.indexOf(':');
+        if (pos > 0) {
+            init.headers[header.slice(0, pos).trim()] = header.slice(pos + 1).trim();
+        }
+    }
+    if (body !== undefined && body !== null) {
+        init.body = body;
+    }
+    return fetch(url, init).then(async (resp) => {
+        const headers = [];
+        resp.headers.forEach((value, name) => headers.push(`${name}: ${value}`));
+        return { status: resp.status, headers: headers, content: await resp.text() };
+    });
+}
+"#)]
+    extern "C" {
+        fn debot_fetch(method: &str, url: &str, headers: JsValue, body: Option<String>) -> Promise;
+    }
+
+    /// Transport based on JavaScript `fetch` function.
+    pub struct FetchHttpClient {}
+
+    #[async_trait::async_trait]
+    impl HttpClient for FetchHttpClient {
+        async fn fetch(&self, request: HttpRequest) -> Result<HttpResponse, String> {
+            let promise = {
+                let headers = to_value(&request.headers).map_err(|e| e.to_string())?;
+                debot_fetch(&request.method, &request.url, headers, request.body.clone())
+            };
+            let value = JsFutureSync::from(promise)
+                .await
+                .map_err(|e| format!("failed to fetch {}: {:?}", request.url, e))?;
+            from_value(value).map_err(|e| format!("invalid fetch response: {}", e))
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub use fetch::FetchHttpClient;
+
+/// Local transport which answers with predefined responses. Used to run
+/// DeBots offline.
+#[derive(Deserialize, Default)]
+pub struct StubHttpClient {
+    /// Map of `"METHOD url"` -> response.
+    routes: HashMap<String, HttpResponse>,
+}
+
+impl StubHttpClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_json(routes: serde_json::Value) -> Result<Self, String> {
+        let routes = serde_json::from_value(routes)
+            .map_err(|e| format!("invalid http stub: {}", e))?;
+        Ok(Self { routes })
+    }
+
+    pub fn add_route(&mut self, method: &str, url: &str, response: HttpResponse) {
+        self.routes.insert(Self::route_key(method, url), response);
+    }
+
+    fn route_key(method: &str, url: &str) -> String {
+        format!("{} {}", method.to_uppercase(), url)
+    }
+
+    fn response(&self, request: &HttpRequest) -> HttpResponse {
+        self.routes
+            .get(&Self::route_key(&request.method, &request.url))
+            .cloned()
+            .unwrap_or(HttpResponse {
+                status: 404,
+                headers: vec![],
+                content: String::new(),
+            })
+    }
+}
+
+#[async_trait::async_trait]
+impl HttpClient for StubHttpClient {
+    async fn fetch(&self, request: HttpRequest) -> Result<HttpResponse, String> {
+        Ok(self.response(&request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request(method: &str, url: &str) -> HttpRequest {
+        HttpRequest {
+            method: method.to_owned(),
+            url: url.to_owned(),
+            headers: vec![],
+            body: None,
+        }
+    }
+
+    #[test]
+    fn test_stub_routes() {
+        let stub = StubHttpClient::from_json(json!({
+            "GET https://example.com/status": {
+                "status": 200,
+                "headers": ["Content-Type: text/plain"],
+                "content": "ok"
+            }
+        }))
+        .unwrap();
+
+        let resp = stub.response(&request("get", "https://example.com/status"));
+        assert_eq!(resp.status, 200);
+        assert_eq!(resp.headers, vec!["Content-Type: text/plain".to_owned()]);
+        assert_eq!(resp.content, "ok");
+
+        let resp = stub.response(&request("POST", "https://example.com/status"));
+        assert_eq!(resp.status, 404);
+
+        let mut stub = StubHttpClient::new();
+        stub.add_route("POST", "https://example.com/send", HttpResponse {
+            status: 201,
+            headers: vec![],
+            content: "created".to_owned(),
+        });
+        let resp = stub.response(&request("POST", "https://example.com/send"));
+        assert_eq!(resp.status, 201);
+        assert_eq!(resp.content, "created");
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), feature = "native-http"))]
+    #[test]
+    fn test_split_header() {
+        use native::split_header;
+        assert_eq!(split_header("Content-Type: text/plain"), Some(("Content-Type", "text/plain")));
+        assert_eq!(split_header("X-Time:1:2"), Some(("X-Time", "1:2")));
+        assert_eq!(split_header(": value"), None);
+        assert_eq!(split_header("no header"), None);
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), feature = "native-http"))]
+    #[tokio::test]
+    async fn test_ureq_client() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/send", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = vec![0u8; 4096];
+            let len = stream.read(&mut request).unwrap();
+            let response = "HTTP/1.1 404 Not Found\r\nX-Test: 1\r\nContent-Length: 7\r\n\r\nmissing";
+            stream.write_all(response.as_bytes()).unwrap();
+            String::from_utf8_lossy(&request[..len]).to_string()
+        });
+
+        let mut req = request("post", &url);
+        req.headers = vec!["X-Debot: test".to_owned()];
+        req.body = Some("hello".to_owned());
+        let resp = UreqHttpClient::new().fetch(req).await.unwrap();
+        assert_eq!(resp.status, 404);
+        assert!(resp.headers.iter().any(|h| h.eq_ignore_ascii_case("x-test: 1")), "{:?}", resp.headers);
+        assert_eq!(resp.content, "missing");
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /send HTTP/1.1"));
+        assert!(request.to_lowercase().contains("x-debot: test"));
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), feature = "native-http"))]
+    #[tokio::test]
+    async fn test_ureq_client_does_not_block() {
+        use crate::loop_guard::with_timeout;
+        use std::net::TcpListener;
+
+        // server accepts connection and never answers
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/slow", listener.local_addr().unwrap());
+        let _server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            std::thread::sleep(std::time::Duration::from_secs(1));
+            drop(stream);
+        });
+
+        let client = UreqHttpClient::new();
+        let started = std::time::Instant::now();
+        let result = with_timeout(Some(100), client.fetch(request("GET", &url))).await;
+        assert!(result.is_none());
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+    }
+}
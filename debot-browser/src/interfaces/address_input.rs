@@ -1,6 +1,6 @@
 use super::dinterface::{decode_answer_id, decode_prompt};
 use crate::helpers::load_ton_address;
-use crate::term_browser::terminal_input;
+use crate::browser_io::{input_valid, SharedBrowserIo};
 use serde_json::{Value, json};
 use ton_client::abi::Abi;
 use ton_client::debot::{DebotInterface, InterfaceResult};
@@ -45,27 +45,29 @@ pub const ABI: &str = r#"
 }
 "#;
 
-pub struct AddressInput;
+pub struct AddressInput {
+    io: SharedBrowserIo,
+}
 
 impl AddressInput {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(io: SharedBrowserIo) -> Self {
+        Self { io }
     }
-    fn get(&self, args: &Value) -> InterfaceResult {
+    async fn get(&self, args: &Value) -> InterfaceResult {
         let answer_id = decode_answer_id(args)?;
         let prompt = decode_prompt(args)?;
-        let value = terminal_input(&prompt, |val| {
+        let value = input_valid(self.io.as_ref(), &prompt, |val| {
             let _ = load_ton_address(val, 0).map_err(|e| format!("Invalid address: {}", e))?;
             Ok(())
-        });
+        }).await?;
         Ok((answer_id, json!({ "value": value })))
     }
-    fn select(&self, args: &Value) -> InterfaceResult {
+    async fn select(&self, args: &Value) -> InterfaceResult {
         let answer_id = decode_answer_id(args)?;
-        let value = terminal_input("", |val| {
+        let value = input_valid(self.io.as_ref(), "", |val| {
             let _ = load_ton_address(val, 0).map_err(|e| format!("Invalid address: {}", e))?;
             Ok(())
-        });
+        }).await?;
         Ok((answer_id, json!({ "value": value })))
     }
 }
@@ -82,8 +84,8 @@ impl DebotInterface for AddressInput {
 
     async fn call(&self, func: &str, args: &Value) -> InterfaceResult {
         match func {
-            "get" => self.get(args),
-            "select" => self.select(args),
+            "get" => self.get(args).await,
+            "select" => self.select(args).await,
             _ => Err(format!("function \"{}\" is not implemented", func)),
         }
     }
@@ -1,6 +1,6 @@
 use super::dinterface::{decode_answer_id, decode_num_arg, decode_prompt};
 use crate::convert;
-use crate::term_browser::terminal_input;
+use crate::browser_io::{input_valid, SharedBrowserIo};
 use serde_json::{Value, json};
 use ton_client::abi::Abi;
 use ton_client::debot::{DebotInterface, InterfaceResult};
@@ -49,13 +49,15 @@ pub const ABI: &str = r#"
 }
 "#;
 
-pub struct AmountInput {}
+pub struct AmountInput {
+    io: SharedBrowserIo,
+}
 
 impl AmountInput {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(io: SharedBrowserIo) -> Self {
+        Self { io }
     }
-    fn get(&self, args: &Value) -> InterfaceResult {
+    async fn get(&self, args: &Value) -> InterfaceResult {
         let answer_id = decode_answer_id(args)?;
         let prompt = decode_prompt(args)?;
         let decimals = decode_num_arg::<usize>(args, "decimals")?;
@@ -72,7 +74,7 @@ impl AmountInput {
             format_amount(min, decimals),
             format_amount(max, decimals)
         );
-        let _ = terminal_input(&prompt, |val| {
+        input_valid(self.io.as_ref(), &prompt, |val| {
             value = convert::convert_amount(val.as_str(), decimals)?;
             let number = decode_abi_number::<u128>(&value)
                 .map_err(|e| format!("input is not a valid amount: {}", e))?;
@@ -80,7 +82,7 @@ impl AmountInput {
                 return Err(format!("amount is out of range"));
             }
             Ok(())
-        });
+        }).await?;
         Ok((answer_id, json!({ "value": value })))
     }
 }
@@ -97,7 +99,7 @@ impl DebotInterface for AmountInput {
 
     async fn call(&self, func: &str, args: &Value) -> InterfaceResult {
         match func {
-            "get" => self.get(args),
+            "get" => self.get(args).await,
             _ => Err(format!("function \"{}\" is not implemented", func)),
         }
     }
@@ -0,0 +1,137 @@
+/*
+* Copyright 2018-2021 TON DEV SOLUTIONS LTD.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific TON DEV software governing permissions and
+* limitations under the License.
+*/
+use super::dinterface::{decode_answer_id, decode_arg, decode_string_arg};
+use serde_json::{Value, json};
+use ton_client::abi::Abi;
+use ton_client::debot::{DebotInterface, InterfaceResult};
+
+const ID: &'static str = "8913b27b45267aad3ee08437e64029ac38fb59274f19adca0b23c4f957c8cfa1";
+
+const ABI: &str = r#"
+{
+    "ABI version": 2,
+    "version": "2.2",
+    "header": ["time"],
+    "functions": [
+        {
+            "name": "encode",
+            "id": "0x31d9f12c",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"data","type":"bytes"}
+            ],
+            "outputs": [
+                {"name":"base64","type":"string"}
+            ]
+        },
+        {
+            "name": "decode",
+            "id": "0x5992a05b",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"base64","type":"string"}
+            ],
+            "outputs": [
+                {"name":"data","type":"bytes"}
+            ]
+        },
+        {
+            "name": "constructor",
+            "id": "0x68b55f3f",
+            "inputs": [
+            ],
+            "outputs": [
+            ]
+        }
+    ],
+    "data": [
+    ],
+    "events": [
+    ],
+    "fields": [
+        {"name":"_pubkey","type":"uint256"},
+        {"name":"_timestamp","type":"uint64"},
+        {"name":"_constructorFlag","type":"bool"}
+    ]
+}
+"#;
+
+pub struct Base64Interface {}
+
+impl Base64Interface {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    fn encode(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let data = decode_arg(args, "data")?;
+        let data = hex::decode(&data).map_err(|e| format!("invalid bytes: {}", e))?;
+        Ok((answer_id, json!({ "base64": base64::encode(&data) })))
+    }
+
+    fn decode(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let base64_str = decode_string_arg(args, "base64")?;
+        let data = base64::decode(&base64_str)
+            .map_err(|e| format!("invalid base64 string: {}", e))?;
+        Ok((answer_id, json!({ "data": hex::encode(&data) })))
+    }
+}
+
+#[async_trait::async_trait]
+impl DebotInterface for Base64Interface {
+    fn get_id(&self) -> String {
+        ID.to_string()
+    }
+
+    fn get_abi(&self) -> Abi {
+        Abi::Json(ABI.to_owned())
+    }
+
+    async fn call(&self, func: &str, args: &Value) -> InterfaceResult {
+        match func {
+            "encode" => self.encode(args),
+            "decode" => self.decode(args),
+            _ => Err(format!("function \"{}\" is not implemented", func)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        let iface = Base64Interface::new();
+        let args = json!({ "answerId": "21", "data": hex::encode("hello world") });
+        let (answer_id, result) = iface.encode(&args).unwrap();
+        assert_eq!(answer_id, 21);
+        assert_eq!(result["base64"], "aGVsbG8gd29ybGQ=");
+
+        let args = json!({ "answerId": "21", "data": "" });
+        assert_eq!(iface.encode(&args).unwrap().1["base64"], "");
+    }
+
+    #[test]
+    fn test_decode() {
+        let iface = Base64Interface::new();
+        let args = json!({ "answerId": "22", "base64": "aGVsbG8gd29ybGQ=" });
+        let (answer_id, result) = iface.decode(&args).unwrap();
+        assert_eq!(answer_id, 22);
+        assert_eq!(result["data"], hex::encode("hello world"));
+
+        assert!(iface.decode(&json!({ "answerId": "22", "base64": "%%%" })).is_err());
+    }
+}
@@ -1,5 +1,5 @@
 use super::dinterface::{decode_answer_id, decode_prompt};
-use crate::term_browser::terminal_input;
+use crate::browser_io::SharedBrowserIo;
 use serde_json::{Value, json};
 use ton_client::abi::Abi;
 use ton_client::debot::{DebotInterface, InterfaceResult};
@@ -44,24 +44,18 @@ pub const ABI: &str = r#"
 }
 "#;
 
-pub struct ConfirmInput {}
+pub struct ConfirmInput {
+    io: SharedBrowserIo,
+}
 
 impl ConfirmInput {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(io: SharedBrowserIo) -> Self {
+        Self { io }
     }
-    fn get(&self, args: &Value) -> InterfaceResult {
+    async fn get(&self, args: &Value) -> InterfaceResult {
         let answer_id = decode_answer_id(args)?;
         let prompt = decode_prompt(args)?;
-        let mut yes_no = false;
-        let _ = terminal_input(&format!("{} (y/n)", prompt), |val| {
-            yes_no = match val.as_str() {
-                "y" => true,
-                "n" => false,
-                _ => Err(format!("invalid enter"))?,
-            };
-            Ok(())
-        });
+        let yes_no = self.io.confirm(&prompt).await?;
         Ok((answer_id, json!({ "value": yes_no })))
     }
 }
@@ -78,7 +72,7 @@ impl DebotInterface for ConfirmInput {
 
     async fn call(&self, func: &str, args: &Value) -> InterfaceResult {
         match func {
-            "get" => self.get(args),
+            "get" => self.get(args).await,
             _ => Err(format!("function \"{}\" is not implemented", func)),
         }
     }
@@ -0,0 +1,142 @@
+use super::dinterface::{decode_answer_id, decode_array, decode_prompt};
+use crate::browser_io::{input_valid, SharedBrowserIo};
+use serde_json::{Value, json};
+use ton_client::abi::Abi;
+use ton_client::debot::{DebotInterface, InterfaceResult};
+
+const ID: &'static str = "be1b29b4d90bba61d78edeee85ee8794e65f92c94d58b9c33abc0582af6cecf6";
+
+pub const ABI: &str = r#"
+{
+    "ABI version": 2,
+    "version": "2.2",
+    "header": ["time"],
+    "functions": [
+        {
+            "name": "get",
+            "id": "0x3645c6ef",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"prompt","type":"string"},
+                {"name":"permitted","type":"string[]"},
+                {"name":"banned","type":"string[]"}
+            ],
+            "outputs": [
+                {"name":"country","type":"string"}
+            ]
+        },
+        {
+            "name": "constructor",
+            "id": "0x68b55f3f",
+            "inputs": [
+            ],
+            "outputs": [
+            ]
+        }
+    ],
+    "data": [
+    ],
+    "events": [
+    ],
+    "fields": [
+        {"name":"_pubkey","type":"uint256"},
+        {"name":"_timestamp","type":"uint64"},
+        {"name":"_constructorFlag","type":"bool"}
+    ]
+}
+"#;
+
+/// ISO 3166-1 alpha-2 country codes.
+const COUNTRY_CODES: &str = "\
+    AD AE AF AG AI AL AM AO AQ AR AS AT AU AW AX AZ BA BB BD BE BF BG BH BI BJ BL BM BN BO BQ \
+    BR BS BT BV BW BY BZ CA CC CD CF CG CH CI CK CL CM CN CO CR CU CV CW CX CY CZ DE DJ DK DM \
+    DO DZ EC EE EG EH ER ES ET FI FJ FK FM FO FR GA GB GD GE GF GG GH GI GL GM GN GP GQ GR GS \
+    GT GU GW GY HK HM HN HR HT HU ID IE IL IM IN IO IQ IR IS IT JE JM JO JP KE KG KH KI KM KN \
+    KP KR KW KY KZ LA LB LC LI LK LR LS LT LU LV LY MA MC MD ME MF MG MH MK ML MM MN MO MP MQ \
+    MR MS MT MU MV MW MX MY MZ NA NC NE NF NG NI NL NO NP NR NU NZ OM PA PE PF PG PH PK PL PM \
+    PN PR PS PT PW PY QA RE RO RS RU RW SA SB SC SD SE SG SH SI SJ SK SL SM SN SO SR SS ST SV \
+    SX SY SZ TC TD TF TG TH TJ TK TL TM TN TO TR TT TV TW TZ UA UG UM US UY UZ VA VC VE VG VI \
+    VN VU WF WS YE YT ZA ZM ZW";
+
+pub struct CountryInput {
+    io: SharedBrowserIo,
+}
+
+impl CountryInput {
+    pub fn new(io: SharedBrowserIo) -> Self {
+        Self { io }
+    }
+    async fn get(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let prompt = decode_prompt(args)?;
+        let permitted = decode_codes(args, "permitted")?;
+        let banned = decode_codes(args, "banned")?;
+        let prompt = if permitted.is_empty() {
+            format!("{}\n(ISO 3166-1 alpha-2 country code)", prompt)
+        } else {
+            format!("{}\n(one of: {})", prompt, permitted.join(", "))
+        };
+        let mut country = String::new();
+        input_valid(self.io.as_ref(), &prompt, |val| {
+            country = check_country(val, &permitted, &banned)?;
+            Ok(())
+        }).await?;
+        Ok((answer_id, json!({ "country": country })))
+    }
+}
+
+#[async_trait::async_trait]
+impl DebotInterface for CountryInput {
+    fn get_id(&self) -> String {
+        ID.to_string()
+    }
+
+    fn get_abi(&self) -> Abi {
+        Abi::Json(ABI.to_owned())
+    }
+
+    async fn call(&self, func: &str, args: &Value) -> InterfaceResult {
+        match func {
+            "get" => self.get(args).await,
+            _ => Err(format!("function \"{}\" is not implemented", func)),
+        }
+    }
+}
+
+fn decode_codes(args: &Value, name: &str) -> Result<Vec<String>, String> {
+    decode_array(args, name, |elem| elem.as_str().map(|x| x.to_uppercase()))
+}
+
+fn check_country(value: &str, permitted: &[String], banned: &[String]) -> Result<String, String> {
+    let code = value.trim().to_uppercase();
+    if code.len() != 2 || !COUNTRY_CODES.split_whitespace().any(|x| x == code) {
+        return Err(format!("unknown country code"));
+    }
+    if !permitted.is_empty() && !permitted.contains(&code) {
+        return Err(format!("country is not permitted"));
+    }
+    if banned.contains(&code) {
+        return Err(format!("country is banned"));
+    }
+    Ok(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_country() {
+        assert_eq!(COUNTRY_CODES.split_whitespace().count(), 249);
+        assert_eq!(check_country("de", &[], &[]).unwrap(), "DE");
+        assert!(check_country("XX", &[], &[]).is_err());
+        assert!(check_country("DEU", &[], &[]).is_err());
+
+        let permitted = vec!["FR".to_owned(), "DE".to_owned()];
+        let banned = vec!["DE".to_owned()];
+        assert_eq!(check_country(" fr ", &permitted, &banned).unwrap(), "FR");
+        assert!(check_country("DE", &permitted, &banned).is_err());
+        assert!(check_country("IT", &permitted, &banned).is_err());
+        assert!(check_country("IT", &[], &banned).is_ok());
+    }
+}
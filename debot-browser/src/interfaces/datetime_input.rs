@@ -0,0 +1,398 @@
+use super::dinterface::{decode_answer_id, decode_num_arg, decode_prompt};
+use crate::browser_io::{input_valid, SharedBrowserIo};
+use serde_json::{Value, json};
+use ton_client::abi::Abi;
+use ton_client::debot::{DebotInterface, InterfaceResult};
+
+pub const ID: &'static str = "f5a0580d4ecf5d9195f0552a05fb936ada04b9a04351c1c543588fb77cc68801";
+
+pub const ABI: &str = r#"
+{
+    "ABI version": 2,
+    "version": "2.2",
+    "header": ["time"],
+    "functions": [
+        {
+            "name": "getDate",
+            "id": "0x665d8a24",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"prompt","type":"string"},
+                {"name":"defaultDate","type":"int128"},
+                {"name":"minDate","type":"int128"},
+                {"name":"maxDate","type":"int128"}
+            ],
+            "outputs": [
+                {"name":"date","type":"int128"}
+            ]
+        },
+        {
+            "name": "getTime",
+            "id": "0x30a754c2",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"prompt","type":"string"},
+                {"name":"defaultTime","type":"uint32"},
+                {"name":"minTime","type":"uint32"},
+                {"name":"maxTime","type":"uint32"},
+                {"name":"minuteInterval","type":"uint8"}
+            ],
+            "outputs": [
+                {"name":"time","type":"uint32"}
+            ]
+        },
+        {
+            "name": "getDateTime",
+            "id": "0x7c70a486",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"prompt","type":"string"},
+                {"name":"defaultDatetime","type":"int128"},
+                {"name":"minDatetime","type":"int128"},
+                {"name":"maxDatetime","type":"int128"},
+                {"name":"minuteInterval","type":"uint8"},
+                {"name":"inTimeZoneOffset","type":"int16"}
+            ],
+            "outputs": [
+                {"name":"datetime","type":"int128"},
+                {"name":"timeZoneOffset","type":"int16"}
+            ]
+        },
+        {
+            "name": "getTimeZoneOffset",
+            "id": "0x02fa0b4e",
+            "inputs": [
+                {"name":"answerId","type":"uint32"}
+            ],
+            "outputs": [
+                {"name":"timeZoneOffset","type":"int16"}
+            ]
+        },
+        {
+            "name": "constructor",
+            "id": "0x68b55f3f",
+            "inputs": [
+            ],
+            "outputs": [
+            ]
+        }
+    ],
+    "data": [
+    ],
+    "events": [
+    ],
+    "fields": [
+        {"name":"_pubkey","type":"uint256"},
+        {"name":"_timestamp","type":"uint64"},
+        {"name":"_constructorFlag","type":"bool"}
+    ]
+}
+"#;
+
+const SECONDS_IN_DAY: i64 = 86400;
+const MIN_TZ_OFFSET: i16 = -720;
+const MAX_TZ_OFFSET: i16 = 840;
+
+pub struct DateTimeInput {
+    io: SharedBrowserIo,
+}
+
+impl DateTimeInput {
+    pub fn new(io: SharedBrowserIo) -> Self {
+        Self { io }
+    }
+
+    async fn get_date(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let prompt = decode_prompt(args)?;
+        let default = decode_num_arg::<i64>(args, "defaultDate")?;
+        let min = decode_num_arg::<i64>(args, "minDate")?;
+        let max = decode_num_arg::<i64>(args, "maxDate")?;
+        if min > max {
+            return Err(format!("invalid date range"));
+        }
+        let prompt = format!(
+            "{}\n(YYYY-MM-DD, >= {} and <= {}, default {})",
+            prompt, format_date(min), format_date(max), format_date(default),
+        );
+        let mut date = default;
+        input_valid(self.io.as_ref(), &prompt, |val| {
+            date = if val.is_empty() { default } else { parse_date(val)? };
+            check_range(date, min, max, "date")
+        }).await?;
+        Ok((answer_id, json!({ "date": date.to_string() })))
+    }
+
+    async fn get_time(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let prompt = decode_prompt(args)?;
+        let default = decode_num_arg::<i64>(args, "defaultTime")?;
+        let min = decode_num_arg::<i64>(args, "minTime")?;
+        let max = decode_num_arg::<i64>(args, "maxTime")?;
+        let interval = decode_num_arg::<i64>(args, "minuteInterval")?;
+        if min > max || max >= SECONDS_IN_DAY {
+            return Err(format!("invalid time range"));
+        }
+        let prompt = format!(
+            "{}\n(HH:MM, >= {} and <= {}, default {})",
+            prompt, format_time(min), format_time(max), format_time(default),
+        );
+        let mut time = default;
+        input_valid(self.io.as_ref(), &prompt, |val| {
+            time = if val.is_empty() { default } else { parse_time(val)? };
+            check_range(time, min, max, "time")?;
+            check_interval(time, interval)
+        }).await?;
+        Ok((answer_id, json!({ "time": time.to_string() })))
+    }
+
+    async fn get_date_time(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let prompt = decode_prompt(args)?;
+        let default = decode_num_arg::<i64>(args, "defaultDatetime")?;
+        let min = decode_num_arg::<i64>(args, "minDatetime")?;
+        let max = decode_num_arg::<i64>(args, "maxDatetime")?;
+        let interval = decode_num_arg::<i64>(args, "minuteInterval")?;
+        let in_offset = decode_num_arg::<i16>(args, "inTimeZoneOffset")?;
+        check_tz_offset(in_offset)?;
+        if min > max {
+            return Err(format!("invalid datetime range"));
+        }
+        let prompt = format!(
+            "{}\n(YYYY-MM-DD HH:MM [+HH:MM], >= {} and <= {}, default {})",
+            prompt,
+            format_date_time(min, in_offset),
+            format_date_time(max, in_offset),
+            format_date_time(default, in_offset),
+        );
+        let mut datetime = (default, in_offset);
+        input_valid(self.io.as_ref(), &prompt, |val| {
+            datetime = if val.is_empty() {
+                (default, in_offset)
+            } else {
+                parse_date_time(val, in_offset)?
+            };
+            check_range(datetime.0, min, max, "datetime")?;
+            check_interval(datetime.0, interval)
+        }).await?;
+        Ok((answer_id, json!({
+            "datetime": datetime.0.to_string(),
+            "timeZoneOffset": datetime.1.to_string(),
+        })))
+    }
+
+    fn get_time_zone_offset(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        Ok((answer_id, json!({ "timeZoneOffset": local_tz_offset().to_string() })))
+    }
+}
+
+#[async_trait::async_trait]
+impl DebotInterface for DateTimeInput {
+    fn get_id(&self) -> String {
+        ID.to_string()
+    }
+
+    fn get_abi(&self) -> Abi {
+        Abi::Json(ABI.to_owned())
+    }
+
+    async fn call(&self, func: &str, args: &Value) -> InterfaceResult {
+        match func {
+            "getDate" => self.get_date(args).await,
+            "getTime" => self.get_time(args).await,
+            "getDateTime" => self.get_date_time(args).await,
+            "getTimeZoneOffset" => self.get_time_zone_offset(args),
+            _ => Err(format!("function \"{}\" is not implemented", func)),
+        }
+    }
+}
+
+/// Time zone offset of the local machine in minutes (east of UTC).
+#[cfg(target_arch = "wasm32")]
+fn local_tz_offset() -> i16 {
+    -(js_sys::Date::new_0().get_timezone_offset() as i16)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn local_tz_offset() -> i16 {
+    (chrono::Local::now().offset().local_minus_utc() / 60) as i16
+}
+
+fn check_range(value: i64, min: i64, max: i64, name: &str) -> Result<(), String> {
+    if value < min || value > max {
+        return Err(format!("{} is out of range", name));
+    }
+    Ok(())
+}
+
+fn check_interval(seconds: i64, interval: i64) -> Result<(), String> {
+    if interval > 1 && (seconds / 60) % interval != 0 {
+        return Err(format!("minutes must be a multiple of {}", interval));
+    }
+    Ok(())
+}
+
+fn check_tz_offset(offset: i16) -> Result<(), String> {
+    if offset < MIN_TZ_OFFSET || offset > MAX_TZ_OFFSET {
+        return Err(format!("invalid time zone offset {}", offset));
+    }
+    Ok(())
+}
+
+/// Number of days since 1970-01-01 for the given civil date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let yoe = year - era * 400;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Civil date (year, month, day) for the number of days since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = if days >= 0 { days } else { days - 146096 } / 146097;
+    let doe = days - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    let next = if month == 12 { days_from_civil(year + 1, 1, 1) } else { days_from_civil(year, month + 1, 1) };
+    next - days_from_civil(year, month, 1)
+}
+
+fn parse_number(s: &str, what: &str) -> Result<i64, String> {
+    s.trim().parse::<i64>().map_err(|_| format!("invalid {}", what))
+}
+
+/// Parses `YYYY-MM-DD` into unix time of the day start (UTC).
+fn parse_date(s: &str) -> Result<i64, String> {
+    let parts: Vec<&str> = s.trim().split('-').collect();
+    if parts.len() != 3 {
+        return Err(format!("date must be in YYYY-MM-DD format"));
+    }
+    let year = parse_number(parts[0], "year")?;
+    let month = parse_number(parts[1], "month")?;
+    if month < 1 || month > 12 {
+        return Err(format!("invalid month"));
+    }
+    let day = parse_number(parts[2], "day")?;
+    if day < 1 || day > days_in_month(year, month) {
+        return Err(format!("invalid day"));
+    }
+    Ok(days_from_civil(year, month, day) * SECONDS_IN_DAY)
+}
+
+/// Parses `HH:MM` into seconds since midnight.
+fn parse_time(s: &str) -> Result<i64, String> {
+    let parts: Vec<&str> = s.trim().split(':').collect();
+    if parts.len() != 2 {
+        return Err(format!("time must be in HH:MM format"));
+    }
+    let hours = parse_number(parts[0], "hours")?;
+    let minutes = parse_number(parts[1], "minutes")?;
+    if hours < 0 || hours > 23 || minutes < 0 || minutes > 59 {
+        return Err(format!("invalid time"));
+    }
+    Ok(hours * 3600 + minutes * 60)
+}
+
+/// Parses `+HH:MM` or `-HH:MM` into offset in minutes.
+fn parse_tz_offset(s: &str) -> Result<i16, String> {
+    let (sign, value) = match s.chars().next() {
+        Some('+') => (1, &s[1..]),
+        Some('-') => (-1, &s[1..]),
+        _ => return Err(format!("time zone must be in +HH:MM format")),
+    };
+    let minutes = parse_time(value).map_err(|_| format!("invalid time zone"))? / 60;
+    let offset = (sign * minutes) as i16;
+    check_tz_offset(offset)?;
+    Ok(offset)
+}
+
+/// Parses `YYYY-MM-DD HH:MM [+HH:MM]` into UTC unix time and time zone
+/// offset in minutes. If zone is omitted then `default_offset` is used.
+fn parse_date_time(s: &str, default_offset: i16) -> Result<(i64, i16), String> {
+    let parts: Vec<&str> = s.split_whitespace().collect();
+    let offset = match parts.len() {
+        2 => default_offset,
+        3 => parse_tz_offset(parts[2])?,
+        _ => return Err(format!("datetime must be in YYYY-MM-DD HH:MM format")),
+    };
+    let local = parse_date(parts[0])? + parse_time(parts[1])?;
+    Ok((local - offset as i64 * 60, offset))
+}
+
+fn format_date(timestamp: i64) -> String {
+    let (year, month, day) = civil_from_days(timestamp.div_euclid(SECONDS_IN_DAY));
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn format_time(seconds: i64) -> String {
+    let seconds = seconds.rem_euclid(SECONDS_IN_DAY);
+    format!("{:02}:{:02}", seconds / 3600, seconds % 3600 / 60)
+}
+
+fn format_date_time(timestamp: i64, offset: i16) -> String {
+    let local = timestamp + offset as i64 * 60;
+    let sign = if offset < 0 { '-' } else { '+' };
+    let offset = (offset as i64).abs() * 60;
+    format!("{} {} {}{}", format_date(local), format_time(local), sign, format_time(offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("1970-01-01").unwrap(), 0);
+        assert_eq!(parse_date("2021-12-31").unwrap(), 1640908800);
+        assert_eq!(parse_date("2024-02-29").unwrap(), 1709164800);
+        assert_eq!(parse_date("1969-12-31").unwrap(), -SECONDS_IN_DAY);
+        assert!(parse_date("2023-02-29").is_err());
+        assert!(parse_date("2023-13-01").is_err());
+        assert!(parse_date("2023-00-10").is_err());
+        assert!(parse_date("20230110").is_err());
+
+        assert_eq!(format_date(1709164800), "2024-02-29");
+        assert_eq!(format_date(-1), "1969-12-31");
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("00:00").unwrap(), 0);
+        assert_eq!(parse_time("23:59").unwrap(), 86340);
+        assert!(parse_time("24:00").is_err());
+        assert!(parse_time("12:60").is_err());
+        assert!(parse_time("12").is_err());
+        assert_eq!(format_time(45000), "12:30");
+
+        assert!(check_interval(parse_time("12:30").unwrap(), 15).is_ok());
+        assert!(check_interval(parse_time("12:35").unwrap(), 15).is_err());
+        assert!(check_interval(parse_time("12:35").unwrap(), 0).is_ok());
+    }
+
+    #[test]
+    fn test_parse_date_time() {
+        assert_eq!(parse_date_time("2021-12-31 12:30", 0).unwrap(), (1640953800, 0));
+        assert_eq!(parse_date_time("2021-12-31 15:30", 180).unwrap(), (1640953800, 180));
+        assert_eq!(parse_date_time("2021-12-31 07:00 -05:30", 180).unwrap(), (1640953800, -330));
+        assert!(parse_date_time("2021-12-31 07:00 +15:00", 0).is_err());
+        assert!(parse_date_time("2021-12-31 07:00 05:00", 0).is_err());
+        assert!(parse_date_time("2021-12-31", 0).is_err());
+
+        assert_eq!(format_date_time(1640953800, -330), "2021-12-31 07:00 -05:30");
+        assert!(check_tz_offset(-720).is_ok());
+        assert!(check_tz_offset(841).is_err());
+        assert!(check_tz_offset(local_tz_offset()).is_ok());
+    }
+}
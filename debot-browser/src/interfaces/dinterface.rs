@@ -1,11 +1,14 @@
 use super::echo::Echo;
 use super::stdout::Stdout;
 use super::{
-    AddressInput, AmountInput, ConfirmInput, EncryptionBoxInput, InputInterface, Menu, 
-    NumberInput, SigningBoxInput, Terminal, UserInfo
+    AddressInput, AmountInput, Base64Interface, ConfirmInput, CountryInput, DateTimeInput,
+    EncryptionBoxInput, HexInterface, InputInterface, JsonDeserialize, Media, Menu, Network,
+    NumberInput, Query, SdkInterface, SigningBoxInput, Terminal, UserInfo
 };
+use crate::browser_io::SharedBrowserIo;
 use crate::config::SharedUserSettings;
 use crate::helpers::TonClient;
+use crate::http_client::SharedHttpClient;
 use crate::ChainProcessor;
 use num_bigint::BigInt;
 use num_traits::cast::NumCast;
@@ -17,9 +20,13 @@ use tokio::sync::RwLock;
 use ton_client::debot::{DebotInterface, DebotInterfaceExecutor};
 use ton_client::encoding::{decode_abi_bigint, decode_abi_number};
 
+/// ABI of the DeBot which is calling interface now.
+pub type CallerAbi = Arc<std::sync::RwLock<Option<String>>>;
+
 pub struct SupportedInterfaces {
     client: TonClient,
     interfaces: HashMap<String, Arc<dyn DebotInterface + Send + Sync>>,
+    caller_abi: CallerAbi,
 }
 
 #[async_trait::async_trait]
@@ -35,72 +42,127 @@ impl DebotInterfaceExecutor for SupportedInterfaces {
 /// Helper struct used only inside SupportedInterfaces.
 struct InterfaceWrapper {
     processor: Arc<RwLock<ChainProcessor>>,
+    printer: Printer,
 }
 impl InterfaceWrapper {
     fn wrap(
         &self,
         iface: Arc<dyn DebotInterface + Send + Sync>,
     ) -> Arc<dyn DebotInterface + Send + Sync> {
-        Arc::new(InputInterface::new(iface, self.processor.clone()))
+        Arc::new(InputInterface::new(iface, self.processor.clone(), self.printer.clone()))
     }
 }
 
 impl SupportedInterfaces {
-    pub fn new(client: TonClient, settings: SharedUserSettings, processor: Arc<RwLock<ChainProcessor>>) -> Self {
+    pub fn new(
+        client: TonClient,
+        settings: SharedUserSettings,
+        processor: Arc<RwLock<ChainProcessor>>,
+        http: SharedHttpClient,
+        io: SharedBrowserIo,
+    ) -> Self {
         let mut interfaces = HashMap::new();
+        let caller_abi: CallerAbi = Arc::new(std::sync::RwLock::new(None));
 
-        let iw = InterfaceWrapper { processor: processor.clone() };
+        let printer = Printer::new(processor.clone(), io.clone());
+        let iw = InterfaceWrapper { processor: processor.clone(), printer: printer.clone() };
 
         let iface: Arc<dyn DebotInterface + Send + Sync> =
-            iw.wrap(Arc::new(AddressInput::new()));
+            iw.wrap(Arc::new(AddressInput::new(io.clone())));
+        interfaces.insert(iface.get_id(), iface);
+
+        let iface: Arc<dyn DebotInterface + Send + Sync> = iw.wrap(Arc::new(AmountInput::new(io.clone())));
+        interfaces.insert(iface.get_id(), iface);
+
+        let iface: Arc<dyn DebotInterface + Send + Sync> = iw.wrap(Arc::new(NumberInput::new(io.clone())));
+        interfaces.insert(iface.get_id(), iface);
+
+        let iface: Arc<dyn DebotInterface + Send + Sync> = iw.wrap(Arc::new(ConfirmInput::new(io.clone())));
         interfaces.insert(iface.get_id(), iface);
 
-        let iface: Arc<dyn DebotInterface + Send + Sync> = iw.wrap(Arc::new(AmountInput::new()));
+        let iface: Arc<dyn DebotInterface + Send + Sync> = iw.wrap(Arc::new(DateTimeInput::new(io.clone())));
         interfaces.insert(iface.get_id(), iface);
 
-        let iface: Arc<dyn DebotInterface + Send + Sync> = iw.wrap(Arc::new(NumberInput::new()));
+        let iface: Arc<dyn DebotInterface + Send + Sync> = iw.wrap(Arc::new(CountryInput::new(io.clone())));
         interfaces.insert(iface.get_id(), iface);
 
-        let iface: Arc<dyn DebotInterface + Send + Sync> = iw.wrap(Arc::new(ConfirmInput::new()));
+        let iface: Arc<dyn DebotInterface + Send + Sync> =
+            iw.wrap(Arc::new(Media::new(printer.clone())));
         interfaces.insert(iface.get_id(), iface);
 
-        let iface: Arc<dyn DebotInterface + Send + Sync> = Arc::new(Stdout::new());
+        let iface: Arc<dyn DebotInterface + Send + Sync> = Arc::new(Stdout::new(io.clone()));
         interfaces.insert(iface.get_id(), iface);
 
         let iface: Arc<dyn DebotInterface + Send + Sync> = Arc::new(Echo::new());
         interfaces.insert(iface.get_id(), iface);
 
+        let iface: Arc<dyn DebotInterface + Send + Sync> = Arc::new(HexInterface::new());
+        interfaces.insert(iface.get_id(), iface);
+
+        let iface: Arc<dyn DebotInterface + Send + Sync> = Arc::new(Base64Interface::new());
+        interfaces.insert(iface.get_id(), iface);
+
         let iface: Arc<dyn DebotInterface + Send + Sync> =
-            iw.wrap(Arc::new(Terminal::new(Printer {processor})));
+            Arc::new(JsonDeserialize::new(client.clone(), caller_abi.clone()));
         interfaces.insert(iface.get_id(), iface);
 
-        let iface: Arc<dyn DebotInterface + Send + Sync> = iw.wrap(Arc::new(Menu::new()));
+        let iface: Arc<dyn DebotInterface + Send + Sync> = Arc::new(SdkInterface::new(client.clone()));
+        interfaces.insert(iface.get_id(), iface);
+
+        let iface: Arc<dyn DebotInterface + Send + Sync> =
+            Arc::new(Query::new(client.clone(), iw.processor.clone()));
+        interfaces.insert(iface.get_id(), iface);
+
+        let iface: Arc<dyn DebotInterface + Send + Sync> =
+            iw.wrap(Arc::new(Terminal::new(client.clone(), printer, io.clone())));
+        interfaces.insert(iface.get_id(), iface);
+
+        let iface: Arc<dyn DebotInterface + Send + Sync> = iw.wrap(Arc::new(Menu::new(io.clone())));
         interfaces.insert(iface.get_id(), iface);
 
         let iface: Arc<dyn DebotInterface + Send + Sync> = Arc::new(
-            SigningBoxInput::new(client.clone(), iw.processor.clone())
+            SigningBoxInput::new(client.clone(), iw.processor.clone(), io.clone(), settings.clone())
         );
         interfaces.insert(iface.get_id(), iface);
 
         let iface: Arc<dyn DebotInterface + Send + Sync> = 
-            Arc::new(UserInfo::new(client.clone(), settings));
+            Arc::new(UserInfo::new(client.clone(), settings.clone(), io.clone()));
         interfaces.insert(iface.get_id(), iface);
 
         let iface: Arc<dyn DebotInterface + Send + Sync> =
-            Arc::new(EncryptionBoxInput::new(client.clone()));
+            Arc::new(EncryptionBoxInput::new(client.clone(), io.clone(), settings, iw.processor.clone()));
         interfaces.insert(iface.get_id(), iface);
 
-        Self { client, interfaces }
+        let iface: Arc<dyn DebotInterface + Send + Sync> =
+            Arc::new(Network::new(http, iw.processor.clone(), io));
+        interfaces.insert(iface.get_id(), iface);
+
+        Self { client, interfaces, caller_abi }
+    }
+
+    /// Sets ABI of the DeBot whose message is going to be executed.
+    pub fn set_caller_abi(&self, abi: Option<String>) {
+        *self.caller_abi.write().unwrap() = abi;
     }
 }
 
+/// Prints messages to user only if browser is interactive.
+#[derive(Clone)]
 pub struct Printer {
     processor: Arc<RwLock<ChainProcessor>>,
+    io: SharedBrowserIo,
 }
 
 impl Printer {
+    pub fn new(processor: Arc<RwLock<ChainProcessor>>, io: SharedBrowserIo) -> Self {
+        Self { processor, io }
+    }
+
     pub async fn print(&self, msg: &str) {
-        self.processor.read().await.print(msg);
+        let interactive = self.processor.read().await.interactive();
+        if interactive {
+            self.io.print(msg).await;
+        }
     }
 }
 
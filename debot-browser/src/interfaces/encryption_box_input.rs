@@ -2,10 +2,18 @@ use super::dinterface::{decode_answer_id, decode_nonce, decode_prompt, decode_ar
 use crate::term_encryption_box::{
     EncryptionBoxType, ParamsOfTerminalEncryptionBox, TerminalEncryptionBox,
 };
-use crate::helpers::TonClient;
+use crate::browser_io::SharedBrowserIo;
+use crate::config::SharedUserSettings;
+use crate::helpers::{normalize_key, TonClient};
+use crate::processor::ChainEncryptionBox;
+use crate::{ChainProcessor, ProcessorError};
 use serde_json::{json, Value};
+use std::sync::Arc;
 use tokio::sync::RwLock;
-use ton_client::{abi::Abi, crypto::EncryptionBoxHandle};
+use ton_client::abi::Abi;
+use ton_client::crypto::{
+    encryption_box_get_info, EncryptionBoxHandle, EncryptionBoxInfo, ParamsOfEncryptionBoxGetInfo,
+};
 use ton_client::debot::{DebotInterface, InterfaceResult};
 
 const ID: &'static str = "5b5f76b54d976d72f1ada3063d1af2e5352edaf1ba86b3b311170d4d81056d61";
@@ -98,13 +106,24 @@ const ABI: &str = r#"
 pub struct EncryptionBoxInput {
     handles: RwLock<Vec<TerminalEncryptionBox>>,
     client: TonClient,
+    io: SharedBrowserIo,
+    settings: SharedUserSettings,
+    processor: Arc<RwLock<ChainProcessor>>,
 }
 
 impl EncryptionBoxInput {
-    pub fn new(client: TonClient) -> Self {
+    pub fn new(
+        client: TonClient,
+        io: SharedBrowserIo,
+        settings: SharedUserSettings,
+        processor: Arc<RwLock<ChainProcessor>>,
+    ) -> Self {
         Self {
             handles: RwLock::new(vec![]),
             client: client,
+            io,
+            settings,
+            processor,
         }
     }
 
@@ -113,42 +132,88 @@ impl EncryptionBoxInput {
         let prompt = decode_prompt(args)?;
         let nonce = decode_nonce(args)?;
         let their_pubkey = decode_arg(args, "theirPubkey")?;
-        println!("{}", prompt);
-        let result = TerminalEncryptionBox::new(ParamsOfTerminalEncryptionBox {
-            context: self.client.clone(),
-            box_type: EncryptionBoxType::NaCl,
-            their_pubkey: their_pubkey,
-            nonce: nonce,
-        }).await;
-        Ok((answer_id, json!({ "handle": self.insert_box(result).await.0 })))
+        self.io.print(&prompt).await;
+        let handle = self.get_box(EncryptionBoxType::NaCl, their_pubkey, nonce).await?;
+        Ok((answer_id, json!({ "handle": handle })))
     }
     async fn get_nacl_secret_box(&self, args: &Value) -> InterfaceResult {
         let answer_id = decode_answer_id(args)?;
         let prompt = decode_prompt(args)?;
         let nonce = decode_nonce(args)?;
-        println!("{}", prompt);
-        let result = TerminalEncryptionBox::new(ParamsOfTerminalEncryptionBox {
-            context: self.client.clone(),
-            box_type: EncryptionBoxType::SecretNaCl,
-            their_pubkey: String::new(),
-            nonce: nonce,
-        })
-        .await;
-        Ok((answer_id, json!({ "handle": self.insert_box(result).await.0})))
+        self.io.print(&prompt).await;
+        let handle = self.get_box(EncryptionBoxType::SecretNaCl, String::new(), nonce).await?;
+        Ok((answer_id, json!({ "handle": handle })))
     }
     async fn get_chacha20_box(&self, args: &Value) -> InterfaceResult {
         let answer_id = decode_answer_id(args)?;
         let nonce = decode_nonce(args)?;
         let prompt = decode_prompt(args)?;
-        println!("{}", prompt);
-        let result = TerminalEncryptionBox::new(ParamsOfTerminalEncryptionBox {
+        self.io.print(&prompt).await;
+        let handle = self.get_box(EncryptionBoxType::ChaCha20, String::new(), nonce).await?;
+        Ok((answer_id, json!({ "handle": handle })))
+    }
+    /// Returns handle of the box from manifest chain, host application box
+    /// or box created with keys entered by user. Chain link goes first and is
+    /// consumed even if it is replaced with host box.
+    async fn get_box(
+        &self,
+        box_type: EncryptionBoxType,
+        their_pubkey: String,
+        nonce: String,
+    ) -> Result<u32, String> {
+        let host_box = self.settings.read().await.encryption_boxes.get(box_type.algorithm()).copied();
+        let chained = {
+            let mut processor = self.processor.write().await;
+            match host_box {
+                Some(_) if !processor.has_encryption_box() => None,
+                _ => Some(processor.next_encryption_box()),
+            }
+        };
+        let keys = match chained {
+            Some(Ok(ChainEncryptionBox::Handle(handle))) => return Ok(handle),
+            Some(Ok(ChainEncryptionBox::Keys(keys))) => Some(keys),
+            // empty link of the recorded session is replayed with host box
+            Some(Err(ProcessorError::EncryptionBoxNotDefined)) if host_box.is_some() => None,
+            Some(Err(ProcessorError::InterfaceCallNeeded)) | None => None,
+            Some(Err(e)) => Err(format!("{:?}", e))?,
+        };
+        if let (None, Some(handle)) = (&keys, host_box) {
+            self.check_host_box(handle, box_type, &their_pubkey, &nonce).await?;
+            return Ok(handle);
+        }
+        let (keystore, mnemonic) = {
+            let settings = self.settings.read().await;
+            (settings.keystore.clone(), settings.mnemonic.clone())
+        };
+        let enc_box = TerminalEncryptionBox::new(ParamsOfTerminalEncryptionBox {
             context: self.client.clone(),
-            box_type: EncryptionBoxType::ChaCha20,
-            their_pubkey: String::new(),
+            io: self.io.clone(),
+            box_type,
+            keystore,
+            keys,
+            mnemonic,
+            their_pubkey,
             nonce,
         })
-        .await;
-        Ok((answer_id, json!({ "handle": self.insert_box(result).await.0})))
+        .await
+        .map_err(|e| format!("failed to create encryption box: {}", e))?;
+        Ok(self.insert_box(enc_box).await.0)
+    }
+    async fn check_host_box(
+        &self,
+        handle: u32,
+        box_type: EncryptionBoxType,
+        their_pubkey: &str,
+        nonce: &str,
+    ) -> Result<(), String> {
+        let info = encryption_box_get_info(
+            self.client.clone(),
+            ParamsOfEncryptionBoxGetInfo { encryption_box: EncryptionBoxHandle(handle) },
+        )
+        .await
+        .map_err(|e| format!("failed to get encryption box info: {}", e))?
+        .info;
+        check_box_info(&info, box_type, their_pubkey, nonce)
     }
     async fn remove_handle(&self, args: &Value) -> InterfaceResult {
         let answer_id = decode_answer_id(args)?;
@@ -173,16 +238,44 @@ impl EncryptionBoxInput {
             }),
         ))
     }
-    async fn insert_box(&self, result_box: Result<TerminalEncryptionBox, String>) -> EncryptionBoxHandle {
-        match result_box {
-            Ok(enc_box) => {
-                let handle = enc_box.handle();
-                self.handles.write().await.push(enc_box);
-                handle
-            },
-            Err(_) => 0.into(),
+    async fn insert_box(&self, enc_box: TerminalEncryptionBox) -> EncryptionBoxHandle {
+        let handle = enc_box.handle();
+        self.handles.write().await.push(enc_box);
+        handle
+    }
+}
+
+/// Checks that host application box has algorithm, nonce and their public
+/// key requested by DeBot. Options missing in box info are not checked.
+fn check_box_info(
+    info: &EncryptionBoxInfo,
+    box_type: EncryptionBoxType,
+    their_pubkey: &str,
+    nonce: &str,
+) -> Result<(), String> {
+    let algorithm = box_type.algorithm();
+    if info.algorithm.as_deref() != Some(algorithm) {
+        return Err(format!(
+            "encryption box algorithm {:?} does not match requested {}",
+            info.algorithm, algorithm
+        ));
+    }
+    let options = info.options.clone().unwrap_or_default();
+    let mut expected = vec![("nonce", nonce)];
+    if let EncryptionBoxType::NaCl = box_type {
+        expected.push(("their_public", their_pubkey));
+    }
+    for (name, value) in expected {
+        if let Some(actual) = options[name].as_str() {
+            if normalize_key(actual) != normalize_key(value) {
+                return Err(format!(
+                    "encryption box {} {} does not match requested {}",
+                    name, actual, value
+                ));
+            }
         }
     }
+    Ok(())
 }
 
 #[async_trait::async_trait]
@@ -207,3 +300,170 @@ impl DebotInterface for EncryptionBoxInput {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::browser_io::ScriptedIo;
+    use crate::config::{make_shared_settings, UserSettings};
+    use crate::helpers::create_client_local;
+    use crate::term_encryption_box::{ChaChaBox, NaClBox, NaClSecretBox};
+    use crate::{ChainLink, DebotManifest};
+    use ton_client::crypto::register_encryption_box;
+
+    const THEIR_PUBKEY: &str = "9711a04f0b19474272bc7bae5472a8fbbb6ef71ce9c193f5ec3f5af808069a41";
+
+    async fn register_box(client: TonClient, box_type: EncryptionBoxType, nonce: &str) -> u32 {
+        let nonce = nonce.to_owned();
+        let registered = match box_type {
+            EncryptionBoxType::ChaCha20 => register_encryption_box(
+                client.clone(),
+                ChaChaBox { key: String::new(), nonce, hdpath: None, client },
+            )
+            .await,
+            EncryptionBoxType::SecretNaCl => register_encryption_box(
+                client.clone(),
+                NaClSecretBox { key: String::new(), nonce, hdpath: None, client },
+            )
+            .await,
+            EncryptionBoxType::NaCl => register_encryption_box(
+                client.clone(),
+                NaClBox {
+                    their_pubkey: THEIR_PUBKEY.to_owned(),
+                    secret: String::new(),
+                    nonce,
+                    hdpath: None,
+                    client,
+                },
+            )
+            .await,
+        };
+        registered.unwrap().handle.0
+    }
+
+    #[tokio::test]
+    async fn test_host_encryption_box() {
+        let client = create_client_local().unwrap();
+        let chacha = register_box(client.clone(), EncryptionBoxType::ChaCha20, "00").await;
+        let nacl = register_box(client.clone(), EncryptionBoxType::NaCl, "11").await;
+        let io = Arc::new(ScriptedIo::default());
+        let mut settings = UserSettings::default();
+        settings.encryption_boxes.insert("ChaCha20".to_owned(), chacha);
+        settings.encryption_boxes.insert("NaclBox".to_owned(), nacl);
+        // registered under wrong algorithm
+        settings.encryption_boxes.insert("NaclSecretBox".to_owned(), chacha);
+        let input = EncryptionBoxInput::new(
+            client,
+            io.clone(),
+            make_shared_settings(settings),
+            Arc::new(RwLock::new(ChainProcessor::new())),
+        );
+        let args = json!({ "answerId": "17", "prompt": "Enter keys", "nonce": "00" });
+        let (answer_id, result) = input.call("getChaCha20Box", &args).await.unwrap();
+        assert_eq!(answer_id, 17);
+        assert_eq!(result, json!({ "handle": chacha }));
+        let args = json!({ "answerId": "17", "prompt": "", "nonce": "01" });
+        let err = input.call("getChaCha20Box", &args).await.unwrap_err();
+        assert!(err.contains("nonce"));
+
+        let their_pubkey = format!("0x{}", THEIR_PUBKEY);
+        let args = json!({ "answerId": "17", "prompt": "", "nonce": "11", "theirPubkey": their_pubkey });
+        assert_eq!(input.call("getNaclBox", &args).await.unwrap().1, json!({ "handle": nacl }));
+        let args = json!({ "answerId": "17", "prompt": "", "nonce": "11", "theirPubkey": "0x11" });
+        assert!(input.call("getNaclBox", &args).await.unwrap_err().contains("their_public"));
+
+        let args = json!({ "answerId": "17", "prompt": "", "nonce": "00" });
+        let err = input.call("getNaclSecretBox", &args).await.unwrap_err();
+        assert!(err.contains("algorithm"));
+        assert_eq!(io.output()[0], "Enter keys");
+    }
+
+    #[tokio::test]
+    async fn test_chain_encryption_box() {
+        let mut manifest = DebotManifest::new();
+        manifest.quiet = true;
+        manifest.chain = vec![
+            ChainLink::EncryptionBox { handle: Some(9), keys: None },
+            ChainLink::EncryptionBox { handle: None, keys: None },
+        ];
+        let mut processor = ChainProcessor::new();
+        processor.load_manifest(manifest);
+        let input = EncryptionBoxInput::new(
+            create_client_local().unwrap(),
+            Arc::new(ScriptedIo::default()),
+            make_shared_settings(UserSettings::default()),
+            Arc::new(RwLock::new(processor)),
+        );
+        let args = json!({ "answerId": "17", "prompt": "", "nonce": "00" });
+        let result = input.call("getNaclSecretBox", &args).await.unwrap();
+        assert_eq!(result, (17, json!({ "handle": 9 })));
+        let err = input.call("getNaclSecretBox", &args).await.unwrap_err();
+        assert!(err.contains("EncryptionBoxNotDefined"));
+        let err = input.call("getNaclSecretBox", &args).await.unwrap_err();
+        assert!(err.contains("NoMoreChainlinks"));
+    }
+
+    #[tokio::test]
+    async fn test_chain_before_host_box() {
+        let client = create_client_local().unwrap();
+        let host = register_box(client.clone(), EncryptionBoxType::ChaCha20, "00").await;
+        let mut manifest = DebotManifest::new();
+        manifest.quiet = true;
+        manifest.chain = vec![
+            ChainLink::EncryptionBox { handle: Some(9), keys: None },
+            ChainLink::EncryptionBox { handle: None, keys: None },
+            ChainLink::Input {
+                interface: "input".to_owned(),
+                method: "get".to_owned(),
+                params: None,
+                mandatory: false,
+            },
+        ];
+        let mut processor = ChainProcessor::new();
+        processor.load_manifest(manifest);
+        let processor = Arc::new(RwLock::new(processor));
+        let mut settings = UserSettings::default();
+        settings.encryption_boxes.insert("ChaCha20".to_owned(), host);
+        let input = EncryptionBoxInput::new(
+            client,
+            Arc::new(ScriptedIo::default()),
+            make_shared_settings(settings),
+            processor.clone(),
+        );
+        let args = json!({ "answerId": "17", "prompt": "", "nonce": "00" });
+        assert_eq!(input.call("getChaCha20Box", &args).await.unwrap().1, json!({ "handle": 9 }));
+        assert_eq!(input.call("getChaCha20Box", &args).await.unwrap().1, json!({ "handle": host }));
+        assert_eq!(input.call("getChaCha20Box", &args).await.unwrap().1, json!({ "handle": host }));
+        assert!(processor.write().await.next_canned_result("input", "get").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_box_params() {
+        let mut manifest = DebotManifest::new();
+        manifest.quiet = true;
+        manifest.chain = vec![
+            ChainLink::EncryptionBox { handle: None, keys: Some("missing-keys.json".to_owned()) };
+            4
+        ];
+        let mut processor = ChainProcessor::new();
+        processor.load_manifest(manifest);
+        let input = EncryptionBoxInput::new(
+            create_client_local().unwrap(),
+            Arc::new(ScriptedIo::default()),
+            make_shared_settings(UserSettings::default()),
+            Arc::new(RwLock::new(processor)),
+        );
+        let nonce = "00".repeat(24);
+        let args = json!({ "answerId": "17", "prompt": "", "nonce": nonce });
+        let err = input.call("getNaclSecretBox", &args).await.unwrap_err();
+        assert!(err.contains("failed to create encryption box"));
+
+        let args = json!({ "answerId": "17", "prompt": "", "nonce": "0102" });
+        assert!(input.call("getNaclSecretBox", &args).await.unwrap_err().contains("nonce"));
+        let args = json!({ "answerId": "17", "prompt": "", "nonce": "zz" });
+        assert!(input.call("getChaCha20Box", &args).await.unwrap_err().contains("nonce"));
+        let args = json!({ "answerId": "17", "prompt": "", "nonce": nonce, "theirPubkey": "0x11" });
+        assert!(input.call("getChaCha20Box", &args).await.unwrap_err().contains("nonce"));
+        assert!(input.handles.read().await.is_empty());
+    }
+}
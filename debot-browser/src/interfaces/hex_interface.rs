@@ -0,0 +1,140 @@
+/*
+* Copyright 2018-2021 TON DEV SOLUTIONS LTD.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific TON DEV software governing permissions and
+* limitations under the License.
+*/
+use super::dinterface::{decode_answer_id, decode_arg, decode_string_arg};
+use serde_json::{Value, json};
+use ton_client::abi::Abi;
+use ton_client::debot::{DebotInterface, InterfaceResult};
+
+const ID: &'static str = "edfbb00d6ebd16d57a1636774845af9499b400ba417da8552f40b1250256ff8f";
+
+const ABI: &str = r#"
+{
+    "ABI version": 2,
+    "version": "2.2",
+    "header": ["time"],
+    "functions": [
+        {
+            "name": "encode",
+            "id": "0x31d9f12c",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"data","type":"bytes"}
+            ],
+            "outputs": [
+                {"name":"hexstr","type":"string"}
+            ]
+        },
+        {
+            "name": "decode",
+            "id": "0x5992a05b",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"hexstr","type":"string"}
+            ],
+            "outputs": [
+                {"name":"data","type":"bytes"}
+            ]
+        },
+        {
+            "name": "constructor",
+            "id": "0x68b55f3f",
+            "inputs": [
+            ],
+            "outputs": [
+            ]
+        }
+    ],
+    "data": [
+    ],
+    "events": [
+    ],
+    "fields": [
+        {"name":"_pubkey","type":"uint256"},
+        {"name":"_timestamp","type":"uint64"},
+        {"name":"_constructorFlag","type":"bool"}
+    ]
+}
+"#;
+
+pub struct HexInterface {}
+
+impl HexInterface {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    fn encode(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let data = decode_arg(args, "data")?;
+        let data = hex::decode(&data).map_err(|e| format!("invalid bytes: {}", e))?;
+        Ok((answer_id, json!({ "hexstr": hex::encode(&data) })))
+    }
+
+    fn decode(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let hexstr = decode_string_arg(args, "hexstr")?;
+        let data = hex::decode(hexstr.trim_start_matches("0x"))
+            .map_err(|e| format!("invalid hex string: {}", e))?;
+        Ok((answer_id, json!({ "data": hex::encode(&data) })))
+    }
+}
+
+#[async_trait::async_trait]
+impl DebotInterface for HexInterface {
+    fn get_id(&self) -> String {
+        ID.to_string()
+    }
+
+    fn get_abi(&self) -> Abi {
+        Abi::Json(ABI.to_owned())
+    }
+
+    async fn call(&self, func: &str, args: &Value) -> InterfaceResult {
+        match func {
+            "encode" => self.encode(args),
+            "decode" => self.decode(args),
+            _ => Err(format!("function \"{}\" is not implemented", func)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        let iface = HexInterface::new();
+        let args = json!({ "answerId": "11", "data": hex::encode("hello") });
+        let (answer_id, result) = iface.encode(&args).unwrap();
+        assert_eq!(answer_id, 11);
+        assert_eq!(result["hexstr"], "68656c6c6f");
+
+        assert!(iface.encode(&json!({ "answerId": "11", "data": "zz" })).is_err());
+    }
+
+    #[test]
+    fn test_decode() {
+        let iface = HexInterface::new();
+        let args = json!({ "answerId": "12", "hexstr": "68656C6C6F" });
+        let (answer_id, result) = iface.decode(&args).unwrap();
+        assert_eq!(answer_id, 12);
+        assert_eq!(result["data"], hex::encode("hello"));
+
+        let args = json!({ "answerId": "12", "hexstr": "0x0102" });
+        assert_eq!(iface.decode(&args).unwrap().1["data"], "0102");
+
+        assert!(iface.decode(&json!({ "answerId": "12", "hexstr": "123" })).is_err());
+        assert!(iface.decode(&json!({ "answerId": "12" })).is_err());
+    }
+}
@@ -10,29 +10,45 @@
 * See the License for the specific TON DEV software governing permissions and
 * limitations under the License.
 */
-use super::dinterface::{decode_answer_id, decode_prompt, decode_string_arg};
+use super::datetime_input::ID as DATETIME_ID;
+use super::dinterface::{decode_answer_id, decode_prompt, decode_string_arg, Printer};
+use super::media::ID as MEDIA_ID;
 use super::menu::{MenuItem, ID as MENU_ID};
 use super::terminal::ID as TERMINAL_ID;
-use crate::{ChainProcessor, ProcessorError};
+use crate::{ChainLink, ChainProcessor, ProcessorError};
 use serde_json::{Value, json};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use ton_client::abi::Abi;
 use ton_client::debot::{DebotInterface, InterfaceResult};
 
+/// Checks if interface function asks user for input. Output functions and
+/// queries of browser capabilities are never answered from manifest chain.
+fn is_input(interface: &str, func: &str) -> bool {
+    match interface {
+        TERMINAL_ID => func != "print" && func != "printf",
+        MEDIA_ID => false,
+        DATETIME_ID => func != "getTimeZoneOffset",
+        _ => true,
+    }
+}
+
 pub struct InputInterface {
     processor: Arc<RwLock<ChainProcessor>>,
     inner_interface: Arc<dyn DebotInterface + Send + Sync>,
+    printer: Printer,
 }
 
 impl InputInterface {
     pub fn new(
         inner_interface: Arc<dyn DebotInterface + Send + Sync>,
         processor: Arc<RwLock<ChainProcessor>>,
+        printer: Printer,
     ) -> Self {
         Self {
             inner_interface,
             processor,
+            printer,
         }
     }
 }
@@ -48,32 +64,35 @@ impl DebotInterface for InputInterface {
     }
 
     async fn call(&self, func: &str, args: &Value) -> InterfaceResult {
-        if &self.get_id() == TERMINAL_ID {
-            if func == "print" || func == "printf" {
-                return self.inner_interface.call(func, args).await;
-            }
+        if !is_input(&self.get_id(), func) {
+            return self.inner_interface.call(func, args).await;
         }
         let result = self.processor.write().await.next_input(&self.get_id(), func, args);
         match result {
             Err(ProcessorError::InterfaceCallNeeded) => {
                 let res = self.inner_interface.call(func, args).await?;
+                self.processor.write().await.record(ChainLink::Input {
+                    interface: self.get_id(),
+                    method: func.to_owned(),
+                    params: Some(res.1.clone()),
+                    mandatory: false,
+                });
                 Ok(res)
             },
             Err(e) => Err(format!("{:?}", e))?,
             Ok(params) => {
                 let prompt = decode_prompt(args);
                 let title = decode_string_arg(args, "title");
-                let processor = self.processor.read().await;
                 if let Ok(prompt) = prompt {
-                    processor.print(&prompt);
+                    self.printer.print(&prompt).await;
                 }
                 if let Ok(prompt) = title {
-                    processor.print(&prompt);
+                    self.printer.print(&prompt).await;
                 }
                 let params = params.unwrap_or(json!({}));
                 if let Some(args) = params.as_object() {
                     for arg in args {
-                        processor.print(&format!("{}", arg.1));
+                        self.printer.print(&format!("{}", arg.1)).await;
                     }
                 }
                 let answer_id = if self.get_id() == MENU_ID {
@@ -90,4 +109,72 @@ impl DebotInterface for InputInterface {
             }
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::browser_io::ScriptedIo;
+    use crate::DebotManifest;
+
+    /// Interface which answers every call with the function name.
+    struct Inner(&'static str);
+
+    #[async_trait::async_trait]
+    impl DebotInterface for Inner {
+        fn get_id(&self) -> String {
+            self.0.to_owned()
+        }
+
+        fn get_abi(&self) -> Abi {
+            Abi::Json("{}".to_owned())
+        }
+
+        async fn call(&self, func: &str, _args: &Value) -> InterfaceResult {
+            Ok((1, json!({ "func": func })))
+        }
+    }
+
+    fn input(id: &'static str, chain: Vec<ChainLink>) -> InputInterface {
+        let mut manifest = DebotManifest::new();
+        manifest.quiet = true;
+        manifest.chain = chain;
+        let mut processor = ChainProcessor::new();
+        processor.load_manifest(manifest);
+        let processor = Arc::new(RwLock::new(processor));
+        let printer = Printer::new(processor.clone(), Arc::new(ScriptedIo::new(Vec::<String>::new())));
+        InputInterface::new(Arc::new(Inner(id)), processor, printer)
+    }
+
+    #[test]
+    fn test_is_input() {
+        assert!(!is_input(TERMINAL_ID, "print"));
+        assert!(is_input(TERMINAL_ID, "input"));
+        assert!(!is_input(MEDIA_ID, "output"));
+        assert!(!is_input(MEDIA_ID, "getSupportDataScheme"));
+        assert!(!is_input(DATETIME_ID, "getTimeZoneOffset"));
+        assert!(is_input(DATETIME_ID, "getDate"));
+        assert!(is_input(MENU_ID, "select"));
+    }
+
+    #[tokio::test]
+    async fn test_queries_bypass_chain() {
+        let link = ChainLink::Input {
+            interface: DATETIME_ID.to_owned(),
+            method: "getDate".to_owned(),
+            params: Some(json!({ "date": "0" })),
+            mandatory: true,
+        };
+        let datetime = input(DATETIME_ID, vec![link]);
+        let args = json!({ "answerId": "2" });
+        let result = datetime.call("getTimeZoneOffset", &args).await.unwrap();
+        assert_eq!(result, (1, json!({ "func": "getTimeZoneOffset" })));
+        // chain link is left for the real input
+        let result = datetime.call("getDate", &args).await.unwrap();
+        assert_eq!(result, (2, json!({ "date": "0" })));
+
+        // quiet mode with empty chain
+        let media = input(MEDIA_ID, vec![]);
+        let result = media.call("getSupportDataScheme", &args).await.unwrap();
+        assert_eq!(result, (1, json!({ "func": "getSupportDataScheme" })));
+    }
+}
@@ -0,0 +1,472 @@
+/*
+* Copyright 2018-2021 TON DEV SOLUTIONS LTD.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific TON DEV software governing permissions and
+* limitations under the License.
+*/
+use super::dinterface::{decode_answer_id, decode_string_arg, CallerAbi};
+use crate::helpers::TonClient;
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use ton_client::abi::Abi;
+use ton_client::crypto::{sha256, ParamsOfHash};
+use ton_client::debot::{DebotInterface, InterfaceResult};
+
+const ID: &'static str = "442288826041d564ccedc579674f17c1b0a3452df799656a9167a41ab270ec19";
+
+const ABI: &str = r#"
+{
+    "ABI version": 2,
+    "version": "2.2",
+    "header": ["time"],
+    "functions": [
+        {
+            "name": "deserialize",
+            "id": "0x7ff42354",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"json","type":"string"}
+            ],
+            "outputs": [
+                {"name":"result","type":"bool"},
+                {"name":"obj","type":"cell"}
+            ]
+        },
+        {
+            "name": "constructor",
+            "id": "0x68b55f3f",
+            "inputs": [
+            ],
+            "outputs": [
+            ]
+        }
+    ],
+    "data": [
+    ],
+    "events": [
+    ],
+    "fields": [
+        {"name":"_pubkey","type":"uint256"},
+        {"name":"_timestamp","type":"uint64"},
+        {"name":"_constructorFlag","type":"bool"}
+    ]
+}
+"#;
+
+const EMPTY_CELL: &str = "te6ccgEBAQEAAgAAAA==";
+
+#[derive(Deserialize, Clone)]
+struct Param {
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    components: Vec<Param>,
+}
+
+#[derive(Deserialize)]
+struct Function {
+    name: String,
+    #[serde(default)]
+    inputs: Vec<Param>,
+    #[serde(default)]
+    outputs: Vec<Param>,
+    id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Contract {
+    #[serde(rename = "ABI version")]
+    abi_version: u8,
+    /// Full ABI version, e.g. "2.2". Not defined in ABI 2.0.
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    functions: Vec<Function>,
+}
+
+impl Contract {
+    /// ABI 2.0 has no `string` type, strings are declared as `bytes`.
+    fn strings_as_bytes(&self) -> bool {
+        self.version.as_ref().map(|v| v == "2.0").unwrap_or(true)
+    }
+}
+
+pub struct JsonDeserialize {
+    client: TonClient,
+    caller_abi: CallerAbi,
+}
+
+impl JsonDeserialize {
+    pub fn new(client: TonClient, caller_abi: CallerAbi) -> Self {
+        Self { client, caller_abi }
+    }
+
+    fn deserialize(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let json_str = decode_string_arg(args, "json")?;
+        let (result_param, obj_param) = self.callback_params(answer_id)?;
+        let obj = serde_json::from_str::<Value>(&json_str)
+            .ok()
+            .and_then(|value| pack(&value, &obj_param).ok());
+        let (result, obj) = match obj {
+            Some(obj) => (true, obj),
+            None => (false, default_value(&obj_param)),
+        };
+        let mut ret_args = Map::new();
+        ret_args.insert(result_param.name, json!(result));
+        ret_args.insert(obj_param.name, obj);
+        Ok((answer_id, Value::Object(ret_args)))
+    }
+
+    /// Finds DeBot callback by `answer_id` and returns its parameters.
+    fn callback_params(&self, answer_id: u32) -> Result<(Param, Param), String> {
+        let abi = self.caller_abi.read().unwrap().clone()
+            .ok_or(format!("DeBot ABI is not defined"))?;
+        let contract: Contract = serde_json::from_str(&abi)
+            .map_err(|e| format!("invalid DeBot ABI: {}", e))?;
+        for func in &contract.functions {
+            if self.function_id(func, contract.abi_version, contract.strings_as_bytes())? != answer_id {
+                continue;
+            }
+            if func.inputs.len() != 2 {
+                return Err(format!("invalid callback \"{}\": must have 2 arguments", func.name));
+            }
+            return Ok((func.inputs[0].clone(), func.inputs[1].clone()));
+        }
+        Err(format!("callback 0x{:x} not found in DeBot ABI", answer_id))
+    }
+
+    fn function_id(&self, func: &Function, abi_version: u8, strings_as_bytes: bool) -> Result<u32, String> {
+        if let Some(id) = &func.id {
+            return u32::from_str_radix(id.trim_start_matches("0x"), 16)
+                .map_err(|e| format!("invalid function id \"{}\": {}", id, e));
+        }
+        let hash = sha256(
+            self.client.clone(),
+            ParamsOfHash {
+                data: base64::encode(function_signature(func, abi_version, strings_as_bytes).as_bytes()),
+            },
+        )
+        .map_err(|e| format!("{}", e))?
+        .hash;
+        let hash = hex::decode(&hash).map_err(|e| format!("{}", e))?;
+        let id = u32::from_be_bytes([hash[0], hash[1], hash[2], hash[3]]);
+        Ok(id & 0x7FFFFFFF)
+    }
+}
+
+fn function_signature(func: &Function, abi_version: u8, strings_as_bytes: bool) -> String {
+    let types = |params: &Vec<Param>| {
+        params
+            .iter()
+            .map(|param| type_signature(param, strings_as_bytes))
+            .collect::<Vec<String>>()
+            .join(",")
+    };
+    format!("{}({})({})v{}", func.name, types(&func.inputs), types(&func.outputs), abi_version)
+}
+
+fn type_signature(param: &Param, strings_as_bytes: bool) -> String {
+    let kind = if strings_as_bytes {
+        param.kind.replace("string", "bytes")
+    } else {
+        param.kind.clone()
+    };
+    if param.components.is_empty() {
+        return kind;
+    }
+    let components = param.components
+        .iter()
+        .map(|param| type_signature(param, strings_as_bytes))
+        .collect::<Vec<String>>()
+        .join(",");
+    kind.replace("tuple", &format!("({})", components))
+}
+
+/// Returns type of array element, value of map or optional value.
+fn inner_type(kind: &str) -> Option<String> {
+    if kind.ends_with("[]") {
+        Some(kind[..kind.len() - 2].to_owned())
+    } else if kind.starts_with("optional(") && kind.ends_with(")") {
+        Some(kind["optional(".len()..kind.len() - 1].to_owned())
+    } else if kind.starts_with("map(") && kind.ends_with(")") {
+        let pos = kind.find(',')?;
+        Some(kind[pos + 1..kind.len() - 1].to_owned())
+    } else {
+        None
+    }
+}
+
+fn with_kind(param: &Param, kind: String) -> Param {
+    Param {
+        name: param.name.clone(),
+        kind,
+        components: param.components.clone(),
+    }
+}
+
+/// Converts arbitrary json value to the form accepted by ABI encoder for `param`.
+fn pack(value: &Value, param: &Param) -> Result<Value, String> {
+    let kind = param.kind.as_str();
+    let invalid = || format!("invalid value for \"{}\" ({}): {}", param.name, kind, value);
+    if let Some(inner) = inner_type(kind) {
+        let inner_param = with_kind(param, inner);
+        if kind.ends_with("[]") {
+            let array = value.as_array().ok_or_else(invalid)?;
+            return array.iter()
+                .map(|elem| pack(elem, &inner_param))
+                .collect::<Result<Vec<Value>, String>>()
+                .map(Value::Array);
+        }
+        if kind.starts_with("optional(") {
+            return match value {
+                Value::Null => Ok(Value::Null),
+                _ => pack(value, &inner_param),
+            };
+        }
+        let object = value.as_object().ok_or_else(invalid)?;
+        let mut map = Map::new();
+        for (key, val) in object {
+            map.insert(key.clone(), pack(val, &inner_param)?);
+        }
+        return Ok(Value::Object(map));
+    }
+    match kind {
+        "tuple" => {
+            let object = value.as_object().ok_or_else(invalid)?;
+            let mut map = Map::new();
+            for component in &param.components {
+                let val = object.get(&component.name).ok_or_else(|| {
+                    format!("field \"{}\" not found", component.name)
+                })?;
+                map.insert(component.name.clone(), pack(val, component)?);
+            }
+            Ok(Value::Object(map))
+        }
+        "bool" => match value {
+            Value::Bool(_) => Ok(value.clone()),
+            Value::String(s) if s == "true" || s == "false" => Ok(json!(s == "true")),
+            _ => Err(invalid()),
+        },
+        "string" => match value {
+            Value::String(_) => Ok(value.clone()),
+            Value::Number(_) | Value::Bool(_) => Ok(json!(value.to_string())),
+            _ => Err(invalid()),
+        },
+        "address" | "cell" => match value {
+            Value::String(_) => Ok(value.clone()),
+            _ => Err(invalid()),
+        },
+        _ if kind.starts_with("bytes") || kind.starts_with("fixedbytes") => match value {
+            Value::String(s) => Ok(json!(hex::encode(s.as_bytes()))),
+            _ => Err(invalid()),
+        },
+        _ if is_integer(kind) => match value {
+            Value::Number(n) => Ok(json!(n.to_string())),
+            Value::String(_) => Ok(value.clone()),
+            Value::Bool(b) => Ok(json!(if *b { "1" } else { "0" })),
+            _ => Err(invalid()),
+        },
+        _ => Err(format!("unsupported type \"{}\"", kind)),
+    }
+}
+
+fn is_integer(kind: &str) -> bool {
+    ["uint", "int", "varuint", "varint", "gram", "token"]
+        .iter()
+        .any(|prefix| kind.starts_with(prefix))
+}
+
+/// Returns value used as `obj` argument when json cannot be deserialized.
+fn default_value(param: &Param) -> Value {
+    let kind = param.kind.as_str();
+    if kind.ends_with("[]") {
+        json!([])
+    } else if kind.starts_with("optional(") {
+        Value::Null
+    } else if kind.starts_with("map(") {
+        json!({})
+    } else if kind == "tuple" {
+        let mut map = Map::new();
+        for component in &param.components {
+            map.insert(component.name.clone(), default_value(component));
+        }
+        Value::Object(map)
+    } else if kind == "bool" {
+        json!(false)
+    } else if kind == "address" {
+        json!(format!("0:{:064}", 0))
+    } else if kind == "cell" {
+        json!(EMPTY_CELL)
+    } else if is_integer(kind) {
+        json!("0")
+    } else {
+        json!("")
+    }
+}
+
+#[async_trait::async_trait]
+impl DebotInterface for JsonDeserialize {
+    fn get_id(&self) -> String {
+        ID.to_string()
+    }
+
+    fn get_abi(&self) -> Abi {
+        Abi::Json(ABI.to_owned())
+    }
+
+    async fn call(&self, func: &str, args: &Value) -> InterfaceResult {
+        match func {
+            "deserialize" => self.deserialize(args),
+            _ => Err(format!("function \"{}\" is not implemented", func)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::create_client_local;
+    use std::sync::{Arc, RwLock};
+
+    const DEBOT_ABI: &str = r#"
+    {
+        "ABI version": 2,
+        "version": "2.2",
+        "header": ["time"],
+        "functions": [
+            {
+                "name": "setUser",
+                "inputs": [
+                    {"name":"ok","type":"bool"},
+                    {"components":[
+                        {"name":"name","type":"string"},
+                        {"name":"age","type":"uint32"},
+                        {"name":"tags","type":"string[]"},
+                        {"name":"data","type":"bytes"}
+                    ],"name":"user","type":"tuple"}
+                ],
+                "outputs": []
+            },
+            {
+                "name": "select",
+                "inputs": [
+                    {"name":"title","type":"string"},
+                    {"name":"description","type":"string"},
+                    {"components":[{"name":"title","type":"string"},{"name":"description","type":"string"},{"name":"handlerId","type":"uint32"}],"name":"items","type":"tuple[]"}
+                ],
+                "outputs": [
+                    {"name":"index","type":"uint32"}
+                ]
+            }
+        ],
+        "data": [],
+        "events": []
+    }
+    "#;
+
+    fn function(name: &str) -> Function {
+        let contract: Contract = serde_json::from_str(DEBOT_ABI).unwrap();
+        contract.functions.into_iter().find(|f| f.name == name).unwrap()
+    }
+
+    #[test]
+    fn test_function_signature() {
+        assert_eq!(
+            function_signature(&function("select"), 2, true),
+            "select(bytes,bytes,(bytes,bytes,uint32)[])(uint32)v2"
+        );
+        assert_eq!(
+            function_signature(&function("setUser"), 2, true),
+            "setUser(bool,(bytes,uint32,bytes[],bytes))()v2"
+        );
+        assert_eq!(
+            function_signature(&function("setUser"), 2, false),
+            "setUser(bool,(string,uint32,string[],bytes))()v2"
+        );
+    }
+
+    #[test]
+    fn test_function_id() {
+        let client = create_client_local().unwrap();
+        let iface = JsonDeserialize::new(client, Arc::new(RwLock::new(None)));
+        let abi = |version: &str| {
+            format!(
+                r#"{{"ABI version": 2, {} "functions": [{{"name": "print", "inputs": [
+                    {{"name":"answerId","type":"uint32"}},
+                    {{"name":"message","type":"string"}}
+                ], "outputs": []}}]}}"#,
+                version
+            )
+        };
+        // id of `print(uint32,string)()v2` in ABI 2.2
+        let contract: Contract = serde_json::from_str(&abi(r#""version": "2.2","#)).unwrap();
+        assert!(!contract.strings_as_bytes());
+        let id = iface.function_id(&contract.functions[0], 2, contract.strings_as_bytes()).unwrap();
+        assert_eq!(id, 0x6367cbed);
+        // in ABI 2.0 it is `print(uint32,bytes)()v2`, the id of `Terminal.print`
+        let contract: Contract = serde_json::from_str(&abi("")).unwrap();
+        assert!(contract.strings_as_bytes());
+        let id = iface.function_id(&contract.functions[0], 2, contract.strings_as_bytes()).unwrap();
+        assert_eq!(id, 0x0ce649c2);
+    }
+
+    #[test]
+    fn test_pack() {
+        let (_, user) = {
+            let f = function("setUser");
+            (f.inputs[0].clone(), f.inputs[1].clone())
+        };
+        let packed = pack(
+            &json!({"name": "Bob", "age": 42, "tags": ["a", "b"], "data": "hi", "extra": 1}),
+            &user,
+        )
+        .unwrap();
+        assert_eq!(
+            packed,
+            json!({"name": "Bob", "age": "42", "tags": ["a", "b"], "data": "6869"})
+        );
+
+        assert!(pack(&json!({"name": "Bob"}), &user).is_err());
+        assert!(pack(&json!({"name": "Bob", "age": {}, "tags": [], "data": ""}), &user).is_err());
+        assert!(pack(&json!([]), &user).is_err());
+
+        assert_eq!(
+            default_value(&user),
+            json!({"name": "", "age": "0", "tags": [], "data": ""})
+        );
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let client = create_client_local().unwrap();
+        let caller_abi: CallerAbi = Arc::new(RwLock::new(Some(DEBOT_ABI.to_owned())));
+        let iface = JsonDeserialize::new(client, caller_abi);
+        let answer_id = iface.function_id(&function("setUser"), 2, false).unwrap();
+
+        let args = json!({
+            "answerId": format!("{}", answer_id),
+            "json": r#"{"name": "Alice", "age": "7", "tags": [], "data": ""}"#,
+        });
+        let (id, result) = iface.deserialize(&args).unwrap();
+        assert_eq!(id, answer_id);
+        assert_eq!(
+            result,
+            json!({"ok": true, "user": {"name": "Alice", "age": "7", "tags": [], "data": ""}})
+        );
+
+        let args = json!({ "answerId": format!("{}", answer_id), "json": "{invalid" });
+        let (_, result) = iface.deserialize(&args).unwrap();
+        assert_eq!(result["ok"], json!(false));
+
+        let args = json!({ "answerId": "1", "json": "{}" });
+        assert!(iface.deserialize(&args).is_err());
+    }
+}
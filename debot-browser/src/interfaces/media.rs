@@ -0,0 +1,183 @@
+use super::dinterface::{decode_answer_id, decode_prompt, decode_string_arg, Printer};
+use serde_json::{Value, json};
+use ton_client::abi::Abi;
+use ton_client::debot::{DebotInterface, InterfaceResult};
+
+pub const ID: &'static str = "d357175cfe8978d39b0f86552dcd9404973493e2816101d9ff6c05ee248ef6bd";
+
+pub const ABI: &str = r#"
+{
+    "ABI version": 2,
+    "version": "2.2",
+    "header": ["time"],
+    "functions": [
+        {
+            "name": "output",
+            "id": "0x543c0299",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"prompt","type":"string"},
+                {"name":"data","type":"string"}
+            ],
+            "outputs": [
+                {"name":"result","type":"uint8"}
+            ]
+        },
+        {
+            "name": "getSupportDataScheme",
+            "id": "0x33c4e962",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"mime","type":"string"}
+            ],
+            "outputs": [
+                {"name":"result","type":"bool"}
+            ]
+        },
+        {
+            "name": "constructor",
+            "id": "0x68b55f3f",
+            "inputs": [
+            ],
+            "outputs": [
+            ]
+        }
+    ],
+    "data": [
+    ],
+    "events": [
+    ],
+    "fields": [
+        {"name":"_pubkey","type":"uint256"},
+        {"name":"_timestamp","type":"uint64"},
+        {"name":"_constructorFlag","type":"bool"}
+    ]
+}
+"#;
+
+/// Media types which can be rendered by terminal (as text or as a short
+/// description of the content).
+const SUPPORTED_TYPES: [&str; 4] = ["text/", "image/", "audio/", "video/"];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MediaStatus {
+    Success = 0,
+    UnsupportedMediaType = 1,
+    InvalidDataScheme = 2,
+}
+
+pub struct Media {
+    printer: Printer,
+}
+
+impl Media {
+    pub fn new(printer: Printer) -> Self {
+        Self { printer }
+    }
+
+    async fn output(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let prompt = decode_prompt(args)?;
+        let data = decode_string_arg(args, "data")?;
+        let status = match render(&data) {
+            Ok(text) => {
+                if !prompt.is_empty() {
+                    self.printer.print(&prompt).await;
+                }
+                self.printer.print(&text).await;
+                MediaStatus::Success
+            },
+            Err(status) => status,
+        };
+        Ok((answer_id, json!({ "result": status as u8 })))
+    }
+
+    fn get_support_data_scheme(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let mime = decode_string_arg(args, "mime")?;
+        Ok((answer_id, json!({ "result": is_supported(&mime) })))
+    }
+}
+
+#[async_trait::async_trait]
+impl DebotInterface for Media {
+    fn get_id(&self) -> String {
+        ID.to_string()
+    }
+
+    fn get_abi(&self) -> Abi {
+        Abi::Json(ABI.to_owned())
+    }
+
+    async fn call(&self, func: &str, args: &Value) -> InterfaceResult {
+        match func {
+            "output" => self.output(args).await,
+            "getSupportDataScheme" => self.get_support_data_scheme(args),
+            _ => Err(format!("function \"{}\" is not implemented", func)),
+        }
+    }
+}
+
+fn is_supported(mime: &str) -> bool {
+    let mime = mime.trim().to_lowercase();
+    SUPPORTED_TYPES.iter().any(|x| mime.starts_with(x) && mime.len() > x.len())
+}
+
+/// Parses `data:<mime>;base64,<data>` url.
+fn parse_data_url(url: &str) -> Result<(String, Vec<u8>), MediaStatus> {
+    let url = url.trim();
+    let rest = url.strip_prefix("data:").ok_or(MediaStatus::InvalidDataScheme)?;
+    let pos = rest.find(',').ok_or(MediaStatus::InvalidDataScheme)?;
+    let (meta, payload) = (&rest[..pos], &rest[pos + 1..]);
+    let mut params = meta.split(';');
+    let mime = params.next().unwrap_or_default();
+    let mime = if mime.is_empty() { "text/plain" } else { mime }.to_lowercase();
+    let data = if params.any(|x| x == "base64") {
+        base64::decode(payload).map_err(|_| MediaStatus::InvalidDataScheme)?
+    } else {
+        payload.as_bytes().to_vec()
+    };
+    Ok((mime, data))
+}
+
+/// Renders media content as text: text is printed as is, other media is
+/// replaced by its description.
+fn render(url: &str) -> Result<String, MediaStatus> {
+    let (mime, data) = parse_data_url(url)?;
+    if !is_supported(&mime) {
+        return Err(MediaStatus::UnsupportedMediaType);
+    }
+    if mime.starts_with("text/") {
+        Ok(String::from_utf8_lossy(&data).to_string())
+    } else {
+        Ok(format!("[{}, {} bytes]", mime, data.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let url = format!("data:text/plain;base64,{}", base64::encode("hello"));
+        assert_eq!(render(&url).unwrap(), "hello");
+        assert_eq!(render("data:,plain text").unwrap(), "plain text");
+
+        let url = format!("data:image/png;base64,{}", base64::encode(&[1u8, 2, 3]));
+        assert_eq!(render(&url).unwrap(), "[image/png, 3 bytes]");
+
+        let url = format!("data:application/zip;base64,{}", base64::encode("zip"));
+        assert_eq!(render(&url), Err(MediaStatus::UnsupportedMediaType));
+        assert_eq!(render("image/png;base64,AAAA"), Err(MediaStatus::InvalidDataScheme));
+        assert_eq!(render("data:image/png;base64,%%%"), Err(MediaStatus::InvalidDataScheme));
+    }
+
+    #[test]
+    fn test_is_supported() {
+        assert!(is_supported("image/png"));
+        assert!(is_supported("Text/HTML"));
+        assert!(!is_supported("image/"));
+        assert!(!is_supported("application/pdf"));
+    }
+}
@@ -11,7 +11,7 @@
 * limitations under the License.
 */
 use super::dinterface::decode_string_arg;
-use crate::term_browser::action_input;
+use crate::browser_io::SharedBrowserIo;
 use serde::{de, Deserialize, Deserializer};
 use serde_json::{Value, json};
 use ton_client::abi::Abi;
@@ -76,43 +76,37 @@ where
     decode_abi_number(&s).map_err(de::Error::custom)
 }
 
-pub struct Menu {}
+pub struct Menu {
+    io: SharedBrowserIo,
+}
 impl Menu {
-	pub fn new() -> Self {
-		Self{}
+	pub fn new(io: SharedBrowserIo) -> Self {
+		Self { io }
 	}
 
-    fn select(&self, args: &Value) -> InterfaceResult {
+    async fn select(&self, args: &Value) -> InterfaceResult {
         let menu_items: Vec<MenuItem> = serde_json::from_value(args["items"].clone()).unwrap();
         let title = decode_string_arg(args, "title")?;
         let description = decode_string_arg(args, "description")?;
         if title.len() > 0 {
-            println!("{}", title);
+            self.io.print(&title).await;
         }
         if description.len() > 0 {
-            println!("{}", description);
-        }
-        for (i, menu) in menu_items.iter().enumerate() {
-            println!("{}) {}", i + 1, menu.title);
-            if menu.description != "" {
-                println!("   {}", menu.description);
-            }
-        }
-        loop {
-            let res = action_input(menu_items.len());
-            if res.is_err() {
-                println!("{}", res.unwrap_err());
-                continue;
-            }
-            let (n, _, _) = res.unwrap();
-            let menu = menu_items.get(n - 1);
-            if menu.is_none() {
-                println!("Invalid menu. Try again.");
-                continue;
-            }
-
-            return Ok(( menu.unwrap().handler_id, json!({ "index": n - 1 }) ));
+            self.io.print(&description).await;
         }
+        let items: Vec<String> = menu_items
+            .iter()
+            .map(|menu| {
+                if menu.description != "" {
+                    format!("{}\n   {}", menu.title, menu.description)
+                } else {
+                    menu.title.clone()
+                }
+            })
+            .collect();
+        let n = self.io.select(&items).await?;
+        let menu = menu_items.get(n).ok_or(format!("invalid menu index {}", n))?;
+        Ok(( menu.handler_id, json!({ "index": n }) ))
     }
 }
 
@@ -128,8 +122,38 @@ impl DebotInterface for Menu {
 
     async fn call(&self, func: &str, args: &Value) -> InterfaceResult {
         match func {
-            "select" => self.select(args),
+            "select" => self.select(args).await,
             _ => Err(format!("function \"{}\" is not implemented", func)),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::browser_io::ScriptedIo;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_select() {
+        let io = Arc::new(ScriptedIo::new(vec!["3", "2"]));
+        let menu = Menu::new(io.clone());
+        let args = json!({
+            "title": "Main menu",
+            "description": "",
+            "items": [
+                {"title": "Send", "description": "", "handlerId": "0x11"},
+                {"title": "Exit", "description": "Quit DeBot", "handlerId": "0x22"}
+            ]
+        });
+        assert!(menu.select(&args).await.is_err());
+        let (handler_id, result) = menu.select(&args).await.unwrap();
+        assert_eq!(handler_id, 0x22);
+        assert_eq!(result, json!({ "index": 1 }));
+        assert_eq!(io.output()[..3], [
+            "Main menu".to_owned(),
+            "1) Send".to_owned(),
+            "2) Exit\n   Quit DeBot".to_owned(),
+        ]);
+    }
+}
@@ -13,22 +13,40 @@
 
 pub mod address_input;
 pub mod amount_input;
+pub mod base64_interface;
 pub mod confirm_input;
+pub mod country_input;
+pub mod datetime_input;
 pub mod dinterface;
 pub mod echo;
+pub mod hex_interface;
 pub mod input_interface;
+pub mod json_interface;
+pub mod media;
 pub mod encryption_box_input;
 pub mod menu;
+pub mod network;
 pub mod number_input;
+pub mod query_interface;
+pub mod sdk_interface;
 pub mod signing_box_input;
 pub mod stdout;
 pub mod terminal;
 pub mod userinfo;
 pub use address_input::AddressInput;
 pub use amount_input::AmountInput;
+pub use base64_interface::Base64Interface;
 pub use confirm_input::ConfirmInput;
+pub use country_input::CountryInput;
+pub use datetime_input::DateTimeInput;
+pub use hex_interface::HexInterface;
+pub use json_interface::JsonDeserialize;
+pub use media::Media;
 pub use menu::Menu;
+pub use network::Network;
 pub use number_input::NumberInput;
+pub use query_interface::Query;
+pub use sdk_interface::SdkInterface;
 pub use signing_box_input::SigningBoxInput;
 pub use input_interface::InputInterface;
 pub use encryption_box_input::EncryptionBoxInput;
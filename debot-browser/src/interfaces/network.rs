@@ -0,0 +1,268 @@
+/*
+* Copyright 2018-2021 TON DEV SOLUTIONS LTD.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific TON DEV software governing permissions and
+* limitations under the License.
+*/
+use super::dinterface::{decode_answer_id, decode_array, decode_string_arg};
+use crate::browser_io::SharedBrowserIo;
+use crate::http_client::{HttpRequest, SharedHttpClient};
+use crate::{ApproveKind, ChainProcessor, ProcessorError};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use ton_client::abi::Abi;
+use ton_client::debot::{DebotInterface, InterfaceResult};
+
+const ID: &'static str = "e38aed5884dc3e4426a87c083faaf4fa08109189fbc0c79281112f52e062d8ee";
+
+const ABI: &str = r#"
+{
+    "ABI version": 2,
+    "version": "2.2",
+    "header": ["time"],
+    "functions": [
+        {
+            "name": "get",
+            "id": "0x74dd3fc1",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"url","type":"string"},
+                {"name":"headers","type":"string[]"}
+            ],
+            "outputs": [
+                {"name":"statusCode","type":"int32"},
+                {"name":"retHeaders","type":"string[]"},
+                {"name":"content","type":"string"}
+            ]
+        },
+        {
+            "name": "post",
+            "id": "0x766d8212",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"url","type":"string"},
+                {"name":"headers","type":"string[]"},
+                {"name":"body","type":"string"}
+            ],
+            "outputs": [
+                {"name":"statusCode","type":"int32"},
+                {"name":"retHeaders","type":"string[]"},
+                {"name":"content","type":"string"}
+            ]
+        },
+        {
+            "name": "constructor",
+            "id": "0x68b55f3f",
+            "inputs": [
+            ],
+            "outputs": [
+            ]
+        }
+    ],
+    "data": [
+    ],
+    "events": [
+    ],
+    "fields": [
+        {"name":"_pubkey","type":"uint256"},
+        {"name":"_timestamp","type":"uint64"},
+        {"name":"_constructorFlag","type":"bool"}
+    ]
+}
+"#;
+
+pub struct Network {
+    http: SharedHttpClient,
+    processor: Arc<RwLock<ChainProcessor>>,
+    io: SharedBrowserIo,
+}
+
+impl Network {
+    pub fn new(
+        http: SharedHttpClient,
+        processor: Arc<RwLock<ChainProcessor>>,
+        io: SharedBrowserIo,
+    ) -> Self {
+        Self { http, processor, io }
+    }
+
+    async fn get(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let request = HttpRequest {
+            method: "GET".to_owned(),
+            url: decode_string_arg(args, "url")?,
+            headers: decode_headers(args)?,
+            body: None,
+        };
+        Ok((answer_id, self.fetch(request).await?))
+    }
+
+    async fn post(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let request = HttpRequest {
+            method: "POST".to_owned(),
+            url: decode_string_arg(args, "url")?,
+            headers: decode_headers(args)?,
+            body: Some(decode_string_arg(args, "body")?),
+        };
+        Ok((answer_id, self.fetch(request).await?))
+    }
+
+    async fn fetch(&self, request: HttpRequest) -> Result<Value, String> {
+        if !self.approve(&request).await? {
+            return Err(format!("network access denied: {} {}", request.method, request.url));
+        }
+        let response = self.http.read().await.fetch(request).await?;
+        Ok(json!({
+            "statusCode": response.status,
+            "retHeaders": response.headers,
+            "content": response.content,
+        }))
+    }
+
+    async fn approve(&self, request: &HttpRequest) -> Result<bool, String> {
+        let result = self.processor.read().await.approve_network();
+        match result {
+            Err(ProcessorError::InteractiveApproveNeeded) => {
+                let info = format!(
+                    "DeBot is going to make network request:\n  {} {}",
+                    request.method, request.url
+                );
+                let activity = json!({
+                    "type": "Network",
+                    "method": request.method,
+                    "url": request.url,
+                });
+                let approved = self.io.approve(&info, &activity).await?;
+                self.processor
+                    .write()
+                    .await
+                    .record_approve(ApproveKind::ApproveNetwork, approved);
+                Ok(approved)
+            },
+            Err(e) => Err(format!("{:?}", e)),
+            Ok(approved) => Ok(approved),
+        }
+    }
+}
+
+fn decode_headers(args: &Value) -> Result<Vec<String>, String> {
+    decode_array(args, "headers", |elem| elem.as_str().map(|x| x.to_owned()))
+}
+
+#[async_trait::async_trait]
+impl DebotInterface for Network {
+    fn get_id(&self) -> String {
+        ID.to_string()
+    }
+
+    fn get_abi(&self) -> Abi {
+        Abi::Json(ABI.to_owned())
+    }
+
+    async fn call(&self, func: &str, args: &Value) -> InterfaceResult {
+        match func {
+            "get" => self.get(args).await,
+            "post" => self.post(args).await,
+            _ => Err(format!("function \"{}\" is not implemented", func)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::browser_io::ScriptedIo;
+    use crate::http_client::{make_shared_http_client, HttpClient, HttpResponse, StubHttpClient};
+    use crate::pipechain::{ApproveKind, DebotManifest};
+
+    fn network(manifest: DebotManifest, answers: Vec<&str>) -> Network {
+        let mut stub = StubHttpClient::new();
+        stub.add_route("GET", "https://example.com", HttpResponse {
+            status: 200,
+            headers: vec![],
+            content: "ok".to_owned(),
+        });
+        let mut processor = ChainProcessor::new();
+        processor.load_manifest(manifest);
+        Network::new(
+            make_shared_http_client(Box::new(stub)),
+            Arc::new(RwLock::new(processor)),
+            Arc::new(ScriptedIo::new(answers)),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_approve() {
+        let args = json!({ "answerId": "1", "url": "https://example.com", "headers": [] });
+
+        let iface = network(DebotManifest::new(), vec!["y", "n"]);
+        let (_, result) = iface.call("get", &args).await.unwrap();
+        assert_eq!(result["statusCode"], json!(200));
+        assert!(iface.call("get", &args).await.is_err());
+
+        let mut manifest = DebotManifest::new();
+        manifest.auto_approve = Some(vec![ApproveKind::ApproveNetwork]);
+        let iface = network(manifest.clone(), vec![]);
+        assert!(iface.call("get", &args).await.is_ok());
+
+        manifest.auto_approve = None;
+        manifest.quiet = true;
+        let iface = network(manifest, vec![]);
+        assert!(iface.call("get", &args).await.is_err());
+    }
+
+    /// Saves all requests which reached the transport.
+    #[derive(Default)]
+    struct RecordingHttpClient {
+        requests: Arc<std::sync::Mutex<Vec<HttpRequest>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl HttpClient for RecordingHttpClient {
+        async fn fetch(&self, request: HttpRequest) -> Result<HttpResponse, String> {
+            self.requests.lock().unwrap().push(request);
+            Ok(HttpResponse { status: 200, headers: vec![], content: String::new() })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_denied_request_is_not_sent() {
+        let args = json!({ "answerId": "1", "url": "https://example.com", "headers": [], "body": "data" });
+        let mut quiet = DebotManifest::new();
+        quiet.quiet = true;
+        let cases = vec![(DebotManifest::new(), vec!["n"]), (quiet, vec![])];
+        for (manifest, answers) in cases {
+            let client = RecordingHttpClient::default();
+            let requests = client.requests.clone();
+            let mut processor = ChainProcessor::new();
+            processor.load_manifest(manifest);
+            let iface = Network::new(
+                make_shared_http_client(Box::new(client)),
+                Arc::new(RwLock::new(processor)),
+                Arc::new(ScriptedIo::new(answers)),
+            );
+            let err = iface.call("post", &args).await.unwrap_err();
+            assert_eq!(err, "network access denied: POST https://example.com");
+            assert!(requests.lock().unwrap().is_empty());
+        }
+
+        let client = RecordingHttpClient::default();
+        let requests = client.requests.clone();
+        let iface = Network::new(
+            make_shared_http_client(Box::new(client)),
+            Arc::new(RwLock::new(ChainProcessor::new())),
+            Arc::new(ScriptedIo::new(vec!["y"])),
+        );
+        iface.call("post", &args).await.unwrap();
+        assert_eq!(requests.lock().unwrap().len(), 1);
+        assert_eq!(requests.lock().unwrap()[0].body, Some("data".to_owned()));
+    }
+}
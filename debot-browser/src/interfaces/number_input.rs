@@ -1,5 +1,5 @@
 use super::dinterface::{decode_answer_id, decode_int256, decode_prompt};
-use crate::term_browser::terminal_input;
+use crate::browser_io::{input_valid, SharedBrowserIo};
 use serde_json::{Value, json};
 use ton_client::abi::Abi;
 use ton_client::debot::{DebotInterface, InterfaceResult};
@@ -47,26 +47,28 @@ pub const ABI: &str = r#"
 }
 "#;
 
-pub struct NumberInput {}
+pub struct NumberInput {
+    io: SharedBrowserIo,
+}
 
 impl NumberInput {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(io: SharedBrowserIo) -> Self {
+        Self { io }
     }
-    fn get(&self, args: &Value) -> InterfaceResult {
+    async fn get(&self, args: &Value) -> InterfaceResult {
         let answer_id = decode_answer_id(args)?;
         let prompt = decode_prompt(args)?;
         let min = decode_int256(args, "min")?;
         let max = decode_int256(args, "max")?;
         let prompt = format!("{}\n(>= {} and <= {})", prompt, min, max);
-        let value = terminal_input(&prompt, |val| {
+        let value = input_valid(self.io.as_ref(), &prompt, |val| {
             let number = decode_abi_bigint(val.as_str())
                 .map_err(|e| format!("input is not a valid number: {}", e))?;
             if number < min || number > max {
                 return Err(format!("number is out of range"));
             }
             Ok(())
-        });
+        }).await?;
         Ok((answer_id, json!({ "value": value })))
     }
 }
@@ -83,7 +85,7 @@ impl DebotInterface for NumberInput {
 
     async fn call(&self, func: &str, args: &Value) -> InterfaceResult {
         match func {
-            "get" => self.get(args),
+            "get" => self.get(args).await,
             _ => Err(format!("function \"{}\" is not implemented", func)),
         }
     }
@@ -0,0 +1,451 @@
+/*
+* Copyright 2018-2021 TON DEV SOLUTIONS LTD.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific TON DEV software governing permissions and
+* limitations under the License.
+*/
+use super::dinterface::{decode_answer_id, decode_num_arg, decode_string_arg};
+use crate::helpers::TonClient;
+use crate::ChainProcessor;
+use log::debug;
+use serde_json::{Map, Value, json};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use ton_client::abi::Abi;
+use ton_client::debot::{DebotInterface, InterfaceResult};
+use ton_client::error::ClientError;
+use ton_client::net::{
+    query, query_collection, wait_for_collection, OrderBy, ParamsOfQuery,
+    ParamsOfQueryCollection, ParamsOfWaitForCollection, SortDirection,
+};
+
+pub const ID: &'static str = "5c6fd81616cdfb963632109c42144a3a885c8d0f2e8deb5d8e15872fb92f2811";
+
+const ABI: &str = r#"
+{
+    "ABI version": 2,
+    "version": "2.2",
+    "header": ["time"],
+    "functions": [
+        {
+            "name": "collection",
+            "id": "0x24bdafb9",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"collectionType","type":"uint8"},
+                {"name":"queryFilter","type":"string"},
+                {"name":"returnFilter","type":"string"},
+                {"name":"limit","type":"uint32"},
+                {"name":"paginationFilter","type":"optional(string)"}
+            ],
+            "outputs": [
+                {"name":"status","type":"uint8"},
+                {"name":"objects","type":"string[]"}
+            ]
+        },
+        {
+            "name": "waitForCollection",
+            "id": "0x29f0a9bd",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"collectionType","type":"uint8"},
+                {"name":"queryFilter","type":"string"},
+                {"name":"returnFilter","type":"string"},
+                {"name":"timeout","type":"uint32"}
+            ],
+            "outputs": [
+                {"name":"status","type":"uint8"},
+                {"name":"object","type":"string"}
+            ]
+        },
+        {
+            "name": "query",
+            "id": "0x4341d936",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"query","type":"string"},
+                {"name":"variables","type":"string"}
+            ],
+            "outputs": [
+                {"name":"status","type":"uint8"},
+                {"name":"object","type":"string"}
+            ]
+        },
+        {
+            "name": "constructor",
+            "id": "0x68b55f3f",
+            "inputs": [
+            ],
+            "outputs": [
+            ]
+        }
+    ],
+    "data": [
+    ],
+    "events": [
+    ],
+    "fields": [
+        {"name":"_pubkey","type":"uint256"},
+        {"name":"_timestamp","type":"uint64"},
+        {"name":"_constructorFlag","type":"bool"}
+    ]
+}
+"#;
+
+/// Max number of objects requested from the server at once.
+const PAGE_SIZE: u32 = 50;
+/// Error code returned by `net.wait_for_collection` on timeout.
+/// Code 603 (`WaitForFailed`) means network error.
+const WAIT_FOR_TIMEOUT_ERROR: u32 = 607;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum QueryStatus {
+    Success = 0,
+    FilterError = 1,
+    NetworkError = 2,
+    PostProcessError = 3,
+    NotFound = 4,
+}
+
+fn wait_error_status(e: &ClientError) -> QueryStatus {
+    if e.code == WAIT_FOR_TIMEOUT_ERROR {
+        QueryStatus::NotFound
+    } else {
+        QueryStatus::NetworkError
+    }
+}
+
+fn collection_name(collection_type: u8) -> Result<&'static str, String> {
+    match collection_type {
+        0 => Ok("accounts"),
+        1 => Ok("messages"),
+        2 => Ok("transactions"),
+        _ => Err(format!("unknown collection type {}", collection_type)),
+    }
+}
+
+pub struct Query {
+    client: TonClient,
+    processor: Arc<RwLock<ChainProcessor>>,
+}
+
+impl Query {
+    pub fn new(client: TonClient, processor: Arc<RwLock<ChainProcessor>>) -> Self {
+        Self { client, processor }
+    }
+
+    async fn collection(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        if let Some(result) = self.canned_result("collection").await {
+            return Ok((answer_id, result));
+        }
+        let collection = collection_name(decode_num_arg::<u8>(args, "collectionType")?)?;
+        let return_filter = decode_string_arg(args, "returnFilter")?;
+        let limit = decode_num_arg::<u32>(args, "limit")?;
+        let filter = decode_string_arg(args, "queryFilter")?;
+        let filter = match build_filter(&filter, args["paginationFilter"].as_str()) {
+            Ok(filter) => filter,
+            Err(_) => return Ok((answer_id, collection_result(QueryStatus::FilterError, vec![]))),
+        };
+        let (status, objects) = self.query_pages(collection, filter, &return_filter, limit).await;
+        Ok((answer_id, collection_result(status, objects)))
+    }
+
+    /// Queries collection page by page until `limit` objects are received.
+    /// If `limit` is 0 then only the first page is returned.
+    async fn query_pages(
+        &self,
+        collection: &str,
+        mut filter: Value,
+        return_filter: &str,
+        limit: u32,
+    ) -> (QueryStatus, Vec<Value>) {
+        let paging = limit > PAGE_SIZE;
+        let result_fields = if paging && !return_filter.split_whitespace().any(|x| x == "id") {
+            format!("{} id", return_filter)
+        } else {
+            return_filter.to_owned()
+        };
+        let mut objects = vec![];
+        loop {
+            let page_size = if limit == 0 {
+                None
+            } else {
+                Some(std::cmp::min(limit - objects.len() as u32, PAGE_SIZE))
+            };
+            let page = query_collection(
+                self.client.clone(),
+                ParamsOfQueryCollection {
+                    collection: collection.to_owned(),
+                    filter: Some(filter.clone()),
+                    result: result_fields.clone(),
+                    order: if paging {
+                        Some(vec![OrderBy { path: "id".to_owned(), direction: SortDirection::ASC }])
+                    } else {
+                        None
+                    },
+                    limit: page_size,
+                },
+            )
+            .await;
+            let page = match page {
+                Ok(page) => page.result,
+                Err(e) => {
+                    debug!("query to {} failed: {}", collection, e);
+                    return (QueryStatus::NetworkError, vec![]);
+                },
+            };
+            let received = page.len() as u32;
+            objects.extend(page);
+            if !paging || received < page_size.unwrap_or(0) || objects.len() as u32 >= limit {
+                return (QueryStatus::Success, objects);
+            }
+            match objects.last().and_then(|obj| obj["id"].as_str()) {
+                Some(last_id) => set_id_gt(&mut filter, last_id),
+                None => return (QueryStatus::PostProcessError, vec![]),
+            }
+        }
+    }
+
+    async fn wait_for_collection(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        if let Some(result) = self.canned_result("waitForCollection").await {
+            return Ok((answer_id, result));
+        }
+        let collection = collection_name(decode_num_arg::<u8>(args, "collectionType")?)?;
+        let return_filter = decode_string_arg(args, "returnFilter")?;
+        let timeout = decode_num_arg::<u32>(args, "timeout")?;
+        let filter = decode_string_arg(args, "queryFilter")?;
+        let filter = match build_filter(&filter, None) {
+            Ok(filter) => filter,
+            Err(_) => return Ok((answer_id, object_result(QueryStatus::FilterError, None))),
+        };
+        let result = wait_for_collection(
+            self.client.clone(),
+            ParamsOfWaitForCollection {
+                collection: collection.to_owned(),
+                filter: Some(filter),
+                result: return_filter,
+                timeout: if timeout == 0 { None } else { Some(timeout) },
+            },
+        )
+        .await;
+        let result = match result {
+            Ok(r) => object_result(QueryStatus::Success, Some(r.result)),
+            Err(e) => {
+                debug!("wait for {} failed: {}", collection, e);
+                object_result(wait_error_status(&e), None)
+            },
+        };
+        Ok((answer_id, result))
+    }
+
+    async fn query(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        if let Some(result) = self.canned_result("query").await {
+            return Ok((answer_id, result));
+        }
+        let query_str = decode_string_arg(args, "query")?;
+        let variables = decode_string_arg(args, "variables")?;
+        let variables = if variables.is_empty() {
+            None
+        } else {
+            match serde_json::from_str::<Value>(&variables) {
+                Ok(vars) => Some(vars),
+                Err(_) => return Ok((answer_id, object_result(QueryStatus::FilterError, None))),
+            }
+        };
+        let result = query(
+            self.client.clone(),
+            ParamsOfQuery { query: query_str, variables },
+        )
+        .await;
+        let result = match result {
+            Ok(r) => match r.result.get("data") {
+                Some(data) => object_result(QueryStatus::Success, Some(data.clone())),
+                None => object_result(QueryStatus::PostProcessError, None),
+            },
+            Err(e) => {
+                debug!("query failed: {}", e);
+                object_result(QueryStatus::NetworkError, None)
+            },
+        };
+        Ok((answer_id, result))
+    }
+
+    /// Takes query result for `method` from pipechain if there is one.
+    async fn canned_result(&self, method: &str) -> Option<Value> {
+        self.processor
+            .write()
+            .await
+            .next_canned_result(ID, method)
+            .map(normalize_canned_result)
+    }
+}
+
+/// Parses query filter and merges pagination filter into it.
+fn build_filter(filter: &str, pagination: Option<&str>) -> Result<Value, String> {
+    let mut filter = parse_filter(filter)?;
+    if let Some(pagination) = pagination {
+        let pagination = parse_filter(pagination)?;
+        if let (Some(filter), Some(pagination)) = (filter.as_object_mut(), pagination.as_object()) {
+            for (key, value) in pagination {
+                filter.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    Ok(filter)
+}
+
+fn parse_filter(filter: &str) -> Result<Value, String> {
+    if filter.trim().is_empty() {
+        return Ok(Value::Object(Map::new()));
+    }
+    let value: Value = serde_json::from_str(filter)
+        .map_err(|e| format!("invalid filter: {}", e))?;
+    if !value.is_object() {
+        return Err(format!("filter must be an object"));
+    }
+    Ok(value)
+}
+
+fn set_id_gt(filter: &mut Value, last_id: &str) {
+    let id_filter = &mut filter["id"];
+    if !id_filter.is_object() {
+        *id_filter = json!({});
+    }
+    id_filter["gt"] = json!(last_id);
+}
+
+fn collection_result(status: QueryStatus, objects: Vec<Value>) -> Value {
+    let objects: Vec<String> = objects.iter().map(|obj| obj.to_string()).collect();
+    json!({ "status": status as u8, "objects": objects })
+}
+
+fn object_result(status: QueryStatus, object: Option<Value>) -> Value {
+    let object = object.map(|obj| obj.to_string()).unwrap_or_default();
+    json!({ "status": status as u8, "object": object })
+}
+
+/// Pipechain may contain objects in JSON form, but DeBot expects them
+/// serialized to strings.
+fn normalize_canned_result(mut params: Value) -> Value {
+    if params["status"].is_null() {
+        params["status"] = json!(QueryStatus::Success as u8);
+    }
+    if let Some(objects) = params["objects"].as_array_mut() {
+        for obj in objects.iter_mut() {
+            if !obj.is_string() {
+                *obj = json!(obj.to_string());
+            }
+        }
+    }
+    if let Some(object) = params.get_mut("object") {
+        if !object.is_string() {
+            *object = json!(object.to_string());
+        }
+    }
+    params
+}
+
+#[async_trait::async_trait]
+impl DebotInterface for Query {
+    fn get_id(&self) -> String {
+        ID.to_string()
+    }
+
+    fn get_abi(&self) -> Abi {
+        Abi::Json(ABI.to_owned())
+    }
+
+    async fn call(&self, func: &str, args: &Value) -> InterfaceResult {
+        match func {
+            "collection" => self.collection(args).await,
+            "waitForCollection" => self.wait_for_collection(args).await,
+            "query" => self.query(args).await,
+            _ => Err(format!("function \"{}\" is not implemented", func)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipechain::{ChainLink, DebotManifest};
+
+    #[test]
+    fn test_build_filter() {
+        let filter = build_filter(r#"{"balance":{"gt":"1000"}}"#, None).unwrap();
+        assert_eq!(filter, json!({ "balance": { "gt": "1000" } }));
+
+        let filter = build_filter(
+            r#"{"balance":{"gt":"1000"}}"#,
+            Some(r#"{"id":{"gt":"0:11"}}"#),
+        ).unwrap();
+        assert_eq!(filter, json!({ "balance": { "gt": "1000" }, "id": { "gt": "0:11" } }));
+
+        assert_eq!(build_filter("", None).unwrap(), json!({}));
+        assert!(build_filter("{balance", None).is_err());
+        assert!(build_filter("[1, 2]", None).is_err());
+        assert!(build_filter("{}", Some("12")).is_err());
+    }
+
+    #[test]
+    fn test_set_id_gt() {
+        let mut filter = json!({ "balance": { "gt": "1000" } });
+        set_id_gt(&mut filter, "0:11");
+        assert_eq!(filter["id"], json!({ "gt": "0:11" }));
+        set_id_gt(&mut filter, "0:22");
+        assert_eq!(filter["id"], json!({ "gt": "0:22" }));
+    }
+
+    #[test]
+    fn test_results() {
+        let result = collection_result(QueryStatus::Success, vec![json!({ "id": "0:11" })]);
+        assert_eq!(result, json!({ "status": 0, "objects": [r#"{"id":"0:11"}"#] }));
+        let result = object_result(QueryStatus::NotFound, None);
+        assert_eq!(result, json!({ "status": 4, "object": "" }));
+
+        let result = normalize_canned_result(json!({ "objects": [{ "id": "0:11" }, "{}"] }));
+        assert_eq!(result, json!({ "status": 0, "objects": [r#"{"id":"0:11"}"#, "{}"] }));
+        let result = normalize_canned_result(json!({ "status": 2 }));
+        assert_eq!(result["status"], 2);
+    }
+
+    #[test]
+    fn test_wait_error_status() {
+        let timeout = ClientError::with_code_message(607, "timeout".to_owned());
+        assert_eq!(wait_error_status(&timeout), QueryStatus::NotFound);
+        let failed = ClientError::with_code_message(603, "failed".to_owned());
+        assert_eq!(wait_error_status(&failed), QueryStatus::NetworkError);
+    }
+
+    #[test]
+    fn test_canned_result() {
+        let mut processor = ChainProcessor::new();
+        let mut manifest = DebotManifest::new();
+        manifest.quiet = true;
+        manifest.chain = vec![
+            ChainLink::Input {
+                interface: ID.to_owned(),
+                method: "collection".to_owned(),
+                params: Some(json!({ "objects": [{ "id": "0:11" }] })),
+                mandatory: false,
+            },
+            ChainLink::SigningBox { handle: 1 },
+        ];
+        processor.load_manifest(manifest);
+
+        assert!(processor.next_canned_result(ID, "query").is_none());
+        let result = processor.next_canned_result(ID, "collection").map(normalize_canned_result);
+        assert_eq!(result.unwrap(), json!({ "status": 0, "objects": [r#"{"id":"0:11"}"#] }));
+        assert!(processor.next_canned_result(ID, "collection").is_none());
+        assert_eq!(processor.next_signing_box().unwrap(), 1);
+    }
+}
@@ -0,0 +1,1004 @@
+/*
+* Copyright 2018-2021 TON DEV SOLUTIONS LTD.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific TON DEV software governing permissions and
+* limitations under the License.
+*/
+use super::dinterface::{
+    decode_answer_id, decode_arg, decode_bool_arg, decode_int256, decode_num_arg,
+    decode_string_arg,
+};
+use crate::helpers::{phrase_word_count, TonClient};
+use serde_json::{Value, json};
+use ton_client::abi::Abi;
+use ton_client::crypto::{
+    encryption_box_decrypt, encryption_box_encrypt, encryption_box_get_info,
+    generate_random_bytes, hdkey_derive_from_xprv, hdkey_derive_from_xprv_path,
+    hdkey_public_from_xprv, hdkey_secret_from_xprv, hdkey_xprv_from_mnemonic,
+    mnemonic_derive_sign_keys, mnemonic_from_random, nacl_box, nacl_box_keypair_from_secret_key,
+    nacl_box_open, nacl_sign_keypair_from_secret_key, signing_box_get_public_key,
+    signing_box_sign, EncryptionBoxHandle, ParamsOfEncryptionBoxDecrypt,
+    ParamsOfEncryptionBoxEncrypt, ParamsOfEncryptionBoxGetInfo, ParamsOfGenerateRandomBytes,
+    ParamsOfHDKeyDeriveFromXPrv, ParamsOfHDKeyDeriveFromXPrvPath, ParamsOfHDKeyPublicFromXPrv,
+    ParamsOfHDKeySecretFromXPrv, ParamsOfHDKeyXPrvFromMnemonic, ParamsOfMnemonicDeriveSignKeys,
+    ParamsOfMnemonicFromRandom, ParamsOfNaclBox, ParamsOfNaclBoxKeyPairFromSecret,
+    ParamsOfNaclBoxOpen, ParamsOfNaclSignKeyPairFromSecret, ParamsOfSigningBoxSign,
+    RegisteredSigningBox, SigningBoxHandle,
+};
+use ton_client::debot::{DebotInterface, InterfaceResult};
+use ton_client::encoding::decode_abi_bigint;
+use ton_client::error::ClientResult;
+use ton_client::net::{
+    query_collection, OrderBy, ParamsOfQueryCollection, ResultOfQueryCollection, SortDirection,
+};
+
+const ID: &'static str = "8fc6454f90072c9f1f6d3313ae1608f64f4a0660c6ae9f42c68b6a79e2a1bc4b";
+
+const ABI: &str = r#"
+{
+    "ABI version": 2,
+    "version": "2.2",
+    "header": ["time"],
+    "functions": [
+        {
+            "name": "getBalance",
+            "id": "0x0036b4f3",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"addr","type":"address"}
+            ],
+            "outputs": [
+                {"name":"nanotokens","type":"uint128"}
+            ]
+        },
+        {
+            "name": "getAccountType",
+            "id": "0x2b885111",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"addr","type":"address"}
+            ],
+            "outputs": [
+                {"name":"acc_type","type":"int8"}
+            ]
+        },
+        {
+            "name": "getAccountCodeHash",
+            "id": "0x38b68a99",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"addr","type":"address"}
+            ],
+            "outputs": [
+                {"name":"code_hash","type":"uint256"}
+            ]
+        },
+        {
+            "name": "getAccountsDataByHash",
+            "id": "0x2ff074fa",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"codeHash","type":"uint256"},
+                {"name":"gt","type":"address"}
+            ],
+            "outputs": [
+                {"components":[{"name":"id","type":"address"},{"name":"data","type":"cell"}],"name":"accounts","type":"tuple[]"}
+            ]
+        },
+        {
+            "name": "mnemonicFromRandom",
+            "id": "0x2f22913c",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"dict","type":"uint32"},
+                {"name":"wordCount","type":"uint32"}
+            ],
+            "outputs": [
+                {"name":"phrase","type":"string"}
+            ]
+        },
+        {
+            "name": "mnemonicDeriveSignKeys",
+            "id": "0x14f12d13",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"phrase","type":"string"},
+                {"name":"path","type":"string"}
+            ],
+            "outputs": [
+                {"name":"pub","type":"uint256"},
+                {"name":"sec","type":"uint256"}
+            ]
+        },
+        {
+            "name": "hdkeyXprvFromMnemonic",
+            "id": "0x3141b013",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"phrase","type":"string"}
+            ],
+            "outputs": [
+                {"name":"xprv","type":"string"}
+            ]
+        },
+        {
+            "name": "hdkeyDeriveFromXprv",
+            "id": "0x3df91936",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"inXprv","type":"string"},
+                {"name":"childIndex","type":"uint32"},
+                {"name":"hardened","type":"bool"}
+            ],
+            "outputs": [
+                {"name":"xprv","type":"string"}
+            ]
+        },
+        {
+            "name": "hdkeyDeriveFromXprvPath",
+            "id": "0x612b1f35",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"inXprv","type":"string"},
+                {"name":"path","type":"string"}
+            ],
+            "outputs": [
+                {"name":"xprv","type":"string"}
+            ]
+        },
+        {
+            "name": "hdkeySecretFromXprv",
+            "id": "0x24b30dc5",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"xprv","type":"string"}
+            ],
+            "outputs": [
+                {"name":"sec","type":"uint256"}
+            ]
+        },
+        {
+            "name": "hdkeyPublicFromXprv",
+            "id": "0x0c991027",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"xprv","type":"string"}
+            ],
+            "outputs": [
+                {"name":"pub","type":"uint256"}
+            ]
+        },
+        {
+            "name": "naclSignKeypairFromSecretKey",
+            "id": "0x5340824d",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"secret","type":"uint256"}
+            ],
+            "outputs": [
+                {"name":"sec","type":"uint256"},
+                {"name":"pub","type":"uint256"}
+            ]
+        },
+        {
+            "name": "naclBox",
+            "id": "0x7f9ee6c7",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"decrypted","type":"bytes"},
+                {"name":"nonce","type":"bytes"},
+                {"name":"publicKey","type":"uint256"},
+                {"name":"secretKey","type":"uint256"}
+            ],
+            "outputs": [
+                {"name":"encrypted","type":"bytes"}
+            ]
+        },
+        {
+            "name": "naclBoxOpen",
+            "id": "0x4151b21f",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"encrypted","type":"bytes"},
+                {"name":"nonce","type":"bytes"},
+                {"name":"publicKey","type":"uint256"},
+                {"name":"secretKey","type":"uint256"}
+            ],
+            "outputs": [
+                {"name":"decrypted","type":"bytes"}
+            ]
+        },
+        {
+            "name": "naclKeypairFromSecret",
+            "id": "0x21159daf",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"secret","type":"uint256"}
+            ],
+            "outputs": [
+                {"name":"publicKey","type":"uint256"},
+                {"name":"secretKey","type":"uint256"}
+            ]
+        },
+        {
+            "name": "genRandom",
+            "id": "0x05c672c3",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"length","type":"uint32"}
+            ],
+            "outputs": [
+                {"name":"buffer","type":"bytes"}
+            ]
+        },
+        {
+            "name": "substring",
+            "id": "0x56c2d6d7",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"str","type":"string"},
+                {"name":"start","type":"uint32"},
+                {"name":"count","type":"uint32"}
+            ],
+            "outputs": [
+                {"name":"substr","type":"string"}
+            ]
+        },
+        {
+            "name": "signHash",
+            "id": "0x422d1a4a",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"boxHandle","type":"uint32"},
+                {"name":"hash","type":"uint256"}
+            ],
+            "outputs": [
+                {"name":"signature","type":"bytes"}
+            ]
+        },
+        {
+            "name": "getSigningBoxInfo",
+            "id": "0x5e836915",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"boxHandle","type":"uint32"}
+            ],
+            "outputs": [
+                {"name":"result","type":"uint32"},
+                {"name":"key","type":"uint256"}
+            ]
+        },
+        {
+            "name": "encrypt",
+            "id": "0x1edf9b42",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"boxHandle","type":"uint32"},
+                {"name":"data","type":"bytes"}
+            ],
+            "outputs": [
+                {"name":"result","type":"uint32"},
+                {"name":"encrypted","type":"bytes"}
+            ]
+        },
+        {
+            "name": "decrypt",
+            "id": "0x6d1ab339",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"boxHandle","type":"uint32"},
+                {"name":"data","type":"bytes"}
+            ],
+            "outputs": [
+                {"name":"result","type":"uint32"},
+                {"name":"decrypted","type":"bytes"}
+            ]
+        },
+        {
+            "name": "getEncryptionBoxInfo",
+            "id": "0x6ce70176",
+            "inputs": [
+                {"name":"answerId","type":"uint32"},
+                {"name":"boxHandle","type":"uint32"}
+            ],
+            "outputs": [
+                {"name":"result","type":"uint32"},
+                {"components":[{"name":"hdpath","type":"string"},{"name":"algorithm","type":"string"},{"name":"options","type":"string"},{"name":"publicInfo","type":"string"}],"name":"info","type":"tuple"}
+            ]
+        },
+        {
+            "name": "constructor",
+            "id": "0x68b55f3f",
+            "inputs": [
+            ],
+            "outputs": [
+            ]
+        }
+    ],
+    "data": [
+    ],
+    "events": [
+    ],
+    "fields": [
+        {"name":"_pubkey","type":"uint256"},
+        {"name":"_timestamp","type":"uint64"},
+        {"name":"_constructorFlag","type":"bool"}
+    ]
+}
+"#;
+
+/// Account type returned by `getAccountType` if account doesn't exist.
+const ACC_TYPE_NON_EXIST: i64 = -1;
+
+/// Runs queries of account functions. Network is used by default.
+#[async_trait::async_trait]
+pub(crate) trait CollectionQuery {
+    async fn query_collection(&self, params: ParamsOfQueryCollection) -> ClientResult<ResultOfQueryCollection>;
+}
+
+struct NetworkQuery {
+    client: TonClient,
+}
+
+#[async_trait::async_trait]
+impl CollectionQuery for NetworkQuery {
+    async fn query_collection(&self, params: ParamsOfQueryCollection) -> ClientResult<ResultOfQueryCollection> {
+        query_collection(self.client.clone(), params).await
+    }
+}
+
+pub struct SdkInterface {
+    client: TonClient,
+    query: Box<dyn CollectionQuery + Send + Sync>,
+}
+
+impl SdkInterface {
+    pub fn new(client: TonClient) -> Self {
+        let query = Box::new(NetworkQuery { client: client.clone() });
+        Self::with_query(client, query)
+    }
+
+    pub(crate) fn with_query(client: TonClient, query: Box<dyn CollectionQuery + Send + Sync>) -> Self {
+        Self { client, query }
+    }
+
+    async fn get_balance(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let addr = decode_string_arg(args, "addr")?;
+        let accounts = self.query_account(&addr, "balance").await?;
+        Ok((answer_id, json!({ "nanotokens": account_balance(&accounts)? })))
+    }
+
+    async fn get_account_type(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let addr = decode_string_arg(args, "addr")?;
+        let accounts = self.query_account(&addr, "acc_type").await?;
+        Ok((answer_id, json!({ "acc_type": account_type(&accounts) })))
+    }
+
+    async fn get_account_code_hash(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let addr = decode_string_arg(args, "addr")?;
+        let accounts = self.query_account(&addr, "code_hash").await?;
+        Ok((answer_id, json!({ "code_hash": account_code_hash(&accounts) })))
+    }
+
+    async fn get_accounts_data_by_hash(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let code_hash = decode_uint256(args, "codeHash")?;
+        let gt = decode_string_arg(args, "gt")?;
+        let accounts = self
+            .query
+            .query_collection(ParamsOfQueryCollection {
+                collection: "accounts".to_owned(),
+                filter: Some(json!({
+                    "code_hash": { "eq": code_hash },
+                    "id": { "gt": gt },
+                })),
+                result: "id data".to_owned(),
+                order: Some(vec![OrderBy {
+                    path: "id".to_owned(),
+                    direction: SortDirection::ASC,
+                }]),
+                limit: None,
+            })
+            .await
+        .map_err(|e| format!("account query failed: {}", e))?
+        .result;
+        Ok((answer_id, json!({ "accounts": accounts_data(&accounts) })))
+    }
+
+    async fn query_account(&self, addr: &str, fields: &str) -> Result<Vec<Value>, String> {
+        self.query
+            .query_collection(ParamsOfQueryCollection {
+                collection: "accounts".to_owned(),
+                filter: Some(json!({ "id": { "eq": addr } })),
+                result: fields.to_owned(),
+                order: None,
+                limit: Some(1),
+            })
+            .await
+        .map(|r| r.result)
+        .map_err(|e| format!("account query failed: {}", e))
+    }
+
+    fn mnemonic_from_random(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let dict = decode_num_arg::<u8>(args, "dict")?;
+        let word_count = decode_num_arg::<u8>(args, "wordCount")?;
+        let result = mnemonic_from_random(
+            self.client.clone(),
+            ParamsOfMnemonicFromRandom {
+                dictionary: Some(dict),
+                word_count: Some(word_count),
+            },
+        )
+        .map_err(|e| format!("{}", e))?;
+        Ok((answer_id, json!({ "phrase": result.phrase })))
+    }
+
+    fn mnemonic_derive_sign_keys(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let phrase = decode_string_arg(args, "phrase")?;
+        let path = decode_string_arg(args, "path")?;
+        let keys = mnemonic_derive_sign_keys(
+            self.client.clone(),
+            ParamsOfMnemonicDeriveSignKeys {
+                word_count: Some(phrase_word_count(&phrase)),
+                phrase,
+                // client crypto config is used if not defined
+                path: if path.is_empty() { None } else { Some(path) },
+                dictionary: None,
+            },
+        )
+        .map_err(|e| format!("{}", e))?;
+        Ok((answer_id, json!({
+            "pub": format!("0x{}", keys.public),
+            "sec": format!("0x{}", keys.secret),
+        })))
+    }
+
+    fn hdkey_xprv_from_mnemonic(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let phrase = decode_string_arg(args, "phrase")?;
+        let result = hdkey_xprv_from_mnemonic(
+            self.client.clone(),
+            ParamsOfHDKeyXPrvFromMnemonic {
+                word_count: Some(phrase_word_count(&phrase)),
+                phrase,
+                dictionary: None,
+            },
+        )
+        .map_err(|e| format!("{}", e))?;
+        Ok((answer_id, json!({ "xprv": result.xprv })))
+    }
+
+    fn hdkey_derive_from_xprv(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let result = hdkey_derive_from_xprv(
+            self.client.clone(),
+            ParamsOfHDKeyDeriveFromXPrv {
+                xprv: decode_string_arg(args, "inXprv")?,
+                child_index: decode_num_arg::<u32>(args, "childIndex")?,
+                hardened: decode_bool_arg(args, "hardened")?,
+            },
+        )
+        .map_err(|e| format!("{}", e))?;
+        Ok((answer_id, json!({ "xprv": result.xprv })))
+    }
+
+    fn hdkey_derive_from_xprv_path(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let result = hdkey_derive_from_xprv_path(
+            self.client.clone(),
+            ParamsOfHDKeyDeriveFromXPrvPath {
+                xprv: decode_string_arg(args, "inXprv")?,
+                path: decode_string_arg(args, "path")?,
+            },
+        )
+        .map_err(|e| format!("{}", e))?;
+        Ok((answer_id, json!({ "xprv": result.xprv })))
+    }
+
+    fn hdkey_secret_from_xprv(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let result = hdkey_secret_from_xprv(
+            self.client.clone(),
+            ParamsOfHDKeySecretFromXPrv { xprv: decode_string_arg(args, "xprv")? },
+        )
+        .map_err(|e| format!("{}", e))?;
+        Ok((answer_id, json!({ "sec": format!("0x{}", result.secret) })))
+    }
+
+    fn hdkey_public_from_xprv(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let result = hdkey_public_from_xprv(
+            self.client.clone(),
+            ParamsOfHDKeyPublicFromXPrv { xprv: decode_string_arg(args, "xprv")? },
+        )
+        .map_err(|e| format!("{}", e))?;
+        Ok((answer_id, json!({ "pub": format!("0x{}", result.public) })))
+    }
+
+    fn nacl_sign_keypair_from_secret_key(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let keys = nacl_sign_keypair_from_secret_key(
+            self.client.clone(),
+            ParamsOfNaclSignKeyPairFromSecret { secret: decode_uint256(args, "secret")? },
+        )
+        .map_err(|e| format!("{}", e))?;
+        // secret key returned by nacl has public key appended.
+        Ok((answer_id, json!({
+            "sec": format!("0x{}", &keys.secret[..64]),
+            "pub": format!("0x{}", keys.public),
+        })))
+    }
+
+    fn nacl_box(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let result = nacl_box(
+            self.client.clone(),
+            ParamsOfNaclBox {
+                decrypted: hex_to_base64(&decode_arg(args, "decrypted")?)?,
+                nonce: decode_arg(args, "nonce")?,
+                their_public: decode_uint256(args, "publicKey")?,
+                secret: decode_uint256(args, "secretKey")?,
+            },
+        )
+        .map_err(|e| format!("{}", e))?;
+        Ok((answer_id, json!({ "encrypted": base64_to_hex(&result.encrypted)? })))
+    }
+
+    fn nacl_box_open(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let result = nacl_box_open(
+            self.client.clone(),
+            ParamsOfNaclBoxOpen {
+                encrypted: hex_to_base64(&decode_arg(args, "encrypted")?)?,
+                nonce: decode_arg(args, "nonce")?,
+                their_public: decode_uint256(args, "publicKey")?,
+                secret: decode_uint256(args, "secretKey")?,
+            },
+        )
+        .map_err(|e| format!("{}", e))?;
+        Ok((answer_id, json!({ "decrypted": base64_to_hex(&result.decrypted)? })))
+    }
+
+    fn nacl_keypair_from_secret(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let keys = nacl_box_keypair_from_secret_key(
+            self.client.clone(),
+            ParamsOfNaclBoxKeyPairFromSecret { secret: decode_uint256(args, "secret")? },
+        )
+        .map_err(|e| format!("{}", e))?;
+        Ok((answer_id, json!({
+            "publicKey": format!("0x{}", keys.public),
+            "secretKey": format!("0x{}", keys.secret),
+        })))
+    }
+
+    fn gen_random(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let length = decode_num_arg::<u32>(args, "length")?;
+        let result = generate_random_bytes(
+            self.client.clone(),
+            ParamsOfGenerateRandomBytes { length },
+        )
+        .map_err(|e| format!("{}", e))?;
+        Ok((answer_id, json!({ "buffer": base64_to_hex(&result.bytes)? })))
+    }
+
+    fn substring(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let string = decode_string_arg(args, "str")?;
+        let start = decode_num_arg::<usize>(args, "start")?;
+        let count = decode_num_arg::<usize>(args, "count")?;
+        let substr: String = string.chars().skip(start).take(count).collect();
+        Ok((answer_id, json!({ "substr": substr })))
+    }
+
+    async fn sign_hash(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let handle = decode_num_arg::<u32>(args, "boxHandle")?;
+        let hash = decode_uint256(args, "hash")?;
+        let result = signing_box_sign(
+            self.client.clone(),
+            ParamsOfSigningBoxSign {
+                signing_box: SigningBoxHandle(handle),
+                unsigned: hex_to_base64(&hash)?,
+            },
+        )
+        .await
+        .map_err(|e| format!("{}", e))?;
+        Ok((answer_id, json!({ "signature": result.signature })))
+    }
+
+    async fn get_signing_box_info(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let handle = decode_num_arg::<u32>(args, "boxHandle")?;
+        let result = signing_box_get_public_key(
+            self.client.clone(),
+            RegisteredSigningBox { handle: SigningBoxHandle(handle) },
+        )
+        .await;
+        Ok((answer_id, match result {
+            Ok(r) => json!({ "result": 0, "key": format!("0x{}", r.pubkey) }),
+            Err(e) => json!({ "result": e.code, "key": format!("0x{:064}", 0) }),
+        }))
+    }
+
+    async fn encrypt(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let handle = decode_num_arg::<u32>(args, "boxHandle")?;
+        let data = hex_to_base64(&decode_arg(args, "data")?)?;
+        let result = encryption_box_encrypt(
+            self.client.clone(),
+            ParamsOfEncryptionBoxEncrypt { encryption_box: EncryptionBoxHandle(handle), data },
+        )
+        .await;
+        Ok((answer_id, match result {
+            Ok(r) => json!({ "result": 0, "encrypted": base64_to_hex(&r.data)? }),
+            Err(e) => json!({ "result": e.code, "encrypted": "" }),
+        }))
+    }
+
+    async fn decrypt(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let handle = decode_num_arg::<u32>(args, "boxHandle")?;
+        let data = hex_to_base64(&decode_arg(args, "data")?)?;
+        let result = encryption_box_decrypt(
+            self.client.clone(),
+            ParamsOfEncryptionBoxDecrypt { encryption_box: EncryptionBoxHandle(handle), data },
+        )
+        .await;
+        Ok((answer_id, match result {
+            Ok(r) => json!({ "result": 0, "decrypted": base64_to_hex(&r.data)? }),
+            Err(e) => json!({ "result": e.code, "decrypted": "" }),
+        }))
+    }
+
+    async fn get_encryption_box_info(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let handle = decode_num_arg::<u32>(args, "boxHandle")?;
+        let result = encryption_box_get_info(
+            self.client.clone(),
+            ParamsOfEncryptionBoxGetInfo { encryption_box: EncryptionBoxHandle(handle) },
+        )
+        .await;
+        let (code, info) = match result {
+            Ok(r) => (0, r.info),
+            Err(e) => (e.code, Default::default()),
+        };
+        Ok((answer_id, json!({
+            "result": code,
+            "info": {
+                "hdpath": info.hdpath.unwrap_or_default(),
+                "algorithm": info.algorithm.unwrap_or_default(),
+                "options": info.options.map(|x| x.to_string()).unwrap_or_default(),
+                "publicInfo": info.public.map(|x| x.to_string()).unwrap_or_default(),
+            },
+        })))
+    }
+}
+
+/// Decodes uint256 argument into 64-digit hex string without prefix.
+fn decode_uint256(args: &Value, name: &str) -> Result<String, String> {
+    let num = decode_int256(args, name)?;
+    Ok(format!("{:064x}", num))
+}
+
+fn hex_to_base64(data: &str) -> Result<String, String> {
+    let bytes = hex::decode(data).map_err(|e| format!("invalid bytes: {}", e))?;
+    Ok(base64::encode(&bytes))
+}
+
+fn base64_to_hex(data: &str) -> Result<String, String> {
+    let bytes = base64::decode(data).map_err(|e| format!("invalid base64: {}", e))?;
+    Ok(hex::encode(&bytes))
+}
+
+fn account_balance(accounts: &[Value]) -> Result<String, String> {
+    match accounts.get(0).and_then(|acc| acc["balance"].as_str()) {
+        Some(balance) => decode_abi_bigint(balance)
+            .map(|x| x.to_string())
+            .map_err(|e| format!("failed to decode balance \"{}\": {}", balance, e)),
+        None => Ok("0".to_owned()),
+    }
+}
+
+fn account_type(accounts: &[Value]) -> i64 {
+    accounts
+        .get(0)
+        .and_then(|acc| acc["acc_type"].as_i64())
+        .unwrap_or(ACC_TYPE_NON_EXIST)
+}
+
+fn account_code_hash(accounts: &[Value]) -> String {
+    let hash = accounts
+        .get(0)
+        .and_then(|acc| acc["code_hash"].as_str())
+        .unwrap_or_default();
+    if hash.is_empty() {
+        format!("0x{:064}", 0)
+    } else {
+        format!("0x{}", hash)
+    }
+}
+
+fn accounts_data(accounts: &[Value]) -> Vec<Value> {
+    accounts
+        .iter()
+        .map(|acc| json!({
+            "id": acc["id"].as_str().unwrap_or_default(),
+            "data": acc["data"].as_str().unwrap_or_default(),
+        }))
+        .collect()
+}
+
+#[async_trait::async_trait]
+impl DebotInterface for SdkInterface {
+    fn get_id(&self) -> String {
+        ID.to_string()
+    }
+
+    fn get_abi(&self) -> Abi {
+        Abi::Json(ABI.to_owned())
+    }
+
+    async fn call(&self, func: &str, args: &Value) -> InterfaceResult {
+        match func {
+            "getBalance" => self.get_balance(args).await,
+            "getAccountType" => self.get_account_type(args).await,
+            "getAccountCodeHash" => self.get_account_code_hash(args).await,
+            "getAccountsDataByHash" => self.get_accounts_data_by_hash(args).await,
+            "mnemonicFromRandom" => self.mnemonic_from_random(args),
+            "mnemonicDeriveSignKeys" => self.mnemonic_derive_sign_keys(args),
+            "hdkeyXprvFromMnemonic" => self.hdkey_xprv_from_mnemonic(args),
+            "hdkeyDeriveFromXprv" => self.hdkey_derive_from_xprv(args),
+            "hdkeyDeriveFromXprvPath" => self.hdkey_derive_from_xprv_path(args),
+            "hdkeySecretFromXprv" => self.hdkey_secret_from_xprv(args),
+            "hdkeyPublicFromXprv" => self.hdkey_public_from_xprv(args),
+            "naclSignKeypairFromSecretKey" => self.nacl_sign_keypair_from_secret_key(args),
+            "naclBox" => self.nacl_box(args),
+            "naclBoxOpen" => self.nacl_box_open(args),
+            "naclKeypairFromSecret" => self.nacl_keypair_from_secret(args),
+            "genRandom" => self.gen_random(args),
+            "substring" => self.substring(args),
+            "signHash" => self.sign_hash(args).await,
+            "getSigningBoxInfo" => self.get_signing_box_info(args).await,
+            "encrypt" => self.encrypt(args).await,
+            "decrypt" => self.decrypt(args).await,
+            "getEncryptionBoxInfo" => self.get_encryption_box_info(args).await,
+            _ => Err(format!("function \"{}\" is not implemented", func)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::{create_client_local, HD_PATH};
+    use std::sync::{Arc, Mutex};
+    use ton_client::error::ClientError;
+
+    const ADDR: &str = "0:1111111111111111111111111111111111111111111111111111111111111111";
+    const OTHER_ADDR: &str = "0:2222222222222222222222222222222222222222222222222222222222222222";
+
+    const PHRASE: &str =
+        "abandon math mimic master filter design carbon crystal rookie group knife young";
+
+    fn sdk() -> SdkInterface {
+        SdkInterface::new(create_client_local().unwrap())
+    }
+
+    /// Returns `accounts` for every query and saves query params.
+    struct FakeQuery {
+        accounts: Option<Vec<Value>>,
+        params: Arc<Mutex<Vec<ParamsOfQueryCollection>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl CollectionQuery for FakeQuery {
+        async fn query_collection(&self, params: ParamsOfQueryCollection) -> ClientResult<ResultOfQueryCollection> {
+            self.params.lock().unwrap().push(params);
+            match &self.accounts {
+                Some(accounts) => Ok(ResultOfQueryCollection { result: accounts.clone() }),
+                None => Err(ClientError::with_code_message(603, "network is down".to_owned())),
+            }
+        }
+    }
+
+    /// Creates interface with queries answered by `accounts`. Queries fail
+    /// if `accounts` is `None`.
+    fn sdk_with_accounts(accounts: Option<Vec<Value>>) -> (SdkInterface, Arc<Mutex<Vec<ParamsOfQueryCollection>>>) {
+        let params = Arc::new(Mutex::new(vec![]));
+        let query = FakeQuery { accounts, params: params.clone() };
+        (SdkInterface::with_query(create_client_local().unwrap(), Box::new(query)), params)
+    }
+
+    #[test]
+    fn test_account_helpers() {
+        let accounts = vec![json!({
+            "id": "0:1111111111111111111111111111111111111111111111111111111111111111",
+            "balance": "0x3b9aca00",
+            "acc_type": 1,
+            "code_hash": "ab".repeat(32),
+            "data": "te6ccgEBAQEAAgAAAA==",
+        })];
+        assert_eq!(account_balance(&accounts).unwrap(), "1000000000");
+        assert_eq!(account_type(&accounts), 1);
+        assert_eq!(account_code_hash(&accounts), format!("0x{}", "ab".repeat(32)));
+        assert_eq!(accounts_data(&accounts), vec![json!({
+            "id": "0:1111111111111111111111111111111111111111111111111111111111111111",
+            "data": "te6ccgEBAQEAAgAAAA==",
+        })]);
+
+        assert_eq!(account_balance(&[]).unwrap(), "0");
+        assert_eq!(account_type(&[]), ACC_TYPE_NON_EXIST);
+        assert_eq!(account_code_hash(&[]), format!("0x{:064}", 0));
+        assert!(accounts_data(&[]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_account_functions() {
+        let account = json!({
+            "id": ADDR,
+            "balance": "0x3b9aca00",
+            "acc_type": 1,
+            "code_hash": "ab".repeat(32),
+        });
+        let (sdk, params) = sdk_with_accounts(Some(vec![account]));
+        let args = json!({ "answerId": "3", "addr": ADDR });
+
+        let (answer_id, result) = sdk.call("getBalance", &args).await.unwrap();
+        assert_eq!(answer_id, 3);
+        assert_eq!(result, json!({ "nanotokens": "1000000000" }));
+        let (_, result) = sdk.call("getAccountType", &args).await.unwrap();
+        assert_eq!(result, json!({ "acc_type": 1 }));
+        let (_, result) = sdk.call("getAccountCodeHash", &args).await.unwrap();
+        assert_eq!(result, json!({ "code_hash": format!("0x{}", "ab".repeat(32)) }));
+
+        let params = params.lock().unwrap();
+        assert_eq!(params.iter().map(|p| p.result.as_str()).collect::<Vec<_>>(), vec!["balance", "acc_type", "code_hash"]);
+        for p in params.iter() {
+            assert_eq!(p.collection, "accounts");
+            assert_eq!(p.filter, Some(json!({ "id": { "eq": ADDR } })));
+            assert_eq!(p.limit, Some(1));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_missing_account() {
+        let (sdk, _) = sdk_with_accounts(Some(vec![]));
+        let args = json!({ "answerId": "3", "addr": ADDR });
+        let (_, result) = sdk.call("getBalance", &args).await.unwrap();
+        assert_eq!(result, json!({ "nanotokens": "0" }));
+        let (_, result) = sdk.call("getAccountType", &args).await.unwrap();
+        assert_eq!(result, json!({ "acc_type": ACC_TYPE_NON_EXIST }));
+        let (_, result) = sdk.call("getAccountCodeHash", &args).await.unwrap();
+        assert_eq!(result, json!({ "code_hash": format!("0x{:064}", 0) }));
+    }
+
+    #[tokio::test]
+    async fn test_accounts_data_by_hash() {
+        let accounts = vec![
+            json!({ "id": ADDR, "data": "te6ccgEBAQEAAgAAAA==" }),
+            json!({ "id": OTHER_ADDR, "data": "te6ccgEBAQEAAgAAAA==" }),
+        ];
+        let (sdk, params) = sdk_with_accounts(Some(accounts.clone()));
+        let args = json!({ "answerId": "4", "codeHash": "0xab", "gt": ADDR });
+        let (answer_id, result) = sdk.call("getAccountsDataByHash", &args).await.unwrap();
+        assert_eq!(answer_id, 4);
+        assert_eq!(result, json!({ "accounts": accounts }));
+
+        let params = params.lock().unwrap();
+        assert_eq!(params[0].filter, Some(json!({
+            "code_hash": { "eq": format!("{:0>64}", "ab") },
+            "id": { "gt": ADDR },
+        })));
+        assert_eq!(params[0].result, "id data");
+        assert_eq!(params[0].limit, None);
+        let order = params[0].order.as_ref().unwrap();
+        assert_eq!(order[0].path, "id");
+        assert!(matches!(order[0].direction, SortDirection::ASC));
+    }
+
+    #[tokio::test]
+    async fn test_account_query_error() {
+        let (sdk, _) = sdk_with_accounts(None);
+        let args = json!({ "answerId": "3", "addr": ADDR, "codeHash": "0xab", "gt": ADDR });
+        for func in &["getBalance", "getAccountType", "getAccountCodeHash", "getAccountsDataByHash"] {
+            let err = sdk.call(func, &args).await.unwrap_err();
+            assert_eq!(err, "account query failed: network is down");
+        }
+    }
+
+    #[test]
+    fn test_decode_uint256() {
+        let args = json!({ "a": "0x0102", "b": "0x".to_owned() + &"ff".repeat(32) });
+        assert_eq!(decode_uint256(&args, "a").unwrap(), format!("{:0>64}", "102"));
+        assert_eq!(decode_uint256(&args, "b").unwrap(), "ff".repeat(32));
+        assert!(decode_uint256(&args, "c").is_err());
+    }
+
+    #[test]
+    fn test_substring() {
+        let args = json!({ "answerId": "1", "str": "hello world", "start": "6", "count": "5" });
+        let (_, result) = sdk().substring(&args).unwrap();
+        assert_eq!(result["substr"], "world");
+
+        let args = json!({ "answerId": "1", "str": "hello", "start": "3", "count": "10" });
+        let (_, result) = sdk().substring(&args).unwrap();
+        assert_eq!(result["substr"], "lo");
+    }
+
+    #[test]
+    fn test_mnemonic_and_hdkeys() {
+        let sdk = sdk();
+        let args = json!({ "answerId": "2", "dict": "1", "wordCount": "12" });
+        let (answer_id, result) = sdk.mnemonic_from_random(&args).unwrap();
+        assert_eq!(answer_id, 2);
+        assert_eq!(result["phrase"].as_str().unwrap().split(' ').count(), 12);
+
+        let args = json!({ "answerId": "3", "phrase": PHRASE });
+        let (_, result) = sdk.hdkey_xprv_from_mnemonic(&args).unwrap();
+        let xprv = result["xprv"].as_str().unwrap().to_owned();
+
+        let args = json!({ "answerId": "4", "inXprv": xprv, "path": HD_PATH });
+        let (_, result) = sdk.hdkey_derive_from_xprv_path(&args).unwrap();
+        let xprv = result["xprv"].as_str().unwrap().to_owned();
+
+        let (_, sec) = sdk.hdkey_secret_from_xprv(&json!({ "answerId": "5", "xprv": xprv })).unwrap();
+        let (_, public) = sdk.hdkey_public_from_xprv(&json!({ "answerId": "5", "xprv": xprv })).unwrap();
+
+        let args = json!({ "answerId": "6", "phrase": PHRASE, "path": "" });
+        let (_, keys) = sdk.mnemonic_derive_sign_keys(&args).unwrap();
+        assert_eq!(keys["sec"], sec["sec"]);
+        assert_eq!(keys["pub"], public["pub"]);
+
+        let args = json!({ "answerId": "7", "secret": sec["sec"] });
+        let (_, keys) = sdk.nacl_sign_keypair_from_secret_key(&args).unwrap();
+        assert_eq!(keys["sec"], sec["sec"]);
+        assert_eq!(keys["pub"], public["pub"]);
+    }
+
+    #[test]
+    fn test_nacl_box() {
+        let sdk = sdk();
+        let (_, alice) = sdk
+            .nacl_keypair_from_secret(&json!({ "answerId": "1", "secret": format!("0x{}", "01".repeat(32)) }))
+            .unwrap();
+        let (_, bob) = sdk
+            .nacl_keypair_from_secret(&json!({ "answerId": "1", "secret": format!("0x{}", "02".repeat(32)) }))
+            .unwrap();
+        let nonce = "cd".repeat(24);
+
+        let args = json!({
+            "answerId": "8",
+            "decrypted": hex::encode("secret message"),
+            "nonce": nonce,
+            "publicKey": bob["publicKey"],
+            "secretKey": alice["secretKey"],
+        });
+        let (_, result) = sdk.nacl_box(&args).unwrap();
+        let args = json!({
+            "answerId": "9",
+            "encrypted": result["encrypted"],
+            "nonce": nonce,
+            "publicKey": alice["publicKey"],
+            "secretKey": bob["secretKey"],
+        });
+        let (answer_id, result) = sdk.nacl_box_open(&args).unwrap();
+        assert_eq!(answer_id, 9);
+        assert_eq!(result["decrypted"], hex::encode("secret message"));
+
+        let (_, result) = sdk.gen_random(&json!({ "answerId": "1", "length": "32" })).unwrap();
+        assert_eq!(result["buffer"].as_str().unwrap().len(), 64);
+    }
+}
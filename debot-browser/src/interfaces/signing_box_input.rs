@@ -1,4 +1,6 @@
 use super::dinterface::{decode_answer_id, decode_array, decode_prompt};
+use crate::browser_io::SharedBrowserIo;
+use crate::config::SharedUserSettings;
 use crate::helpers::TonClient;
 use crate::{term_signing_box::TerminalSigningBox, ChainProcessor, ProcessorError};
 use serde_json::{Value, json};
@@ -53,10 +55,17 @@ pub struct SigningBoxInput {
     handles: RwLock<Vec<TerminalSigningBox>>,
     client: TonClient,
     processor: Arc<RwLock<ChainProcessor>>,
+    io: SharedBrowserIo,
+    settings: SharedUserSettings,
 }
 impl SigningBoxInput {
-    pub fn new(client: TonClient, processor: Arc<RwLock<ChainProcessor>>) -> Self {
-        Self { handles: RwLock::new(vec![]), client, processor }
+    pub fn new(
+        client: TonClient,
+        processor: Arc<RwLock<ChainProcessor>>,
+        io: SharedBrowserIo,
+        settings: SharedUserSettings,
+    ) -> Self {
+        Self { handles: RwLock::new(vec![]), client, processor, io, settings }
     }
 
     async fn get(&self, args: &Value) -> InterfaceResult {
@@ -70,12 +79,16 @@ impl SigningBoxInput {
                 Some(elem.as_str().unwrap().to_string())
             }
         )?;
-        println!("{}", prompt);
+        self.io.print(&prompt).await;
         let result = self.processor.write().await.next_signing_box();
         match result {
             Err(ProcessorError::InterfaceCallNeeded) => {
-                let signing_box = TerminalSigningBox::new::<&[u8]>(
-                    self.client.clone(), possible_keys, None
+                let (keystore, mnemonic) = {
+                    let settings = self.settings.read().await;
+                    (settings.keystore.clone(), settings.mnemonic.clone())
+                };
+                let signing_box = TerminalSigningBox::new(
+                    self.client.clone(), possible_keys, self.io.as_ref(), keystore.as_ref(), &mnemonic
                 ).await?;
                 let handle = signing_box.handle();
                 self.handles.write().await.push(signing_box);
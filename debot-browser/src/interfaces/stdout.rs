@@ -1,3 +1,4 @@
+use crate::browser_io::SharedBrowserIo;
 use serde_json::{Value, json};
 use ton_client::abi::Abi;
 use ton_client::debot::{DebotInterface, InterfaceResult};
@@ -30,15 +31,17 @@ pub const STDOUT_ABI: &str = r#"{
 	]
 }"#;
 
-pub struct Stdout {}
+pub struct Stdout {
+	io: SharedBrowserIo,
+}
 impl Stdout {
-	pub fn new() -> Self {
-		Self {}
+	pub fn new(io: SharedBrowserIo) -> Self {
+		Self { io }
 	}
-	pub fn print(&self, args: &Value) -> InterfaceResult {
+	pub async fn print(&self, args: &Value) -> InterfaceResult {
 		let text_vec = hex::decode(args["message"].as_str().unwrap()).unwrap();
 		let text = std::str::from_utf8(&text_vec).unwrap();
-		println!("{}", text);
+		self.io.print(text).await;
 		Ok((0, json!({})))
 	}
 }
@@ -55,7 +58,7 @@ impl DebotInterface for Stdout {
 
 	async fn call(&self, func: &str, args: &Value) -> InterfaceResult {
 		match func {
-			"print" => self.print(args),
+			"print" => self.print(args).await,
 			_ => Err(format!("function \"{}\" is not implemented", func)),
 		}
 	}
@@ -1,17 +1,18 @@
 use super::dinterface::{
     decode_answer_id, decode_bool_arg, decode_prompt, decode_string_arg, Printer,
 };
+use crate::browser_io::{input_valid, SharedBrowserIo};
 use crate::convert::convert_token;
-use crate::term_browser::terminal_input;
+use crate::helpers::TonClient;
+use num_bigint::BigInt;
 use serde_json::{Value, json};
-use std::io::Read;
-use ton_client::abi::Abi;
+use ton_client::abi::{decode_boc, Abi, AbiParam, ParamsOfDecodeBoc};
 use ton_client::debot::{DebotInterface, InterfaceResult};
 use ton_client::encoding::decode_abi_bigint;
 
-pub(super) const ID: &'static str = "8796536366ee21852db56dccb60bc564598b618c865fc50c8b1ab740bba128e3";
+pub(crate) const ID: &'static str = "8796536366ee21852db56dccb60bc564598b618c865fc50c8b1ab740bba128e3";
 
-const ABI: &str = r#"
+pub(crate) const ABI: &str = r#"
 {
 	"ABI version": 2,
 	"version": "2.2",
@@ -72,74 +73,58 @@ const ABI: &str = r#"
 "#;
 
 pub struct Terminal {
+    client: TonClient,
     printer: Printer,
+    io: SharedBrowserIo,
 }
 
 impl Terminal {
-    pub fn new(printer: Printer) -> Self {
-        Self {printer}
+    pub fn new(client: TonClient, printer: Printer, io: SharedBrowserIo) -> Self {
+        Self {client, printer, io}
     }
-    fn input_str(&self, args: &Value) -> InterfaceResult {
+    async fn input_str(&self, args: &Value) -> InterfaceResult {
         let answer_id = decode_answer_id(args)?;
         let prompt = decode_prompt(args)?;
         let multiline = decode_bool_arg(args, "multiline")?;
-        let mut value = String::new();
-        if multiline {
-            println!("{}", &prompt);
-            if cfg!(windows) {
-                println!("(Ctrl+Z to exit)");
-            } else {
-                println!("(Ctrl+D to exit)");
-            }
-            std::io::stdin().read_to_string(&mut value)
-                .map_err(|e| format!("input error: {}", e))?;
-            println!();
+        let value = if multiline {
+            self.io.input_multiline(&prompt).await?
         } else {
-            value = terminal_input(&prompt, |_val| Ok(()));
-        }
+            self.io.input(&prompt).await?
+        };
         Ok((answer_id, json!({ "value": value })))
     }
 
-    fn input_int(&self, args: &Value) -> InterfaceResult {
+    async fn input_int(&self, args: &Value) -> InterfaceResult {
         let answer_id = decode_answer_id(args)?;
-        let value = terminal_input(&decode_prompt(args)?, |val| {
+        let value = input_valid(self.io.as_ref(), &decode_prompt(args)?, |val| {
             let _ = decode_abi_bigint(val).map_err(|e| format!("{}", e))?;
             Ok(())
-        });
+        }).await?;
         Ok((answer_id, json!({ "value": value })))
     }
 
-    fn input_uint(&self, args: &Value) -> InterfaceResult {
+    async fn input_uint(&self, args: &Value) -> InterfaceResult {
         let answer_id = decode_answer_id(args)?;
-        let value = terminal_input(&decode_prompt(args)?, |val| {
+        let value = input_valid(self.io.as_ref(), &decode_prompt(args)?, |val| {
             let _ = decode_abi_bigint(val).map_err(|e| format!("{}", e))?;
             Ok(())
-        });
+        }).await?;
         Ok((answer_id, json!({ "value": value })))
     }
 
-    fn input_tokens(&self, args: &Value) -> InterfaceResult {
+    async fn input_tokens(&self, args: &Value) -> InterfaceResult {
         let answer_id = decode_answer_id(args)?;
         let mut nanotokens = String::new();
-        let _ = terminal_input(&decode_prompt(args)?, |val| {
+        input_valid(self.io.as_ref(), &decode_prompt(args)?, |val| {
             nanotokens = convert_token(val)?;
             Ok(())
-        });
+        }).await?;
         Ok((answer_id, json!({ "value": nanotokens })))
     }
 
-    fn input_boolean(&self, args: &Value) -> InterfaceResult {
+    async fn input_boolean(&self, args: &Value) -> InterfaceResult {
         let answer_id = decode_answer_id(args)?;
-        println!("{}", decode_prompt(args)?);
-        let mut yes_no = false;
-        let _ = terminal_input("(y/n)", |val| {
-            yes_no = match val.as_str() {
-                "y" => true,
-                "n" => false,
-                _ => Err(format!("invalid enter"))?,
-            };
-            Ok(())
-        });
+        let yes_no = self.io.confirm(&decode_prompt(args)?).await?;
         Ok((answer_id, json!({ "value": yes_no })))
     }
 
@@ -149,6 +134,142 @@ impl Terminal {
 		self.printer.print(&message).await;
 		Ok((answer_id, json!({})))
     }
+
+    pub async fn printf(&self, args: &Value) -> InterfaceResult {
+        let answer_id = decode_answer_id(args)?;
+        let fmt = decode_string_arg(args, "fmt")?;
+        let fargs = decode_string_arg(args, "fargs")?;
+        let pieces = parse_format(&fmt);
+        let params = format_params(&pieces)?;
+        let data = if params.is_empty() {
+            json!({})
+        } else {
+            decode_boc(
+                self.client.clone(),
+                ParamsOfDecodeBoc { params, boc: fargs, allow_partial: true },
+            )
+            .await
+            .map_err(|e| format!("failed to decode printf arguments: {}", e))?
+            .data
+        };
+        let message = format_message(&pieces, &data)?;
+        self.printer.print(&message).await;
+        Ok((answer_id, json!({})))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ArgKind {
+    Number,
+    Tokens,
+    Address,
+    String,
+    Bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum Piece {
+    Text(String),
+    /// Placeholder with ABI type of the argument.
+    Arg(ArgKind, String),
+}
+
+fn placeholder(spec: &str) -> Option<Piece> {
+    let (kind, abi_type) = match spec {
+        "uint" => (ArgKind::Number, "uint256".to_owned()),
+        "int" => (ArgKind::Number, "int256".to_owned()),
+        "tokens" => (ArgKind::Tokens, "uint128".to_owned()),
+        "address" => (ArgKind::Address, "address".to_owned()),
+        "string" => (ArgKind::String, "string".to_owned()),
+        "bool" => (ArgKind::Bool, "bool".to_owned()),
+        _ => {
+            let size = spec
+                .strip_prefix("uint")
+                .or_else(|| spec.strip_prefix("int"))?
+                .parse::<u16>()
+                .ok()?;
+            if size == 0 || size > 256 {
+                return None;
+            }
+            (ArgKind::Number, spec.to_owned())
+        }
+    };
+    Some(Piece::Arg(kind, abi_type))
+}
+
+/// Splits format string into text and `{type}` placeholders. Braces which
+/// don't form a known placeholder are printed as is.
+fn parse_format(fmt: &str) -> Vec<Piece> {
+    let mut pieces = vec![];
+    let mut text = String::new();
+    let mut rest = fmt;
+    while let Some(start) = rest.find('{') {
+        text += &rest[..start];
+        rest = &rest[start..];
+        let arg = rest.find('}').and_then(|end| placeholder(&rest[1..end]).map(|p| (p, end)));
+        match arg {
+            Some((piece, end)) => {
+                if !text.is_empty() {
+                    pieces.push(Piece::Text(std::mem::take(&mut text)));
+                }
+                pieces.push(piece);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                text.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    text += rest;
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+    pieces
+}
+
+fn format_params(pieces: &[Piece]) -> Result<Vec<AbiParam>, String> {
+    let mut params = vec![];
+    for piece in pieces {
+        if let Piece::Arg(_, abi_type) = piece {
+            let param = json!({ "name": format!("arg{}", params.len()), "type": abi_type });
+            params.push(serde_json::from_value(param).map_err(|e| format!("{}", e))?);
+        }
+    }
+    Ok(params)
+}
+
+fn format_message(pieces: &[Piece], data: &Value) -> Result<String, String> {
+    let mut message = String::new();
+    let mut index = 0;
+    for piece in pieces {
+        match piece {
+            Piece::Text(text) => message += text,
+            Piece::Arg(kind, _) => {
+                let name = format!("arg{}", index);
+                index += 1;
+                message += &format_arg(*kind, &data[&name])
+                    .ok_or(format!("printf argument {} is missing or invalid", index))?;
+            }
+        }
+    }
+    Ok(message)
+}
+
+fn format_arg(kind: ArgKind, value: &Value) -> Option<String> {
+    match kind {
+        ArgKind::Number => decode_abi_bigint(value.as_str()?).ok().map(|x| x.to_string()),
+        ArgKind::Tokens => decode_abi_bigint(value.as_str()?).ok().map(|x| format_tokens(&x)),
+        ArgKind::Address | ArgKind::String => value.as_str().map(|x| x.to_owned()),
+        ArgKind::Bool => value.as_bool().map(|x| x.to_string()),
+    }
+}
+
+fn format_tokens(nanotokens: &BigInt) -> String {
+    let nano = BigInt::from(1_000_000_000u64);
+    let integer = nanotokens / &nano;
+    let fraction = nanotokens % &nano;
+    format!("{}.{:0>9}", integer, fraction)
 }
 
 #[async_trait::async_trait]
@@ -163,14 +284,104 @@ impl DebotInterface for Terminal {
 
     async fn call(&self, func: &str, args: &Value) -> InterfaceResult {
         match func {
-            "input" => self.input_str(args),
-            "inputStr" => self.input_str(args),
-            "inputInt" => self.input_int(args),
-            "inputUint" => self.input_uint(args),
-            "inputTons" => self.input_tokens(args),
-            "inputBoolean" => self.input_boolean(args),
+            "input" => self.input_str(args).await,
+            "inputStr" => self.input_str(args).await,
+            "inputInt" => self.input_int(args).await,
+            "inputUint" => self.input_uint(args).await,
+            "inputTons" => self.input_tokens(args).await,
+            "inputBoolean" => self.input_boolean(args).await,
             "print" => self.print(args).await,
+            "printf" => self.printf(args).await,
             _ => Err(format!("function \"{}\" is not implemented", func)),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_format() {
+        let pieces = parse_format("Balance of {address}: {tokens} ({uint64}) {x} {bool}");
+        assert_eq!(pieces, vec![
+            Piece::Text("Balance of ".to_owned()),
+            Piece::Arg(ArgKind::Address, "address".to_owned()),
+            Piece::Text(": ".to_owned()),
+            Piece::Arg(ArgKind::Tokens, "uint128".to_owned()),
+            Piece::Text(" (".to_owned()),
+            Piece::Arg(ArgKind::Number, "uint64".to_owned()),
+            Piece::Text(") {x} ".to_owned()),
+            Piece::Arg(ArgKind::Bool, "bool".to_owned()),
+        ]);
+        assert_eq!(parse_format("{int}{"), vec![
+            Piece::Arg(ArgKind::Number, "int256".to_owned()),
+            Piece::Text("{".to_owned()),
+        ]);
+        assert_eq!(parse_format("{uint512}"), vec![Piece::Text("{uint512}".to_owned())]);
+        assert!(parse_format("").is_empty());
+    }
+
+    #[test]
+    fn test_format_message() {
+        let pieces = parse_format("{string}: {tokens}, {int8}, {uint}, {bool}");
+        let data = json!({
+            "arg0": "wallet",
+            "arg1": "1500000000",
+            "arg2": "-5",
+            "arg3": "0x0100",
+            "arg4": false,
+        });
+        assert_eq!(
+            format_message(&pieces, &data).unwrap(),
+            "wallet: 1.500000000, -5, 256, false"
+        );
+        assert!(format_message(&pieces, &json!({ "arg0": "wallet" })).is_err());
+        assert_eq!(format_params(&pieces).unwrap().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_printf() {
+        use crate::browser_io::ScriptedIo;
+        use crate::helpers::create_client_local;
+        use crate::ChainProcessor;
+        use std::sync::Arc;
+        use tokio::sync::RwLock;
+        use ton_client::abi::{encode_boc, ParamsOfAbiEncodeBoc};
+
+        let client = create_client_local().unwrap();
+        let io = Arc::new(ScriptedIo::new(Vec::<String>::new()));
+        let printer = Printer::new(Arc::new(RwLock::new(ChainProcessor::new())), io.clone());
+        let terminal = Terminal::new(client.clone(), printer, io.clone());
+
+        let fmt = "{string} has {tokens} at {address}: {uint64}, {bool}";
+        let address = "0:1111111111111111111111111111111111111111111111111111111111111111";
+        let fargs = encode_boc(
+            client,
+            ParamsOfAbiEncodeBoc {
+                params: format_params(&parse_format(fmt)).unwrap(),
+                data: json!({
+                    "arg0": "wallet",
+                    "arg1": "1500000000",
+                    "arg2": address,
+                    "arg3": "42",
+                    "arg4": true,
+                }),
+                boc_cache: None,
+            },
+        )
+        .await
+        .unwrap()
+        .boc;
+        let args = json!({ "answerId": "7", "fmt": fmt, "fargs": fargs });
+        let (answer_id, _) = terminal.printf(&args).await.unwrap();
+        assert_eq!(answer_id, 7);
+        assert_eq!(
+            io.output(),
+            vec![format!("wallet has 1.500000000 at {}: 42, true", address)]
+        );
+
+        let args = json!({ "answerId": "7", "fmt": fmt, "fargs": "te6ccgEBAQEAAgAAAA==" });
+        assert!(terminal.printf(&args).await.is_err());
+    }
+}
@@ -1,4 +1,5 @@
 use super::dinterface::decode_answer_id;
+use crate::browser_io::SharedBrowserIo;
 use crate::config::SharedUserSettings;
 use crate::term_signing_box::TerminalSigningBox;
 use crate::helpers::TonClient;
@@ -68,10 +69,11 @@ const ABI: &str = r#"
 pub struct UserInfo {
     _client: TonClient,
     settings: SharedUserSettings,
+    io: SharedBrowserIo,
 }
 impl UserInfo {
-    pub fn new(client: TonClient, settings: SharedUserSettings) -> Self {
-        Self { _client: client, settings }
+    pub fn new(client: TonClient, settings: SharedUserSettings, io: SharedBrowserIo) -> Self {
+        Self { _client: client, settings, io }
     }
 
     async fn get_account(&self, args: &Value) -> InterfaceResult {
@@ -112,9 +114,28 @@ impl UserInfo {
                 None
             }
         };
+        let handle = match handle {
+            Some(handle) => Some(handle),
+            None => self.keystore_signing_box().await?,
+        };
         Ok((answer_id, json!({ "handle": handle.unwrap_or_default()})))
     }
 
+    /// Unlocks user key from keystore. `None` if key is not defined.
+    async fn keystore_signing_box(&self) -> Result<Option<u32>, String> {
+        let (keystore, key) = {
+            let settings = self.settings.read().await;
+            (settings.keystore.clone(), settings.key.clone())
+        };
+        let (keystore, key) = match (keystore, key) {
+            (Some(keystore), Some(key)) => (keystore, key),
+            _ => return Ok(None),
+        };
+        let keys = keystore.unlock_interactive(&key, self.io.as_ref(), 3).await?;
+        let mut signing_box = TerminalSigningBox::new_with_keys(self._client.clone(), keys).await?;
+        Ok(Some(signing_box.leak().0))
+    }
+
 }
 
 #[async_trait::async_trait]
@@ -0,0 +1,300 @@
+/*
+* Copyright 2018-2021 TON DEV SOLUTIONS LTD.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific TON DEV software governing permissions and
+* limitations under the License.
+*/
+use crate::browser_io::BrowserIo;
+use crate::helpers::{create_client_local, normalize_key, TonClient};
+use serde::{Deserialize, Serialize};
+use ton_client::crypto::{
+    chacha20, generate_random_bytes, nacl_sign_keypair_from_secret_key, scrypt, KeyPair,
+    ParamsOfChaCha20, ParamsOfGenerateRandomBytes, ParamsOfNaclSignKeyPairFromSecret,
+    ParamsOfScrypt,
+};
+
+const KEYSTORE_VERSION: u8 = 1;
+const SALT_LEN: u32 = 32;
+const NONCE_LEN: u32 = 12;
+
+fn default_log_n() -> u8 { 14 }
+fn default_r() -> u32 { 8 }
+fn default_p() -> u32 { 1 }
+
+/// Keypair sealed with a passphrase. Encryption key is derived from the
+/// passphrase with scrypt, secret key is encrypted with chacha20.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyStoreEntry {
+    pub name: String,
+    /// Public key in hex. Used to find entry and to check passphrase.
+    pub public: String,
+    /// Encrypted secret key, encoded with base64.
+    pub secret: String,
+    /// Scrypt salt, encoded with base64.
+    pub salt: String,
+    /// Chacha20 nonce, encoded with hex.
+    pub nonce: String,
+    #[serde(default = "default_log_n")]
+    pub log_n: u8,
+    #[serde(default = "default_r")]
+    pub r: u32,
+    #[serde(default = "default_p")]
+    pub p: u32,
+}
+
+/// Named keypairs encrypted with passphrases.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyStore {
+    pub version: u8,
+    #[serde(default)]
+    pub entries: Vec<KeyStoreEntry>,
+}
+
+impl Default for KeyStore {
+    fn default() -> Self {
+        Self { version: KEYSTORE_VERSION, entries: vec![] }
+    }
+}
+
+fn random_bytes(client: TonClient, length: u32) -> Result<Vec<u8>, String> {
+    let bytes = generate_random_bytes(client, ParamsOfGenerateRandomBytes { length })
+        .map_err(|e| format!("failed to generate random bytes: {}", e))?
+        .bytes;
+    base64::decode(&bytes).map_err(|e| format!("failed to decode random bytes: {}", e))
+}
+
+/// Derives hex encoded 256-bit key from the passphrase.
+fn derive_key(client: TonClient, entry: &KeyStoreEntry, passphrase: &str) -> Result<String, String> {
+    scrypt(
+        client,
+        ParamsOfScrypt {
+            password: base64::encode(passphrase.as_bytes()),
+            salt: entry.salt.clone(),
+            log_n: entry.log_n,
+            r: entry.r,
+            p: entry.p,
+            dk_len: 32,
+        },
+    )
+    .map(|r| r.key)
+    .map_err(|e| format!("failed to derive key from passphrase: {}", e))
+}
+
+/// Chacha20 is symmetric: the same call encrypts and decrypts.
+fn apply_cipher(client: TonClient, key: String, nonce: String, data: String) -> Result<String, String> {
+    chacha20(client, ParamsOfChaCha20 { data, key, nonce })
+        .map(|r| r.data)
+        .map_err(|e| format!("failed to encrypt key: {}", e))
+}
+
+/// Returns public key of the ed25519 secret key.
+fn public_key(client: TonClient, secret: &str) -> Result<String, String> {
+    nacl_sign_keypair_from_secret_key(
+        client,
+        ParamsOfNaclSignKeyPairFromSecret { secret: secret.to_owned(), ..Default::default() },
+    )
+    .map(|pair| pair.public)
+    .map_err(|e| format!("invalid secret key: {}", e))
+}
+
+impl KeyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read keystore: {}", e))?;
+        Self::from_json(&text)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn to_file(&self, path: &str) -> Result<(), String> {
+        let text = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("failed to serialize keystore: {}", e))?;
+        std::fs::write(path, text).map_err(|e| format!("failed to write keystore: {}", e))
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        let keystore: KeyStore = serde_json::from_str(text)
+            .map_err(|e| format!("invalid keystore: {}", e))?;
+        if keystore.version != KEYSTORE_VERSION {
+            return Err(format!("unsupported keystore version {}", keystore.version));
+        }
+        Ok(keystore)
+    }
+
+    /// Returns names and public keys of all entries.
+    pub fn list(&self) -> Vec<(String, String)> {
+        self.entries.iter().map(|e| (e.name.clone(), e.public.clone())).collect()
+    }
+
+    /// Finds entry by name or public key (with or without `0x` prefix).
+    pub fn find(&self, name_or_pubkey: &str) -> Option<&KeyStoreEntry> {
+        self.entries.iter().find(|e| e.name == name_or_pubkey).or_else(|| {
+            let key = normalize_key(name_or_pubkey);
+            self.entries.iter().find(|e| normalize_key(&e.public) == key)
+        })
+    }
+
+    /// Finds entry with one of the public keys.
+    pub fn find_any(&self, pubkeys: &[String]) -> Option<&KeyStoreEntry> {
+        pubkeys.iter().find_map(|key| {
+            let key = normalize_key(key);
+            self.entries.iter().find(|e| normalize_key(&e.public) == key)
+        })
+    }
+
+    /// Encrypts keypair with the passphrase and adds it under the `name`.
+    pub fn create(&mut self, name: &str, keys: &KeyPair, passphrase: &str) -> Result<(), String> {
+        if name.is_empty() {
+            return Err(format!("key name is empty"));
+        }
+        if self.find(name).is_some() {
+            return Err(format!("key \"{}\" already exists", name));
+        }
+        let client = create_client_local()?;
+        if normalize_key(&public_key(client.clone(), &keys.secret)?) != normalize_key(&keys.public) {
+            return Err(format!("public key does not match secret key"));
+        }
+        let mut entry = KeyStoreEntry {
+            name: name.to_owned(),
+            public: keys.public.clone(),
+            secret: String::new(),
+            salt: base64::encode(&random_bytes(client.clone(), SALT_LEN)?),
+            nonce: hex::encode(&random_bytes(client.clone(), NONCE_LEN)?),
+            log_n: default_log_n(),
+            r: default_r(),
+            p: default_p(),
+        };
+        let secret = hex::decode(&keys.secret)
+            .map_err(|e| format!("invalid secret key: {}", e))?;
+        let key = derive_key(client.clone(), &entry, passphrase)?;
+        entry.secret = apply_cipher(client, key, entry.nonce.clone(), base64::encode(&secret))?;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<(), String> {
+        let len = self.entries.len();
+        self.entries.retain(|e| e.name != name);
+        if self.entries.len() == len {
+            return Err(format!("key \"{}\" not found", name));
+        }
+        Ok(())
+    }
+
+    /// Decrypts keypair found by name or public key.
+    pub fn unlock(&self, name_or_pubkey: &str, passphrase: &str) -> Result<KeyPair, String> {
+        let entry = self
+            .find(name_or_pubkey)
+            .ok_or(format!("key \"{}\" not found in keystore", name_or_pubkey))?;
+        let client = create_client_local()?;
+        let key = derive_key(client.clone(), entry, passphrase)?;
+        let secret = apply_cipher(client.clone(), key, entry.nonce.clone(), entry.secret.clone())?;
+        let secret = base64::decode(&secret)
+            .map_err(|e| format!("failed to decode secret key: {}", e))
+            .map(hex::encode)?;
+        // chacha20 has no authentication tag: wrong passphrase gives another key.
+        if public_key(client, &secret).ok().map(|k| normalize_key(&k)) != Some(normalize_key(&entry.public)) {
+            return Err(format!("invalid passphrase for key \"{}\"", entry.name));
+        }
+        Ok(KeyPair { public: entry.public.clone(), secret })
+    }
+
+    /// Asks user for passphrase and decrypts keypair. User has `tries` attempts.
+    pub async fn unlock_interactive(
+        &self,
+        name_or_pubkey: &str,
+        io: &(dyn BrowserIo + Send + Sync),
+        tries: u8,
+    ) -> Result<KeyPair, String> {
+        let name = self
+            .find(name_or_pubkey)
+            .map(|e| e.name.clone())
+            .ok_or(format!("key \"{}\" not found in keystore", name_or_pubkey))?;
+        let mut result = Err(format!("key \"{}\" is not unlocked", name));
+        for _ in 0..tries {
+            let passphrase = io.secret(&format!("enter passphrase for key \"{}\"", name)).await?;
+            result = self.unlock(&name, &passphrase);
+            match &result {
+                Ok(_) => break,
+                Err(e) => io.print(&format!("{}. Try again.", e)).await,
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::browser_io::ScriptedIo;
+    use serde_json::json;
+
+    const PUBLIC: &str = "9711a04f0b19474272bc7bae5472a8fbbb6ef71ce9c193f5ec3f5af808069a41";
+    const PRIVATE: &str = "cdf2a820517fa783b9b6094d15e650af92d485084ab217fc2c859f02d49623f3";
+
+    fn entry(name: &str, public: &str) -> KeyStoreEntry {
+        KeyStoreEntry {
+            name: name.to_owned(),
+            public: public.to_owned(),
+            secret: String::new(),
+            salt: String::new(),
+            nonce: String::new(),
+            log_n: default_log_n(),
+            r: default_r(),
+            p: default_p(),
+        }
+    }
+
+    #[test]
+    fn test_find() {
+        let keystore: KeyStore = KeyStore::from_json(&json!({
+            "version": 1,
+            "entries": [
+                { "name": "main", "public": PUBLIC, "secret": "", "salt": "", "nonce": "" }
+            ]
+        }).to_string()).unwrap();
+        assert_eq!(keystore.entries[0], entry("main", PUBLIC));
+        assert_eq!(keystore.find("main").unwrap().public, PUBLIC);
+        assert_eq!(keystore.find(&format!("0x{}", PUBLIC.to_uppercase())).unwrap().name, "main");
+        assert!(keystore.find("other").is_none());
+        assert_eq!(keystore.find_any(&["0x11".to_owned(), format!("0x{}", PUBLIC)]).unwrap().name, "main");
+        assert_eq!(keystore.list(), vec![("main".to_owned(), PUBLIC.to_owned())]);
+
+        assert!(KeyStore::from_json(r#"{"version": 2}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_and_unlock() {
+        let mut keystore = KeyStore::new();
+        let keys = KeyPair { public: PUBLIC.to_owned(), secret: PRIVATE.to_owned() };
+        keystore.create("main", &keys, "passw0rd").unwrap();
+        assert!(keystore.create("main", &keys, "passw0rd").is_err());
+        let mismatched = KeyPair { public: PUBLIC.replace("97", "00"), secret: PRIVATE.to_owned() };
+        let err = keystore.create("other", &mismatched, "passw0rd").unwrap_err();
+        assert!(err.contains("does not match"));
+        assert_ne!(keystore.entries[0].secret, base64::encode(&hex::decode(PRIVATE).unwrap()));
+
+        let unlocked = keystore.unlock(PUBLIC, "passw0rd").unwrap();
+        assert_eq!(unlocked.secret, PRIVATE);
+        assert!(keystore.unlock("main", "wrong").is_err());
+
+        let io = ScriptedIo::new(vec!["wrong", "passw0rd"]);
+        let unlocked = keystore.unlock_interactive("main", &io, 2).await.unwrap();
+        assert_eq!(unlocked.public, PUBLIC);
+
+        keystore.remove("main").unwrap();
+        assert!(keystore.remove("main").is_err());
+    }
+}
@@ -11,18 +11,31 @@
 * limitations under the License.
 */
 mod api;
+pub mod browser_io;
 mod callbacks;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cli;
 mod config;
 mod convert;
 mod crypto;
+mod dapp_encryption_box;
 mod dapp_signing_box;
+pub mod events;
+pub mod debot_cache;
 mod helpers;
+pub mod http_client;
 mod interfaces;
+pub mod keystore;
+pub mod local_engine;
+pub mod loop_guard;
 mod pipechain;
+pub mod policy;
 mod processor;
+pub mod run_report;
 pub mod term_browser;
 mod term_encryption_box;
 mod term_signing_box;
+pub mod trust;
 mod utils;
 
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
@@ -31,20 +44,24 @@ mod utils;
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
+#[cfg(target_arch = "wasm32")]
+pub use api::set_debot_cache_storage;
 pub use api::{
-    close_signing_box, create_browser, destroy_browser, generate_keypair, init_log,
-    register_signing_box, run_browser, run_debot_browser, sign, signing_box_public_key,
-    update_user_settings, scrypt, sha256, chacha20, generate_random_bytes
+    clear_debot_cache, close_encryption_box, close_signing_box, create_browser, destroy_browser, generate_keypair,
+    get_recorded_manifest, get_run_report, init_log, keystore_add_key, keystore_list_keys,
+    keystore_unlock_key, register_encryption_box, register_signing_box, run_browser, run_debot_browser, sign, signing_box_public_key,
+    start_recording, update_user_settings, scrypt, sha256, chacha20, generate_random_bytes
 };
 use callbacks::Callbacks;
 pub use interfaces::dinterface::SupportedInterfaces;
 use log::{Level, Metadata, Record};
 use pipechain::{ApproveKind, ChainLink, DebotManifest};
 use processor::{ChainProcessor, ProcessorError};
-use term_browser::{action_input, input, terminal_input, TerminalBrowser};
+use term_browser::TerminalBrowser;
 
 type BrowserHandle = u64;
 type SigningBoxHandle = u32;
+type EncryptionBoxHandle = u32;
 
 struct DeBotBrowserLogger;
 static LOGGER: DeBotBrowserLogger = DeBotBrowserLogger;
@@ -0,0 +1,530 @@
+/*
+* Copyright 2018-2021 TON DEV SOLUTIONS LTD.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific TON DEV software governing permissions and
+* limitations under the License.
+*/
+use crate::debot_cache::query_account;
+use crate::helpers::{load_abi, load_ton_address, TonClient};
+use crate::Callbacks;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use ton_client::abi::{
+    encode_account, encode_internal_message, encode_message, Abi, CallSet,
+    ParamsOfEncodeAccount, ParamsOfEncodeInternalMessage, ParamsOfEncodeMessage, Signer,
+    StateInitParams, StateInitSource,
+};
+use ton_client::boc::{parse_account, parse_message, ParamsOfParse};
+use ton_client::debot::{BrowserCallbacks, DAction, DEngine, DebotInfo, DEBOT_WC};
+use ton_client::tvm::{run_tvm, ParamsOfRunTvm};
+
+const BROWSER_ADDR: &str = "-31:0000000000000000000000000000000000000000000000000000000000000000";
+/// Balance of accounts created from TVC.
+const LOCAL_BALANCE: u64 = 1_000_000_000_000_000;
+
+/// Local DeBot description. Used to run DeBot without network.
+///
+/// Either `state` or `tvc` must be defined. Both are base64 encoded BOCs.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalDebotParams {
+    /// DeBot address. If omitted, address is calculated from the state.
+    #[serde(default)]
+    pub address: Option<String>,
+    /// Account state BOC.
+    #[serde(default)]
+    pub state: Option<String>,
+    /// TVC used to create account state.
+    #[serde(default)]
+    pub tvc: Option<String>,
+    /// DeBot ABI: JSON string or object.
+    pub abi: Value,
+    /// Initial data of the contract created from TVC.
+    #[serde(default)]
+    pub initial_data: Option<Value>,
+    /// Public key of the contract created from TVC.
+    #[serde(default)]
+    pub public_key: Option<String>,
+}
+
+impl LocalDebotParams {
+    fn abi_json(&self) -> Result<String, String> {
+        match &self.abi {
+            Value::String(abi) => Ok(abi.clone()),
+            Value::Object(_) => Ok(self.abi.to_string()),
+            _ => Err(format!("local DeBot ABI must be a string or an object")),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct LocalDebot {
+    abi: String,
+    state: String,
+}
+
+/// Map of local DeBots: [addr] -> DeBot ABI and account state.
+#[derive(Clone, Default)]
+pub struct LocalDebots {
+    debots: HashMap<String, LocalDebot>,
+}
+
+impl LocalDebots {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds DeBot to the map. Returns DeBot address.
+    pub async fn add(&mut self, client: TonClient, params: LocalDebotParams) -> Result<String, String> {
+        let abi = params.abi_json()?;
+        let (state, id) = match (&params.state, &params.tvc) {
+            (Some(state), None) => {
+                let parsed = parse_account(client.clone(), ParamsOfParse { boc: state.clone() })
+                    .await
+                    .map_err(|e| format!("invalid DeBot state: {}", e))?
+                    .parsed;
+                let id = parsed["id"].as_str().unwrap_or_default().to_owned();
+                (state.clone(), id)
+            },
+            (None, Some(tvc)) => {
+                let init_params = match params.initial_data {
+                    Some(value) => Some(StateInitParams { abi: Some(load_abi(&abi)?), value }),
+                    None => None,
+                };
+                let account = encode_account(
+                    client.clone(),
+                    ParamsOfEncodeAccount {
+                        state_init: StateInitSource::Tvc {
+                            tvc: tvc.clone(),
+                            public_key: params.public_key.clone(),
+                            init_params,
+                        },
+                        balance: Some(LOCAL_BALANCE),
+                        last_trans_lt: None,
+                        last_paid: None,
+                        boc_cache: None,
+                    },
+                )
+                .await
+                .map_err(|e| format!("failed to create DeBot state from TVC: {}", e))?;
+                (account.account, account.id)
+            },
+            _ => return Err(format!("either state or tvc must be defined for local DeBot")),
+        };
+        let addr = match params.address {
+            Some(addr) => addr,
+            None if !id.is_empty() => id,
+            None => return Err(format!("failed to calculate local DeBot address")),
+        };
+        let addr = load_ton_address(&addr, 0)?;
+        self.debots.insert(addr.clone(), LocalDebot { abi, state });
+        Ok(addr)
+    }
+
+    pub fn contains(&self, addr: &str) -> bool {
+        self.debots.contains_key(addr)
+    }
+
+    /// Returns parsed account of the local DeBot.
+    pub(crate) async fn parse_account(&self, client: TonClient, addr: &str) -> Option<Value> {
+        let debot = self.debots.get(addr)?;
+        parse_account(client, ParamsOfParse { boc: debot.state.clone() })
+            .await
+            .ok()
+            .map(|result| result.parsed)
+    }
+
+    /// Returns address of any DeBot from the map.
+    pub fn first_address(&self) -> Option<String> {
+        self.debots.keys().next().cloned()
+    }
+}
+
+/// DeBot engine which runs DeBot code locally with `run_tvm`.
+///
+/// Only messages between DeBots and interfaces are supported. Onchain calls
+/// and get-methods of other contracts require the network engine.
+pub(crate) struct LocalEngine {
+    addr: String,
+    abi_json: String,
+    abi: Abi,
+    state: String,
+    client: TonClient,
+    callbacks: Arc<Callbacks>,
+}
+
+impl LocalEngine {
+    fn new(
+        addr: String,
+        debot: &LocalDebot,
+        client: TonClient,
+        callbacks: Arc<Callbacks>,
+    ) -> Result<Self, String> {
+        Ok(Self {
+            abi: load_abi(&debot.abi)?,
+            abi_json: debot.abi.clone(),
+            state: debot.state.clone(),
+            addr,
+            client,
+            callbacks,
+        })
+    }
+
+    async fn init(&mut self) -> Result<DebotInfo, String> {
+        let output = self.run_getter("getDebotInfo").await?;
+        let abi_version = abi_version(&self.abi_json)?;
+        let mut info = info_from_output(&output, &abi_version);
+        if info.interfaces.is_empty() {
+            if let Ok(output) = self.run_getter("getRequiredInterfaces").await {
+                info.interfaces = string_array(&output["interfaces"]);
+            }
+        }
+        info.dabi = Some(self.abi_json.clone());
+        Ok(info)
+    }
+
+    async fn start(&mut self) -> Result<(), String> {
+        let message = encode_internal_message(
+            self.client.clone(),
+            ParamsOfEncodeInternalMessage {
+                abi: Some(self.abi.clone()),
+                address: Some(self.addr.clone()),
+                src_address: Some(BROWSER_ADDR.to_owned()),
+                call_set: CallSet::some_with_function("start"),
+                value: LOCAL_BALANCE.to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(|e| format!("{}", e))?
+        .message;
+        self.send(message).await
+    }
+
+    /// Runs DeBot with inbound message and passes outbound messages to browser.
+    async fn send(&mut self, message: String) -> Result<(), String> {
+        let result = run_tvm(
+            self.client.clone(),
+            ParamsOfRunTvm {
+                message,
+                account: self.state.clone(),
+                abi: Some(self.abi.clone()),
+                return_updated_account: Some(true),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(|e| format!("{}", e))?;
+        self.state = result.account;
+        for msg in result.out_messages {
+            let parsed = parse_message(self.client.clone(), ParamsOfParse { boc: msg.clone() })
+                .await
+                .map_err(|e| format!("{}", e))?
+                .parsed;
+            if !is_browser_message(&parsed) {
+                let dst = parsed["dst"].as_str().unwrap_or_default();
+                return Err(format!(
+                    "local DeBot {} calls contract {}: onchain calls are not supported for local DeBots",
+                    self.addr, dst
+                ));
+            }
+            self.callbacks.send(msg).await;
+        }
+        Ok(())
+    }
+
+    async fn run_getter(&self, name: &str) -> Result<Value, String> {
+        let message = encode_message(
+            self.client.clone(),
+            ParamsOfEncodeMessage {
+                abi: self.abi.clone(),
+                address: Some(self.addr.clone()),
+                deploy_set: None,
+                call_set: CallSet::some_with_function(name),
+                signer: Signer::None,
+                processing_try_index: None,
+            },
+        )
+        .await
+        .map_err(|e| format!("{}", e))?
+        .message;
+        let result = run_tvm(
+            self.client.clone(),
+            ParamsOfRunTvm {
+                message,
+                account: self.state.clone(),
+                abi: Some(self.abi.clone()),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(|e| format!("failed to run {}: {}", name, e))?;
+        result
+            .decoded
+            .and_then(|decoded| decoded.output)
+            .ok_or(format!("{} returned no output", name))
+    }
+}
+
+/// DeBot engine used by browser: network engine or local one.
+#[async_trait::async_trait]
+pub trait DebotEngine: Send {
+    /// Downloads DeBot, loads its state and returns its info.
+    async fn init(&mut self) -> Result<DebotInfo, String>;
+    /// Loads DeBot state. Called instead of `init` if DeBot info is taken
+    /// from cache.
+    async fn load(&mut self) -> Result<(), String>;
+    async fn start(&mut self) -> Result<(), String>;
+    async fn execute_action(&mut self, action: &DAction) -> Result<(), String>;
+    async fn send(&mut self, message: String) -> Result<(), String>;
+}
+
+/// Source of DeBots which are not local. By default DeBots are downloaded
+/// from network.
+#[async_trait::async_trait]
+pub trait DebotSource {
+    /// Returns DeBot account with `code_hash` and `data` fields.
+    async fn query_account(&self, addr: &str) -> Option<Value>;
+    /// Creates engine of the DeBot. `abi` is DeBot ABI if it is already known.
+    fn create_engine(
+        &self,
+        addr: String,
+        abi: Option<String>,
+        callbacks: Arc<dyn BrowserCallbacks + Send + Sync>,
+    ) -> Box<dyn DebotEngine>;
+}
+
+pub type SharedDebotSource = Arc<dyn DebotSource + Send + Sync>;
+
+/// Downloads DeBots from network with SDK `DEngine`.
+pub struct NetworkSource {
+    client: TonClient,
+}
+
+impl NetworkSource {
+    pub fn new(client: TonClient) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait::async_trait]
+impl DebotSource for NetworkSource {
+    async fn query_account(&self, addr: &str) -> Option<Value> {
+        query_account(self.client.clone(), addr).await
+    }
+
+    fn create_engine(
+        &self,
+        addr: String,
+        abi: Option<String>,
+        callbacks: Arc<dyn BrowserCallbacks + Send + Sync>,
+    ) -> Box<dyn DebotEngine> {
+        Box::new(NetworkEngine(DEngine::new_with_client(addr, abi, self.client.clone(), callbacks)))
+    }
+}
+
+/// Network engine based on SDK `DEngine`.
+struct NetworkEngine(DEngine);
+
+#[async_trait::async_trait]
+impl DebotEngine for NetworkEngine {
+    async fn init(&mut self) -> Result<DebotInfo, String> {
+        Ok(self.0.init().await?.into())
+    }
+
+    async fn load(&mut self) -> Result<(), String> {
+        // `DEngine` loads account state only in `init`.
+        self.0.init().await.map(|_| ())
+    }
+
+    async fn start(&mut self) -> Result<(), String> {
+        self.0.start().await
+    }
+
+    async fn execute_action(&mut self, action: &DAction) -> Result<(), String> {
+        self.0.execute_action(action).await
+    }
+
+    async fn send(&mut self, message: String) -> Result<(), String> {
+        self.0.send(message).await
+    }
+}
+
+#[async_trait::async_trait]
+impl DebotEngine for LocalEngine {
+    async fn init(&mut self) -> Result<DebotInfo, String> {
+        LocalEngine::init(self).await
+    }
+
+    async fn load(&mut self) -> Result<(), String> {
+        // state is kept in memory
+        Ok(())
+    }
+
+    async fn start(&mut self) -> Result<(), String> {
+        LocalEngine::start(self).await
+    }
+
+    async fn execute_action(&mut self, action: &DAction) -> Result<(), String> {
+        Err(format!(
+            "local DeBot {} can not execute action {}: actions are not supported for local DeBots",
+            self.addr, action.name
+        ))
+    }
+
+    async fn send(&mut self, message: String) -> Result<(), String> {
+        LocalEngine::send(self, message).await
+    }
+}
+
+/// Creates local engine if DeBot is in the `local` map. Otherwise engine is
+/// created by `source`.
+pub(crate) fn create_engine(
+    addr: String,
+    local: &LocalDebots,
+    source: &SharedDebotSource,
+    client: TonClient,
+    callbacks: Arc<Callbacks>,
+    abi: Option<String>,
+) -> Result<Box<dyn DebotEngine>, String> {
+    Ok(match local.debots.get(&addr) {
+        Some(debot) => Box::new(LocalEngine::new(addr, debot, client, callbacks)?),
+        None => source.create_engine(addr, abi, callbacks),
+    })
+}
+
+/// Checks if outbound message of local DeBot can be passed to browser:
+/// internal message to interface or another DeBot, or external message to
+/// interface. Other external messages are onchain calls.
+fn is_browser_message(parsed: &Value) -> bool {
+    match parsed["msg_type"].as_u64() {
+        Some(0) => true,
+        Some(2) => parsed["dst"]
+            .as_str()
+            .map(|dst| dst.starts_with(&format!("{}:", DEBOT_WC)))
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+fn abi_version(abi: &str) -> Result<String, String> {
+    let abi: Value = serde_json::from_str(abi).map_err(|e| format!("ABI is not a valid json: {}", e))?;
+    if let Some(version) = abi["version"].as_str() {
+        return Ok(version.to_owned());
+    }
+    let major = abi["ABI version"].as_u64().unwrap_or(2);
+    Ok(format!("{}.0", major))
+}
+
+/// ABI 2.0 DeBots return strings as hex encoded bytes.
+fn text_field(value: &Value, hex_encoded: bool) -> Option<String> {
+    let text = value.as_str()?;
+    if hex_encoded {
+        hex::decode(text).ok().and_then(|bytes| String::from_utf8(bytes).ok())
+    } else {
+        Some(text.to_owned())
+    }
+}
+
+fn string_array(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .map(|items| items.iter().filter_map(|v| v.as_str().map(|s| s.to_owned())).collect())
+        .unwrap_or_default()
+}
+
+fn info_from_output(output: &Value, abi_version: &str) -> DebotInfo {
+    let hex_encoded = abi_version == "2.0";
+    let text = |name: &str| text_field(&output[name], hex_encoded);
+    DebotInfo {
+        name: text("name"),
+        version: text("version"),
+        publisher: text("publisher"),
+        caption: text("caption"),
+        author: text("author"),
+        support: output["support"].as_str().map(|s| s.to_owned()),
+        hello: text("hello"),
+        language: text("language"),
+        dabi: None,
+        icon: text_field(&output["icon"], true),
+        interfaces: string_array(&output["interfaces"]),
+        dabi_version: abi_version.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::create_client_local;
+    use serde_json::json;
+
+    #[test]
+    fn test_browser_messages() {
+        let interface = "-31:8796536366ee21852db56dccb60bc564598b618c865fc50c8b1ab740bba128e3";
+        let contract = "0:1111111111111111111111111111111111111111111111111111111111111111";
+        assert!(is_browser_message(&json!({ "msg_type": 0, "dst": interface })));
+        assert!(is_browser_message(&json!({ "msg_type": 0, "dst": contract })));
+        assert!(is_browser_message(&json!({ "msg_type": 2, "dst": interface })));
+        assert!(!is_browser_message(&json!({ "msg_type": 2, "dst": contract })));
+        assert!(!is_browser_message(&json!({ "msg_type": 2, "dst": "" })));
+        assert!(!is_browser_message(&json!({ "msg_type": 1, "dst": contract })));
+    }
+
+    #[test]
+    fn test_abi_version() {
+        assert_eq!(abi_version(r#"{"version": "2.2", "functions": []}"#).unwrap(), "2.2");
+        assert_eq!(abi_version(r#"{"ABI version": 2, "functions": []}"#).unwrap(), "2.0");
+        assert!(abi_version("not json").is_err());
+    }
+
+    #[test]
+    fn test_info_from_output() {
+        let output = json!({
+            "name": hex::encode("Hello"),
+            "version": hex::encode("0.1.0"),
+            "icon": hex::encode("data:image/png;base64,"),
+            "support": "0:1111",
+            "interfaces": ["0x8796536366ee21852db56dccb60bc564598b618c865fc50c8b1ab740bba128e3"],
+        });
+        let info = info_from_output(&output, "2.0");
+        assert_eq!(info.name, Some("Hello".to_owned()));
+        assert_eq!(info.version, Some("0.1.0".to_owned()));
+        assert_eq!(info.icon, Some("data:image/png;base64,".to_owned()));
+        assert_eq!(info.support, Some("0:1111".to_owned()));
+        assert_eq!(info.interfaces.len(), 1);
+        assert_eq!(info.dabi_version, "2.0");
+
+        let output = json!({ "name": "Hello", "hello": "Hi!" });
+        let info = info_from_output(&output, "2.2");
+        assert_eq!(info.name, Some("Hello".to_owned()));
+        assert_eq!(info.hello, Some("Hi!".to_owned()));
+        assert_eq!(info.caption, None);
+    }
+
+    #[tokio::test]
+    async fn test_add_invalid_params() {
+        let client = create_client_local().unwrap();
+        let mut debots = LocalDebots::new();
+        let params: LocalDebotParams = serde_json::from_value(json!({ "abi": {"version": "2.2"} })).unwrap();
+        assert!(debots.add(client.clone(), params.clone()).await.is_err());
+
+        let params = LocalDebotParams {
+            state: Some("te6".to_owned()),
+            tvc: Some("te6".to_owned()),
+            ..params
+        };
+        assert!(debots.add(client.clone(), params.clone()).await.is_err());
+
+        let params = LocalDebotParams { abi: json!(2), tvc: None, ..params };
+        assert!(debots.add(client, params).await.is_err());
+        assert!(debots.first_address().is_none());
+    }
+}
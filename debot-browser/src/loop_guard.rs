@@ -0,0 +1,313 @@
+/*
+* Copyright 2018-2021 TON DEV SOLUTIONS LTD.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific TON DEV software governing permissions and
+* limitations under the License.
+*/
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+fn default_max_messages() -> Option<u32> { Some(10000) }
+fn default_max_depth() -> Option<u32> { Some(16) }
+fn default_history() -> usize { 5 }
+
+/// Limits of the message loop of one run. Can be set in manifest `limits` field.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LoopLimits {
+    /// Maximum number of messages processed during the run.
+    #[serde(default = "default_max_messages")]
+    pub max_messages: Option<u32>,
+    /// Maximum duration of the run in milliseconds.
+    #[serde(default)]
+    pub max_time_ms: Option<u64>,
+    /// Maximum length of the chain of calls between different DeBots.
+    #[serde(default = "default_max_depth")]
+    pub max_depth: Option<u32>,
+    /// Number of last messages reported when a limit is exceeded.
+    #[serde(default = "default_history")]
+    pub history: usize,
+}
+
+impl Default for LoopLimits {
+    fn default() -> Self {
+        Self {
+            max_messages: default_max_messages(),
+            max_time_ms: None,
+            max_depth: default_max_depth(),
+            history: default_history(),
+        }
+    }
+}
+
+/// Short description of the processed message.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageRecord {
+    pub src: String,
+    pub dst: String,
+    pub depth: u32,
+}
+
+/// Error returned when message loop exceeds one of the limits.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LoopError {
+    /// Name of the exceeded limit: `maxMessages`, `maxTimeMs` or `maxDepth`.
+    pub limit: String,
+    /// Value of the exceeded limit.
+    pub value: u64,
+    /// Number of messages processed before the limit was exceeded.
+    pub processed: u32,
+    /// Last processed messages, the oldest first.
+    pub last_messages: Vec<MessageRecord>,
+}
+
+impl fmt::Display for LoopError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let json = serde_json::to_string(self).map_err(|_| fmt::Error)?;
+        write!(f, "message loop limit exceeded: {}", json)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn now_ms() -> f64 {
+    js_sys::Date::now()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now_ms() -> f64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64() * 1000.0)
+        .unwrap_or_default()
+}
+
+#[cfg(target_arch = "wasm32")]
+mod timer {
+    use crate::dapp_signing_box::{JsFutureSync, Promise};
+    use wasm_bindgen::prelude::*;
+
+    #[wasm_bindgen(inline_js = r#"
+export function debot_sleep(ms) {
+    return new Promise((resolve) => setTimeout(resolve, ms));
+}
+"#)]
+    extern "C" {
+        fn debot_sleep(ms: f64) -> Promise;
+    }
+
+    pub async fn sleep(ms: u64) {
+        let _ = JsFutureSync::from(debot_sleep(ms as f64)).await;
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod timer {
+    pub async fn sleep(ms: u64) {
+        tokio::time::delay_for(std::time::Duration::from_millis(ms)).await;
+    }
+}
+
+/// Future which is ready with `None` if inner future is not ready before delay.
+struct Timeout<F: Future> {
+    future: Pin<Box<F>>,
+    delay: Pin<Box<dyn Future<Output = ()>>>,
+}
+
+impl<F: Future> Future for Timeout<F> {
+    type Output = Option<F::Output>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        if let Poll::Ready(output) = self.future.as_mut().poll(cx) {
+            return Poll::Ready(Some(output));
+        }
+        self.delay.as_mut().poll(cx).map(|_| None)
+    }
+}
+
+/// Runs `future` for at most `ms` milliseconds. Returns `None` on timeout.
+/// There is no timeout if `ms` is `None`.
+pub async fn with_timeout<F: Future>(ms: Option<u64>, future: F) -> Option<F::Output> {
+    match ms {
+        Some(ms) => Timeout { future: Box::pin(future), delay: Box::pin(timer::sleep(ms)) }.await,
+        None => Some(future.await),
+    }
+}
+
+/// Tracks messages of one run and stops the run if DeBots are looping.
+pub struct LoopGuard {
+    limits: LoopLimits,
+    started: f64,
+    processed: u32,
+    last_messages: VecDeque<MessageRecord>,
+}
+
+impl LoopGuard {
+    pub fn new(limits: LoopLimits) -> Self {
+        Self {
+            limits,
+            started: now_ms(),
+            processed: 0,
+            last_messages: VecDeque::new(),
+        }
+    }
+
+    /// Registers next message. Returns error if any limit is exceeded.
+    pub fn check(&mut self, message: MessageRecord) -> Result<(), LoopError> {
+        self.check_at(message, now_ms())
+    }
+
+    /// Returns time in milliseconds left before the time limit is exceeded.
+    /// `None` if there is no time limit.
+    pub fn time_left(&self) -> Option<u64> {
+        self.time_left_at(now_ms())
+    }
+
+    fn time_left_at(&self, now: f64) -> Option<u64> {
+        let max = self.limits.max_time_ms?;
+        Some((self.started + max as f64 - now).max(0.0) as u64)
+    }
+
+    /// Returns error for the message which is not handled in time.
+    pub fn time_error(&self) -> LoopError {
+        self.error("maxTimeMs", self.limits.max_time_ms.unwrap_or_default())
+    }
+
+    fn check_at(&mut self, message: MessageRecord, now: f64) -> Result<(), LoopError> {
+        let depth = message.depth;
+        self.processed += 1;
+        self.last_messages.push_back(message);
+        while self.last_messages.len() > self.limits.history {
+            self.last_messages.pop_front();
+        }
+        if let Some(max) = self.limits.max_messages {
+            if self.processed > max {
+                return Err(self.error("maxMessages", max as u64));
+            }
+        }
+        if let Some(max) = self.limits.max_time_ms {
+            if now - self.started > max as f64 {
+                return Err(self.error("maxTimeMs", max));
+            }
+        }
+        if let Some(max) = self.limits.max_depth {
+            if depth > max {
+                return Err(self.error("maxDepth", max as u64));
+            }
+        }
+        Ok(())
+    }
+
+    fn error(&self, limit: &str, value: u64) -> LoopError {
+        LoopError {
+            limit: limit.to_owned(),
+            value,
+            processed: self.processed.saturating_sub(1),
+            last_messages: self.last_messages.iter().cloned().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEBOT: &str = "0:1111111111111111111111111111111111111111111111111111111111111111";
+    const OTHER: &str = "0:2222222222222222222222222222222222222222222222222222222222222222";
+
+    /// DeBot which sends a message to itself in response to each message.
+    struct LoopingDebot {
+        sent: u32,
+    }
+
+    impl Iterator for LoopingDebot {
+        type Item = MessageRecord;
+        fn next(&mut self) -> Option<MessageRecord> {
+            self.sent += 1;
+            Some(MessageRecord { src: DEBOT.to_owned(), dst: DEBOT.to_owned(), depth: 0 })
+        }
+    }
+
+    fn limits(max_messages: Option<u32>, max_time_ms: Option<u64>, max_depth: Option<u32>) -> LoopLimits {
+        LoopLimits { max_messages, max_time_ms, max_depth, history: 3 }
+    }
+
+    #[test]
+    fn test_max_messages() {
+        let mut guard = LoopGuard::new(limits(Some(100), None, None));
+        let mut source = LoopingDebot { sent: 0 };
+        let err = source.find_map(|msg| guard.check(msg).err()).unwrap();
+        assert_eq!(source.sent, 101);
+        assert_eq!(err.limit, "maxMessages");
+        assert_eq!(err.value, 100);
+        assert_eq!(err.processed, 100);
+        assert_eq!(err.last_messages.len(), 3);
+        assert_eq!(err.last_messages[2].dst, DEBOT);
+        assert!(err.to_string().contains(r#""limit":"maxMessages""#));
+    }
+
+    #[test]
+    fn test_max_time() {
+        let mut guard = LoopGuard::new(limits(None, Some(1000), None));
+        let started = guard.started;
+        let mut source = LoopingDebot { sent: 0 };
+        for i in 0..10 {
+            let msg = source.next().unwrap();
+            assert!(guard.check_at(msg, started + i as f64 * 100.0).is_ok());
+        }
+        let err = guard.check_at(source.next().unwrap(), started + 1001.0).unwrap_err();
+        assert_eq!(err.limit, "maxTimeMs");
+        assert_eq!(err.processed, 10);
+    }
+
+    #[test]
+    fn test_time_left() {
+        let guard = LoopGuard::new(limits(None, Some(1000), None));
+        assert_eq!(guard.time_left_at(guard.started + 400.0), Some(600));
+        assert_eq!(guard.time_left_at(guard.started + 1500.0), Some(0));
+        assert_eq!(guard.time_error().limit, "maxTimeMs");
+        assert_eq!(guard.time_error().processed, 0);
+        assert_eq!(LoopGuard::new(limits(None, None, None)).time_left(), None);
+    }
+
+    #[tokio::test]
+    async fn test_with_timeout() {
+        assert_eq!(with_timeout(None, async { 1 }).await, Some(1));
+        assert_eq!(with_timeout(Some(1000), async { 1 }).await, Some(1));
+        let pending = std::future::pending::<u32>();
+        assert_eq!(with_timeout(Some(10), pending).await, None);
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let mut guard = LoopGuard::new(limits(None, None, Some(2)));
+        for depth in 0..=2 {
+            let msg = MessageRecord { src: DEBOT.to_owned(), dst: OTHER.to_owned(), depth };
+            assert!(guard.check(msg).is_ok());
+        }
+        let msg = MessageRecord { src: OTHER.to_owned(), dst: DEBOT.to_owned(), depth: 3 };
+        let err = guard.check(msg).unwrap_err();
+        assert_eq!(err.limit, "maxDepth");
+        assert_eq!(err.last_messages.iter().map(|m| m.depth).collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_default_limits() {
+        let limits: LoopLimits = serde_json::from_str(r#"{"maxTimeMs": 5000}"#).unwrap();
+        assert_eq!(limits, LoopLimits { max_time_ms: Some(5000), ..Default::default() });
+        let limits: LoopLimits = serde_json::from_str(r#"{"maxMessages": null}"#).unwrap();
+        assert_eq!(limits.max_messages, None);
+    }
+}
@@ -0,0 +1,22 @@
+/*
+* Copyright 2018-2021 TON DEV SOLUTIONS LTD.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific TON DEV software governing permissions and
+* limitations under the License.
+*/
+
+#[cfg(not(target_arch = "wasm32"))]
+#[tokio::main(basic_scheduler)]
+async fn main() {
+    let code = debot_browser::cli::main_with_args(std::env::args_os()).await;
+    std::process::exit(code);
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
@@ -1,28 +1,52 @@
+use crate::loop_guard::LoopLimits;
+use crate::policy::ApprovePolicy;
+use crate::trust::TrustList;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 use std::default::Default;
 
 fn default_init_method() -> String { format!("start") }
 fn default_mandatory() -> bool { false }
 
-#[derive(Deserialize, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub enum ApproveKind {
     ApproveOnChainCall,
     ApproveNetwork,
     ApproveMessageLimit,
 }
 
-#[derive(Deserialize, Clone, Default)]
-#[serde(rename_all(deserialize = "camelCase"))]
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
 pub struct DebotManifest {
     pub version: u8,
     pub debot_address: String,
     #[serde(default = "default_init_method")]
     pub init_method: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub init_args: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub init_msg: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub abi: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub auto_approve: Option<Vec<ApproveKind>>,
+    /// Number of messages DeBot can send before user must approve next ones.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_limit: Option<u32>,
+    /// Limits of the message loop.
+    #[serde(default)]
+    pub limits: LoopLimits,
+    /// ABIs of contracts called by DeBot: [addr] -> ABI. Used to show
+    /// function calls in approve requests.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub contract_abis: HashMap<String, Value>,
+    /// Approve policy for onchain calls.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy: Option<ApprovePolicy>,
+    /// DeBots allowed to run. Added to the trust list of the browser.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trust_list: Option<TrustList>,
     pub quiet: bool,
     pub chain: Vec<ChainLink>,
 }
@@ -54,4 +78,11 @@ pub enum ChainLink {
     SigningBox {
         handle: u32
     },
+    /// Encryption box for `EncryptionBoxInput` request: handle of registered
+    /// box or keys (seed phrase or path to keypair file) used to create the
+    /// requested box.
+    EncryptionBox {
+        handle: Option<u32>,
+        keys: Option<String>,
+    },
 }
\ No newline at end of file
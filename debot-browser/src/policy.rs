@@ -0,0 +1,269 @@
+/*
+* Copyright 2018-2021 TON DEV SOLUTIONS LTD.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific TON DEV software governing permissions and
+* limitations under the License.
+*/
+use crate::helpers::{normalize_key, parse_amount, same_address};
+use serde::{Deserialize, Serialize};
+
+/// Decision of the policy rule.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum PolicyAction {
+    Approve,
+    Reject,
+    /// Ask user (or reject if browser is not interactive).
+    Prompt,
+}
+
+impl Default for PolicyAction {
+    fn default() -> Self {
+        PolicyAction::Prompt
+    }
+}
+
+/// Rule of the approve policy. Rule matches transaction if all defined
+/// conditions are satisfied. Amounts are in nanotokens.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyRule {
+    /// Rule name used in logs and run report.
+    #[serde(default)]
+    pub name: Option<String>,
+    pub action: PolicyAction,
+    /// Maximum total amount of outgoing transfers of the transaction.
+    #[serde(default)]
+    pub max_amount: Option<String>,
+    /// Maximum total amount of approved transfers in the session, including this transaction.
+    #[serde(default)]
+    pub max_session_total: Option<String>,
+    /// Maximum transaction fees.
+    #[serde(default)]
+    pub max_fee: Option<String>,
+    /// Allowed transfer recipients. Transaction account is checked if there are no transfers.
+    #[serde(default)]
+    pub recipients: Option<Vec<String>>,
+    /// Allowed transaction accounts.
+    #[serde(default)]
+    pub accounts: Option<Vec<String>>,
+    /// Allowed message signer public keys.
+    #[serde(default)]
+    pub signkeys: Option<Vec<String>>,
+    /// Matches only transactions which change (true) or don't change (false) account code.
+    #[serde(default)]
+    pub setcode: Option<bool>,
+}
+
+/// Declarative approve policy for onchain calls. Rules are checked in order,
+/// the first matching rule decides.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ApprovePolicy {
+    #[serde(default)]
+    pub rules: Vec<PolicyRule>,
+    /// Decision if no rule matches.
+    #[serde(default)]
+    pub default: PolicyAction,
+}
+
+/// Transaction fields checked by the policy.
+pub struct TransactionInfo<'a> {
+    pub dst: &'a str,
+    pub out: Vec<(u64, &'a str)>,
+    pub fee: u64,
+    pub setcode: bool,
+    pub signkey: &'a str,
+}
+
+impl TransactionInfo<'_> {
+    pub fn amount(&self) -> u64 {
+        self.out.iter().fold(0, |total, s| total.saturating_add(s.0))
+    }
+}
+
+/// Policy decision and name of the rule which made it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PolicyDecision {
+    pub action: PolicyAction,
+    pub rule: String,
+}
+
+fn contains_address(list: &[String], addr: &str) -> bool {
+    list.iter().any(|item| same_address(item, addr))
+}
+
+impl PolicyRule {
+    fn matches(&self, tx: &TransactionInfo, session_total: u64) -> Result<bool, String> {
+        let amount = tx.amount();
+        if let Some(max) = &self.max_amount {
+            if amount > parse_amount(max, "maxAmount")? {
+                return Ok(false);
+            }
+        }
+        if let Some(max) = &self.max_session_total {
+            if session_total.saturating_add(amount) > parse_amount(max, "maxSessionTotal")? {
+                return Ok(false);
+            }
+        }
+        if let Some(max) = &self.max_fee {
+            if tx.fee > parse_amount(max, "maxFee")? {
+                return Ok(false);
+            }
+        }
+        if let Some(recipients) = &self.recipients {
+            let mut actual: Vec<&str> = tx.out.iter().map(|s| s.1).collect();
+            if actual.is_empty() {
+                actual.push(tx.dst);
+            }
+            if !actual.iter().all(|addr| contains_address(recipients, addr)) {
+                return Ok(false);
+            }
+        }
+        if let Some(accounts) = &self.accounts {
+            if !contains_address(accounts, tx.dst) {
+                return Ok(false);
+            }
+        }
+        if let Some(keys) = &self.signkeys {
+            if !keys.iter().any(|k| normalize_key(k) == normalize_key(tx.signkey)) {
+                return Ok(false);
+            }
+        }
+        if let Some(setcode) = self.setcode {
+            if setcode != tx.setcode {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl ApprovePolicy {
+    /// Returns decision for the transaction. `session_total` is the amount
+    /// approved before in this session.
+    pub fn evaluate(&self, tx: &TransactionInfo, session_total: u64) -> Result<PolicyDecision, String> {
+        for (i, rule) in self.rules.iter().enumerate() {
+            if rule.matches(tx, session_total)? {
+                return Ok(PolicyDecision {
+                    action: rule.action,
+                    rule: rule.name.clone().unwrap_or(format!("#{}", i + 1)),
+                });
+            }
+        }
+        Ok(PolicyDecision { action: self.default, rule: "default".to_owned() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const FRIEND: &str = "0:1111111111111111111111111111111111111111111111111111111111111111";
+    const STRANGER: &str = "0:2222222222222222222222222222222222222222222222222222222222222222";
+    const WALLET: &str = "0:3333333333333333333333333333333333333333333333333333333333333333";
+
+    fn policy() -> ApprovePolicy {
+        serde_json::from_value(json!({
+            "rules": [
+                { "name": "no setcode", "action": "Reject", "setcode": true },
+                {
+                    "name": "small to friends",
+                    "action": "Approve",
+                    "maxAmount": "2000000000",
+                    "maxSessionTotal": "5000000000",
+                    "recipients": [FRIEND]
+                },
+                { "action": "Prompt", "maxFee": "100000000" }
+            ],
+            "default": "Reject"
+        }))
+        .unwrap()
+    }
+
+    fn tx(out: Vec<(u64, &'static str)>, fee: u64, setcode: bool) -> TransactionInfo<'static> {
+        TransactionInfo { dst: WALLET, out, fee, setcode, signkey: "" }
+    }
+
+    fn decide(tx: &TransactionInfo, total: u64) -> (PolicyAction, String) {
+        let decision = policy().evaluate(tx, total).unwrap();
+        (decision.action, decision.rule)
+    }
+
+    #[test]
+    fn test_rules_order() {
+        let small = tx(vec![(1_000_000_000, FRIEND)], 1000, false);
+        assert_eq!(decide(&small, 0), (PolicyAction::Approve, "small to friends".to_owned()));
+
+        let setcode = tx(vec![(1_000_000_000, FRIEND)], 1000, true);
+        assert_eq!(decide(&setcode, 0), (PolicyAction::Reject, "no setcode".to_owned()));
+
+        let big = tx(vec![(3_000_000_000, FRIEND)], 1000, false);
+        assert_eq!(decide(&big, 0), (PolicyAction::Prompt, "#3".to_owned()));
+
+        let stranger = tx(vec![(1_000_000_000, FRIEND), (1, STRANGER)], 1000, false);
+        assert_eq!(decide(&stranger, 0).0, PolicyAction::Prompt);
+
+        let expensive = tx(vec![(3_000_000_000, FRIEND)], 200_000_000, false);
+        assert_eq!(decide(&expensive, 0), (PolicyAction::Reject, "default".to_owned()));
+    }
+
+    #[test]
+    fn test_session_total() {
+        let small = tx(vec![(2_000_000_000, FRIEND)], 1000, false);
+        assert_eq!(decide(&small, 3_000_000_000).0, PolicyAction::Approve);
+        assert_eq!(decide(&small, 3_000_000_001).0, PolicyAction::Prompt);
+    }
+
+    #[test]
+    fn test_conditions() {
+        let rule = PolicyRule {
+            action: PolicyAction::Approve,
+            accounts: Some(vec![WALLET.to_owned()]),
+            signkeys: Some(vec!["0xABCD".to_owned()]),
+            ..Default::default()
+        };
+        let mut info = tx(vec![], 0, false);
+        info.signkey = "abcd";
+        assert!(rule.matches(&info, 0).unwrap());
+        info.signkey = "abce";
+        assert!(!rule.matches(&info, 0).unwrap());
+        info.dst = FRIEND;
+        info.signkey = "abcd";
+        assert!(!rule.matches(&info, 0).unwrap());
+
+        let rule = PolicyRule { max_amount: Some("ten".to_owned()), ..Default::default() };
+        assert!(rule.matches(&info, 0).is_err());
+    }
+
+    #[test]
+    fn test_short_addresses() {
+        let rule = PolicyRule {
+            action: PolicyAction::Approve,
+            accounts: Some(vec![WALLET.trim_start_matches("0:").to_owned()]),
+            recipients: Some(vec![FRIEND.trim_start_matches("0:").to_owned()]),
+            ..Default::default()
+        };
+        assert!(rule.matches(&tx(vec![(1, FRIEND)], 0, false), 0).unwrap());
+        assert!(!rule.matches(&tx(vec![(1, STRANGER)], 0, false), 0).unwrap());
+        // user-friendly form of FRIEND
+        let rule = PolicyRule {
+            recipients: Some(vec!["EQAREREREREREREREREREREREREREREREREREREREREREeYT".to_owned()]),
+            ..rule
+        };
+        assert!(rule.matches(&tx(vec![(1, FRIEND)], 0, false), 0).unwrap());
+    }
+
+    #[test]
+    fn test_amount_overflow() {
+        let info = tx(vec![(u64::MAX, FRIEND), (1, FRIEND)], 0, false);
+        assert_eq!(info.amount(), u64::MAX);
+        assert_eq!(decide(&info, 0).0, PolicyAction::Prompt);
+    }
+}
@@ -1,5 +1,11 @@
-use serde_json::Value;
+use serde_json::{Value, json};
 use super::{ApproveKind, DebotManifest, ChainLink};
+use crate::helpers::{is_address, load_ton_address, parse_amount, same_address};
+use crate::loop_guard::LoopLimits;
+use crate::policy::{ApprovePolicy, PolicyAction, TransactionInfo};
+use log::info;
+use std::collections::HashMap;
+use std::iter::Peekable;
 use std::vec::IntoIter;
 use ton_client::abi::{Abi, CallSet};
 use ton_client::debot::DebotActivity;
@@ -12,30 +18,167 @@ pub enum ProcessorError {
     UnexpectedInterface,
     UnexpectedMethod,
     InteractiveApproveNeeded,
+    /// `EncryptionBox` chain link has neither handle nor keys.
+    EncryptionBoxNotDefined,
+    /// `iflq` or `ifeq` condition of onchain call chain link is not satisfied.
+    ApproveConditionFailed(String),
     // TODO:
     // UnexpectedApproveKind,
 }
 
 pub struct ChainProcessor {
     manifest: DebotManifest,
-    chain_iter: IntoIter<ChainLink>,
+    chain_iter: Peekable<IntoIter<ChainLink>>,
+    /// Reason of the last rejected approve. Returned as run error.
+    failure: Option<String>,
+    /// ABIs of fetched DeBots: [addr] -> ABI.
+    debot_abis: HashMap<String, Abi>,
+    /// Approve policy used if manifest has no policy.
+    default_policy: Option<ApprovePolicy>,
+    /// Total amount of approved transfers in the session.
+    session_total: u64,
+    /// Name of the policy rule which decided the last approve.
+    policy_rule: Option<String>,
+    /// Chain links of the recorded session. `None` if recording is off.
+    recorded: Option<Vec<ChainLink>>,
+    /// Approve kinds confirmed and refused by user during recording.
+    approved_kinds: Vec<ApproveKind>,
+    refused_kinds: Vec<ApproveKind>,
+}
+
+/// Handle written to recorded `SigningBox` chain links. Must be replaced
+/// with a real signing box handle before replay.
+pub const SIGNING_BOX_PLACEHOLDER: u32 = 0;
+
+/// Encryption box supplied by manifest chain.
+#[derive(Debug, PartialEq)]
+pub enum ChainEncryptionBox {
+    /// Handle of registered encryption box.
+    Handle(u32),
+    /// Seed phrase or path to keypair file.
+    Keys(String),
 }
 
 impl ChainProcessor {
     pub fn new() -> Self {
-        Self { manifest: DebotManifest::default(), chain_iter: vec![].into_iter() }
+        Self {
+            manifest: DebotManifest::default(),
+            chain_iter: vec![].into_iter().peekable(),
+            failure: None,
+            debot_abis: HashMap::new(),
+            default_policy: None,
+            session_total: 0,
+            policy_rule: None,
+            recorded: None,
+            approved_kinds: vec![],
+            refused_kinds: vec![],
+        }
+    }
+
+    /// Starts recording of chain links consumed or answered by user.
+    pub fn start_recording(&mut self) {
+        self.recorded = Some(vec![]);
+        self.approved_kinds.clear();
+        self.refused_kinds.clear();
+    }
+
+    pub fn recording(&self) -> bool {
+        self.recorded.is_some()
+    }
+
+    /// Adds chain link to the recorded session.
+    pub fn record(&mut self, link: ChainLink) {
+        if let Some(recorded) = self.recorded.as_mut() {
+            recorded.push(link);
+        }
+    }
+
+    /// Remembers user answer to approve request. Approve kind is added to
+    /// `autoApprove` list of the recorded manifest if user never refused it.
+    pub fn record_approve(&mut self, kind: ApproveKind, approved: bool) {
+        if !self.recording() {
+            return;
+        }
+        let kinds = if approved { &mut self.approved_kinds } else { &mut self.refused_kinds };
+        if !kinds.contains(&kind) {
+            kinds.push(kind);
+        }
+    }
+
+    /// Returns manifest which replays recorded session in quiet mode.
+    pub fn recorded_manifest(&self) -> Option<DebotManifest> {
+        let chain = self.recorded.clone()?;
+        let mut manifest = self.manifest.clone();
+        let mut auto_approve = manifest.auto_approve.take().unwrap_or_default();
+        for kind in &self.approved_kinds {
+            if !auto_approve.contains(kind) {
+                auto_approve.push(kind.clone());
+            }
+        }
+        auto_approve.retain(|kind| !self.refused_kinds.contains(kind));
+        if !auto_approve.is_empty() || self.manifest.auto_approve.is_some() {
+            manifest.auto_approve = Some(auto_approve);
+        }
+        if manifest.policy.is_none() {
+            manifest.policy = self.default_policy.clone();
+        }
+        manifest.quiet = true;
+        manifest.chain = chain;
+        Some(manifest)
+    }
+
+    /// Sets approve policy used if manifest has no policy.
+    pub fn set_default_policy(&mut self, policy: Option<ApprovePolicy>) {
+        self.default_policy = policy;
+    }
+
+    /// Adds amount approved by user to the session total.
+    pub fn record_spending(&mut self, amount: u64) {
+        self.session_total = self.session_total.saturating_add(amount);
+    }
+
+    /// Returns name of the policy rule which decided the last approve.
+    pub fn take_policy_rule(&mut self) -> Option<String> {
+        self.policy_rule.take()
     }
 
     pub fn load_manifest(&mut self, mut manifest: DebotManifest) {
         let chain_vec = std::mem::take(&mut manifest.chain);
         self.manifest = manifest;
-        self.chain_iter = chain_vec.into_iter();
+        self.chain_iter = chain_vec.into_iter().peekable();
+        self.failure = None;
+        if self.recording() {
+            self.start_recording();
+        }
+    }
+
+    /// Returns and resets reason of the rejected approve.
+    pub fn take_failure(&mut self) -> Option<String> {
+        self.failure.take()
     }
 
     pub fn abi(&self) -> Option<Abi> {
         self.manifest.abi.clone().map(|v| Abi::Json(v.to_string()))
     }
 
+    /// Registers ABI of the fetched DeBot.
+    pub fn add_debot_abi(&mut self, addr: &str, abi: Abi) {
+        self.debot_abis.insert(addr.to_owned(), abi);
+    }
+
+    /// Returns ABI of the contract from manifest `contractAbis` or ABI of
+    /// the fetched DeBot.
+    pub fn contract_abi(&self, addr: &str) -> Option<Abi> {
+        let from_manifest = self.manifest.contract_abis.iter().find(|(key, _)| {
+            load_ton_address(key, 0).ok().as_deref() == Some(addr)
+        });
+        match from_manifest {
+            Some((_, Value::String(abi))) => Some(Abi::Json(abi.clone())),
+            Some((_, abi)) => Some(Abi::Json(abi.to_string())),
+            None => self.debot_abis.get(addr).cloned(),
+        }
+    }
+
     pub fn interactive(&self) -> bool {
         !self.manifest.quiet
     }
@@ -44,12 +187,6 @@ impl ChainProcessor {
         self.manifest.init_method == "start"
     }
 
-    pub fn print(&self, message: &str) {
-        if self.interactive() {
-            println!("{}", message);
-        }
-    }
-
     pub fn initial_msg(&self) -> Option<String> {
         self.manifest.init_msg.clone()
     }
@@ -93,62 +230,539 @@ impl ChainProcessor {
                 } else if method != in_method {
                     Err(ProcessorError::UnexpectedMethod)
                 } else {
-                    Ok(params.clone())
+                    self.record(ChainLink::Input {
+                        interface,
+                        method,
+                        params: params.clone(),
+                        mandatory,
+                    });
+                    Ok(params)
                 }
             },
             _ => Err(ProcessorError::UnexpectedChainLinkKind),
         }
     }
 
+    /// Returns params of the next chain link if it is an input for the given
+    /// interface method. Otherwise the chain is left untouched.
+    pub fn next_canned_result(&mut self, in_interface: &str, in_method: &str) -> Option<Value> {
+        match self.chain_iter.peek() {
+            Some(ChainLink::Input { interface, method, .. })
+                if interface == in_interface && method == in_method => {},
+            _ => return None,
+        }
+        match self.chain_iter.next() {
+            Some(ChainLink::Input { interface, method, params, mandatory }) => {
+                let result = params.clone().unwrap_or(json!({}));
+                self.record(ChainLink::Input { interface, method, params, mandatory });
+                Some(result)
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns signing box handle from the chain. Recorded session gets
+    /// placeholder handle both for chain and user signing boxes.
     pub fn next_signing_box(&mut self) -> Result<u32, ProcessorError> {
-        let chlink = self.chain_iter.next().ok_or(
-            if self.interactive() {
-                ProcessorError::InterfaceCallNeeded
-            } else {
-                ProcessorError::NoMoreChainlinks
-            }
-        )?;
+        let result = match self.chain_iter.next() {
+            Some(ChainLink::SigningBox {handle}) => Ok(handle),
+            Some(_) => Err(ProcessorError::UnexpectedChainLinkKind),
+            None if self.interactive() => Err(ProcessorError::InterfaceCallNeeded),
+            None => Err(ProcessorError::NoMoreChainlinks),
+        };
+        if let Ok(_) | Err(ProcessorError::InterfaceCallNeeded) = result {
+            self.record(ChainLink::SigningBox { handle: SIGNING_BOX_PLACEHOLDER });
+        }
+        result
+    }
 
-        match chlink {
-            ChainLink::SigningBox {handle} => {
-                Ok(handle)
+    /// Returns encryption box from the chain. Recorded session gets link
+    /// without handle and keys (keys are not saved to keep them secret).
+    pub fn next_encryption_box(&mut self) -> Result<ChainEncryptionBox, ProcessorError> {
+        let result = match self.chain_iter.next() {
+            Some(ChainLink::EncryptionBox { handle: Some(handle), .. }) => {
+                Ok(ChainEncryptionBox::Handle(handle))
             },
-            _ => Err(ProcessorError::UnexpectedChainLinkKind),
+            Some(ChainLink::EncryptionBox { keys: Some(keys), .. }) => Ok(ChainEncryptionBox::Keys(keys)),
+            Some(ChainLink::EncryptionBox { .. }) => Err(ProcessorError::EncryptionBoxNotDefined),
+            Some(_) => Err(ProcessorError::UnexpectedChainLinkKind),
+            None if self.interactive() => Err(ProcessorError::InterfaceCallNeeded),
+            None => Err(ProcessorError::NoMoreChainlinks),
+        };
+        if let Ok(_) | Err(ProcessorError::InterfaceCallNeeded) = result {
+            self.record(ChainLink::EncryptionBox { handle: None, keys: None });
+        }
+        result
+    }
+
+    /// Checks if the next chain link is an encryption box.
+    pub fn has_encryption_box(&mut self) -> bool {
+        matches!(self.chain_iter.peek(), Some(ChainLink::EncryptionBox { .. }))
+    }
+
+    fn auto_approve(&self, app_kind: &ApproveKind) -> Option<bool> {
+        self.manifest.auto_approve.as_ref().and_then(|vec| {
+            Some(vec.iter().find(|x| *x == app_kind).is_some())
+        })
+    }
+
+    fn approve_by_manifest(&self, app_kind: &ApproveKind) -> Result<bool, ProcessorError> {
+        match self.auto_approve(app_kind) {
+            Some(approved) => Ok(approved),
+            None if self.interactive() => Err(ProcessorError::InteractiveApproveNeeded),
+            None => Ok(false),
         }
     }
 
+    /// Checks if DeBot is allowed to make outbound network requests.
+    pub fn approve_network(&self) -> Result<bool, ProcessorError> {
+        self.approve_by_manifest(&ApproveKind::ApproveNetwork)
+    }
+
+    /// Maximum number of messages DeBot can send without approve.
+    pub fn message_limit(&self) -> Option<u32> {
+        self.manifest.message_limit
+    }
+
+    pub fn loop_limits(&self) -> LoopLimits {
+        self.manifest.limits.clone()
+    }
+
+    /// Checks if DeBot is allowed to send more messages after message limit is reached.
+    pub fn approve_message_limit(&self) -> Result<bool, ProcessorError> {
+        self.approve_by_manifest(&ApproveKind::ApproveMessageLimit)
+    }
+
     pub fn next_approve(&mut self, activity: &DebotActivity) -> Result<bool, ProcessorError> {
         let app_kind = match activity {
             DebotActivity::Transaction {..} => ApproveKind::ApproveOnChainCall,
         };
-        let auto_approve = self.manifest.auto_approve.as_ref().and_then(|vec| {
-            Some(vec.iter().find(|x| **x == app_kind).is_some())
+        let auto_approve = self.auto_approve(&app_kind);
+        self.policy_rule = None;
+
+        let scripted = self.chain_iter.next().map(|chlink| {
+            self.record(chlink.clone());
+            self.scripted_approve(chlink, activity)
         });
+        let policy_action = self.evaluate_policy(activity)?;
 
-        let chlink = self.chain_iter.next();
-        if chlink.is_none() {
-            if auto_approve.is_some() {
-                return Ok(auto_approve.unwrap());
-            } else {
-                if self.interactive() {
-                    return Err(ProcessorError::InteractiveApproveNeeded);
-                } else {
-                    return Ok(false);
+        let result = match (policy_action, scripted) {
+            (Some(PolicyAction::Reject), _) => Ok(false),
+            (_, Some(result)) => result,
+            (Some(PolicyAction::Approve), None) => Ok(true),
+            (Some(PolicyAction::Prompt), None) | (None, None) => {
+                match auto_approve {
+                    Some(approved) if policy_action.is_none() => Ok(approved),
+                    _ if self.interactive() => Err(ProcessorError::InteractiveApproveNeeded),
+                    _ => Ok(false),
+                }
+            },
+        };
+        if let Ok(true) = result {
+            match activity {
+                DebotActivity::Transaction { out, .. } => {
+                    let amount = out.iter().fold(0u64, |total, s| total.saturating_add(s.amount));
+                    self.record_spending(amount);
                 }
             }
         }
+        result
+    }
 
-        // TODO: ?
-        let chlink = chlink.unwrap();
+    /// Checks onchain call chain link against transaction.
+    fn scripted_approve(
+        &mut self,
+        chlink: ChainLink,
+        activity: &DebotActivity,
+    ) -> Result<bool, ProcessorError> {
         match chlink {
-            ChainLink::OnchainCall { approve, iflq: _, ifeq: _ } => {
+            ChainLink::OnchainCall { approve, iflq, ifeq } => {
+                if !approve {
+                    return Ok(false);
+                }
                 match activity {
-                    DebotActivity::Transaction {msg: _, dst: _, out: _, fee: _, setcode: _, signkey: _, signing_box_handle: _} => {
-                        Ok(approve.clone())
+                    DebotActivity::Transaction { dst, out, .. } => {
+                        let spendings: Vec<(u64, &str)> = out
+                            .iter()
+                            .map(|s| (s.amount, s.dst.as_str()))
+                            .collect();
+                        check_conditions(iflq.as_deref(), ifeq.as_deref(), dst, &spendings)
+                            .map(|_| true)
+                            .map_err(|e| {
+                                self.failure = Some(e.clone());
+                                ProcessorError::ApproveConditionFailed(e)
+                            })
                     }
                 }
             },
             _ => Err(ProcessorError::UnexpectedChainLinkKind)
         }
     }
-}
\ No newline at end of file
+
+    /// Evaluates approve policy from manifest (or default one). Returns `None`
+    /// if there is no policy.
+    fn evaluate_policy(&mut self, activity: &DebotActivity) -> Result<Option<PolicyAction>, ProcessorError> {
+        let policy = match self.manifest.policy.as_ref().or(self.default_policy.as_ref()) {
+            Some(policy) => policy,
+            None => return Ok(None),
+        };
+        let decision = match activity {
+            DebotActivity::Transaction { dst, out, fee, setcode, signkey, .. } => {
+                let tx = TransactionInfo {
+                    dst,
+                    out: out.iter().map(|s| (s.amount, s.dst.as_str())).collect(),
+                    fee: *fee,
+                    setcode: *setcode,
+                    signkey,
+                };
+                policy.evaluate(&tx, self.session_total)
+            }
+        };
+        let decision = decision.map_err(|e| {
+            self.failure = Some(e.clone());
+            ProcessorError::ApproveConditionFailed(e)
+        })?;
+        info!("onchain call: {:?} by policy rule \"{}\"", decision.action, decision.rule);
+        self.policy_rule = Some(decision.rule);
+        Ok(Some(decision.action))
+    }
+}
+
+/// Checks `iflq` and `ifeq` conditions of onchain call against transaction.
+/// `iflq` - maximum total amount of outgoing transfers in nanotokens.
+/// `ifeq` - either exact total amount of outgoing transfers or destination
+/// address (raw or user-friendly). Address is compared with every transfer
+/// recipient or with transaction account if there are no transfers.
+fn check_conditions(
+    iflq: Option<&str>,
+    ifeq: Option<&str>,
+    dst: &str,
+    spendings: &[(u64, &str)],
+) -> Result<(), String> {
+    let total = spendings
+        .iter()
+        .try_fold(0u64, |total, s| total.checked_add(s.0))
+        .ok_or_else(|| "onchain call rejected: outgoing amount overflows".to_owned());
+    if let Some(iflq) = iflq {
+        let limit = parse_amount(iflq, "iflq")?;
+        let total = total.clone()?;
+        if total > limit {
+            return Err(format!(
+                "onchain call rejected: outgoing amount {} is greater than iflq {}",
+                total, limit
+            ));
+        }
+    }
+    if let Some(ifeq) = ifeq {
+        if is_address(ifeq) {
+            let recipients: Vec<&str> = if spendings.is_empty() {
+                vec![dst]
+            } else {
+                spendings.iter().map(|s| s.1).collect()
+            };
+            if let Some(addr) = recipients.iter().find(|addr| !same_address(addr, ifeq)) {
+                return Err(format!(
+                    "onchain call rejected: destination {} is not equal to ifeq {}",
+                    addr, ifeq
+                ));
+            }
+        } else {
+            let expected = parse_amount(ifeq, "ifeq")?;
+            let total = total?;
+            if total != expected {
+                return Err(format!(
+                    "onchain call rejected: outgoing amount {} is not equal to ifeq {}",
+                    total, expected
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ton_client::debot::Spending;
+
+    const WALLET: &str = "0:1111111111111111111111111111111111111111111111111111111111111111";
+    const RECIPIENT: &str = "0:2222222222222222222222222222222222222222222222222222222222222222";
+
+    fn transaction(out: Vec<(u64, &str)>) -> DebotActivity {
+        DebotActivity::Transaction {
+            msg: String::new(),
+            dst: WALLET.to_owned(),
+            out: out
+                .into_iter()
+                .map(|(amount, dst)| Spending { amount, dst: dst.to_owned() })
+                .collect(),
+            fee: 1000,
+            setcode: false,
+            signkey: String::new(),
+            signing_box_handle: 0,
+        }
+    }
+
+    fn processor(approve: bool, iflq: Option<&str>, ifeq: Option<&str>) -> ChainProcessor {
+        let mut manifest = DebotManifest::new();
+        manifest.chain = vec![ChainLink::OnchainCall {
+            approve,
+            iflq: iflq.map(|x| x.to_owned()),
+            ifeq: ifeq.map(|x| x.to_owned()),
+        }];
+        let mut processor = ChainProcessor::new();
+        processor.load_manifest(manifest);
+        processor
+    }
+
+    fn approve(
+        approve: bool,
+        iflq: Option<&str>,
+        ifeq: Option<&str>,
+        out: Vec<(u64, &str)>,
+    ) -> Result<bool, ProcessorError> {
+        processor(approve, iflq, ifeq).next_approve(&transaction(out))
+    }
+
+    #[test]
+    fn test_approve_without_conditions() {
+        assert!(approve(true, None, None, vec![(500, RECIPIENT)]).unwrap());
+        assert!(!approve(false, None, None, vec![(500, RECIPIENT)]).unwrap());
+        assert!(!approve(false, Some("1000"), None, vec![(500, RECIPIENT)]).unwrap());
+    }
+
+    #[test]
+    fn test_approve_iflq() {
+        let out = vec![(300, RECIPIENT), (200, RECIPIENT)];
+        assert!(approve(true, Some("500"), None, out.clone()).unwrap());
+        assert!(approve(true, Some("0x1f4"), None, out.clone()).unwrap());
+        assert!(approve(true, Some("0"), None, vec![]).unwrap());
+        assert!(matches!(
+            approve(true, Some("499"), None, out.clone()),
+            Err(ProcessorError::ApproveConditionFailed(_))
+        ));
+        assert!(matches!(
+            approve(true, Some("abc"), None, out),
+            Err(ProcessorError::ApproveConditionFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_approve_ifeq() {
+        let out = vec![(300, RECIPIENT), (200, RECIPIENT)];
+        assert!(approve(true, None, Some("500"), out.clone()).unwrap());
+        assert!(approve(true, None, Some(RECIPIENT), out.clone()).unwrap());
+        assert!(approve(true, None, Some(WALLET), vec![]).unwrap());
+        assert!(approve(true, None, Some(RECIPIENT), vec![]).is_err());
+        assert!(approve(true, None, Some("501"), out.clone()).is_err());
+        assert!(approve(true, None, Some(WALLET), out.clone()).is_err());
+        assert!(approve(true, None, Some(RECIPIENT), vec![(1, RECIPIENT), (1, WALLET)]).is_err());
+    }
+
+    #[test]
+    fn test_approve_ifeq_address_forms() {
+        let out = vec![(300, RECIPIENT)];
+        let upper = format!("0:{}", RECIPIENT[2..].to_uppercase());
+        assert!(approve(true, None, Some(&upper), out.clone()).unwrap());
+        // bounceable and non-bounceable user-friendly forms of RECIPIENT
+        let friendly = "EQAiIiIiIiIiIiIiIiIiIiIiIiIiIiIiIiIiIiIiIiIiIp3C";
+        assert!(approve(true, None, Some(friendly), out.clone()).unwrap());
+        let friendly = "UQAiIiIiIiIiIiIiIiIiIiIiIiIiIiIiIiIiIiIiIiIiIsAH";
+        assert!(approve(true, None, Some(friendly), out.clone()).unwrap());
+        assert!(approve(true, None, Some(friendly), vec![(300, WALLET)]).is_err());
+        // broken checksum
+        let broken = "EQAiIiIiIiIiIiIiIiIiIiIiIiIiIiIiIiIiIiIiIiIiIp3D";
+        assert!(approve(true, None, Some(broken), out).is_err());
+    }
+
+    #[test]
+    fn test_approve_amount_overflow() {
+        let out = vec![(u64::MAX, RECIPIENT), (1, RECIPIENT)];
+        assert!(matches!(
+            approve(true, Some("1000"), None, out.clone()),
+            Err(ProcessorError::ApproveConditionFailed(_))
+        ));
+        assert!(approve(true, None, Some("0"), out.clone()).is_err());
+        assert!(approve(true, None, Some(RECIPIENT), out).unwrap());
+    }
+
+    #[test]
+    fn test_approve_iflq_and_ifeq() {
+        let out = vec![(500, RECIPIENT)];
+        assert!(approve(true, Some("1000"), Some(RECIPIENT), out.clone()).unwrap());
+        assert!(approve(true, Some("100"), Some(RECIPIENT), out.clone()).is_err());
+        assert!(approve(true, Some("1000"), Some(WALLET), out.clone()).is_err());
+    }
+
+    #[test]
+    fn test_approve_network_and_message_limit() {
+        let mut manifest = DebotManifest::new();
+        let mut processor = ChainProcessor::new();
+        processor.load_manifest(manifest.clone());
+        assert!(matches!(processor.approve_network(), Err(ProcessorError::InteractiveApproveNeeded)));
+        assert!(matches!(
+            processor.approve_message_limit(),
+            Err(ProcessorError::InteractiveApproveNeeded)
+        ));
+        assert_eq!(processor.message_limit(), None);
+
+        manifest.auto_approve = Some(vec![ApproveKind::ApproveNetwork]);
+        manifest.message_limit = Some(10);
+        processor.load_manifest(manifest.clone());
+        assert!(processor.approve_network().unwrap());
+        assert!(!processor.approve_message_limit().unwrap());
+        assert_eq!(processor.message_limit(), Some(10));
+
+        manifest.auto_approve = None;
+        manifest.quiet = true;
+        processor.load_manifest(manifest);
+        assert!(!processor.approve_network().unwrap());
+        assert!(!processor.approve_message_limit().unwrap());
+    }
+
+    #[test]
+    fn test_contract_abi() {
+        let mut manifest = DebotManifest::new();
+        manifest.contract_abis.insert(
+            WALLET.trim_start_matches("0:").to_owned(),
+            json!({"ABI version": 2, "functions": []}),
+        );
+        manifest.contract_abis.insert(RECIPIENT.to_owned(), json!(r#"{"version": "2.2"}"#));
+        let mut processor = ChainProcessor::new();
+        processor.load_manifest(manifest);
+        let debot = "0:3333333333333333333333333333333333333333333333333333333333333333";
+        processor.add_debot_abi(debot, Abi::Json("{}".to_owned()));
+
+        assert!(matches!(processor.contract_abi(WALLET), Some(Abi::Json(abi)) if abi.contains("ABI version")));
+        assert!(matches!(processor.contract_abi(RECIPIENT), Some(Abi::Json(abi)) if abi == r#"{"version": "2.2"}"#));
+        assert!(matches!(processor.contract_abi(debot), Some(Abi::Json(abi)) if abi == "{}"));
+        assert!(processor.contract_abi("0:44").is_none());
+    }
+
+    #[test]
+    fn test_approve_policy() {
+        let policy: ApprovePolicy = serde_json::from_value(json!({
+            "rules": [
+                { "name": "no setcode", "action": "Reject", "setcode": true },
+                { "name": "small", "action": "Approve", "maxSessionTotal": "1000", "recipients": [RECIPIENT] }
+            ]
+        }))
+        .unwrap();
+        let mut manifest = DebotManifest::new();
+        manifest.quiet = true;
+        manifest.policy = Some(policy);
+        let mut processor = ChainProcessor::new();
+        processor.load_manifest(manifest);
+
+        assert!(processor.next_approve(&transaction(vec![(600, RECIPIENT)])).unwrap());
+        assert_eq!(processor.take_policy_rule(), Some("small".to_owned()));
+        // session total 600 + 600 exceeds limit, default action is prompt
+        assert!(!processor.next_approve(&transaction(vec![(600, RECIPIENT)])).unwrap());
+        assert_eq!(processor.take_policy_rule(), Some("default".to_owned()));
+        assert!(processor.next_approve(&transaction(vec![(400, RECIPIENT)])).unwrap());
+
+        let mut setcode = transaction(vec![]);
+        let DebotActivity::Transaction { setcode: flag, .. } = &mut setcode;
+        *flag = true;
+        assert!(!processor.next_approve(&setcode).unwrap());
+        assert_eq!(processor.take_policy_rule(), Some("no setcode".to_owned()));
+    }
+
+    #[test]
+    fn test_policy_overrides_chain() {
+        let mut rejecting = processor(true, None, None);
+        rejecting.set_default_policy(Some(ApprovePolicy {
+            default: PolicyAction::Reject,
+            ..Default::default()
+        }));
+        assert!(!rejecting.next_approve(&transaction(vec![(1, RECIPIENT)])).unwrap());
+
+        let mut prompting = processor(true, None, None);
+        prompting.set_default_policy(Some(ApprovePolicy::default()));
+        assert!(prompting.next_approve(&transaction(vec![(1, RECIPIENT)])).unwrap());
+        // no more chain links, prompt in interactive mode
+        assert!(matches!(
+            prompting.next_approve(&transaction(vec![(1, RECIPIENT)])),
+            Err(ProcessorError::InteractiveApproveNeeded)
+        ));
+    }
+
+    #[test]
+    fn test_failure_is_recorded() {
+        let mut processor = processor(true, Some("1"), None);
+        let result = processor.next_approve(&transaction(vec![(2, RECIPIENT)]));
+        assert!(result.is_err());
+        let failure = processor.take_failure().unwrap();
+        assert!(failure.contains("greater than iflq 1"));
+        assert!(processor.take_failure().is_none());
+    }
+
+    #[test]
+    fn test_session_recording() {
+        let mut manifest = DebotManifest::new();
+        manifest.debot_address = WALLET.to_owned();
+        manifest.chain = vec![
+            ChainLink::Input {
+                interface: "menu".to_owned(),
+                method: "select".to_owned(),
+                params: Some(json!({ "index": 1 })),
+                mandatory: false,
+            },
+            ChainLink::SigningBox { handle: 7 },
+        ];
+        let mut processor = ChainProcessor::new();
+        assert!(processor.recorded_manifest().is_none());
+        processor.start_recording();
+        processor.load_manifest(manifest);
+
+        assert!(processor.next_input("menu", "select", &json!({})).is_ok());
+        assert_eq!(processor.next_signing_box().unwrap(), 7);
+        // user answers
+        assert!(matches!(
+            processor.next_input("input", "get", &json!({})),
+            Err(ProcessorError::InterfaceCallNeeded)
+        ));
+        processor.record(ChainLink::Input {
+            interface: "input".to_owned(),
+            method: "get".to_owned(),
+            params: Some(json!({ "value": "hello" })),
+            mandatory: false,
+        });
+        assert!(matches!(processor.next_signing_box(), Err(ProcessorError::InterfaceCallNeeded)));
+        processor.record(ChainLink::OnchainCall { approve: true, iflq: None, ifeq: None });
+        processor.record_approve(ApproveKind::ApproveNetwork, true);
+        processor.record_approve(ApproveKind::ApproveMessageLimit, true);
+        processor.record_approve(ApproveKind::ApproveMessageLimit, false);
+
+        let recorded = serde_json::to_value(processor.recorded_manifest().unwrap()).unwrap();
+        assert_eq!(recorded["debotAddress"], json!(WALLET));
+        assert_eq!(recorded["quiet"], json!(true));
+        assert_eq!(recorded["autoApprove"], json!(["ApproveNetwork"]));
+        assert_eq!(recorded["chain"], json!([
+            { "type": "Input", "interface": "menu", "method": "select", "params": { "index": 1 }, "mandatory": false },
+            { "type": "SigningBox", "handle": SIGNING_BOX_PLACEHOLDER },
+            { "type": "Input", "interface": "input", "method": "get", "params": { "value": "hello" }, "mandatory": false },
+            { "type": "SigningBox", "handle": SIGNING_BOX_PLACEHOLDER },
+            { "type": "OnchainCall", "approve": true, "iflq": null, "ifeq": null },
+        ]));
+
+        let replayed: DebotManifest = serde_json::from_value(recorded).unwrap();
+        assert_eq!(replayed.chain.len(), 5);
+        assert!(replayed.quiet);
+    }
+
+    #[test]
+    fn test_recorded_default_policy() {
+        let policy = ApprovePolicy { default: PolicyAction::Approve, ..Default::default() };
+        let mut processor = ChainProcessor::new();
+        processor.set_default_policy(Some(policy.clone()));
+        processor.start_recording();
+        processor.load_manifest(DebotManifest::new());
+        assert_eq!(processor.recorded_manifest().unwrap().policy, Some(policy));
+
+        let mut manifest = DebotManifest::new();
+        manifest.policy = Some(ApprovePolicy::default());
+        processor.load_manifest(manifest);
+        assert_eq!(processor.recorded_manifest().unwrap().policy, Some(ApprovePolicy::default()));
+    }
+}
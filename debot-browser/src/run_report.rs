@@ -0,0 +1,110 @@
+/*
+* Copyright 2018-2021 TON DEV SOLUTIONS LTD.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific TON DEV software governing permissions and
+* limitations under the License.
+*/
+use serde::Serialize;
+use serde_json::Value;
+
+/// Invocation of one DeBot by another one (`Debot.invoke` action).
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Invocation {
+    /// Address of the invoking DeBot.
+    pub caller: String,
+    /// Address of the invoked DeBot.
+    pub debot: String,
+    /// Name of the executed action.
+    pub action: String,
+    /// Position in the call stack, 1 for invocation made by the main DeBot.
+    pub depth: u32,
+    /// Error returned by the invoked DeBot, `None` if it succeeded.
+    pub error: Option<String>,
+}
+
+/// Function call decoded from the message body.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedCall {
+    pub function: String,
+    pub args: Value,
+}
+
+/// Onchain transaction approve request and user decision.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Approval {
+    /// Account of the transaction.
+    pub dst: String,
+    /// Total amount of outgoing transfers in nanotokens.
+    pub amount: u64,
+    /// Transaction fees in nanotokens.
+    pub fee: u64,
+    /// Called function, `None` if contract ABI is unknown.
+    pub call: Option<DecodedCall>,
+    /// Name of the policy rule which decided the approve.
+    pub rule: Option<String>,
+    pub approved: bool,
+}
+
+/// Details of the last DeBot run.
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RunReport {
+    pub invocations: Vec<Invocation>,
+    pub approvals: Vec<Approval>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_serialize() {
+        let report = RunReport {
+            invocations: vec![Invocation {
+                caller: "0:11".to_owned(),
+                debot: "0:22".to_owned(),
+                action: "invokeTransfer".to_owned(),
+                depth: 1,
+                error: None,
+            }],
+            approvals: vec![Approval {
+                dst: "0:33".to_owned(),
+                amount: 500,
+                fee: 10,
+                call: Some(DecodedCall {
+                    function: "submitTransaction".to_owned(),
+                    args: json!({"value": "500"}),
+                }),
+                rule: Some("small transfers".to_owned()),
+                approved: true,
+            }],
+        };
+        assert_eq!(serde_json::to_value(&report).unwrap(), json!({
+            "invocations": [{
+                "caller": "0:11",
+                "debot": "0:22",
+                "action": "invokeTransfer",
+                "depth": 1,
+                "error": null
+            }],
+            "approvals": [{
+                "dst": "0:33",
+                "amount": 500,
+                "fee": 10,
+                "call": {"function": "submitTransaction", "args": {"value": "500"}},
+                "rule": "small transfers",
+                "approved": true
+            }]
+        }));
+    }
+}
@@ -10,28 +10,74 @@
 * See the License for the specific TON DEV software governing permissions and
 * limitations under the License.
 */
+use super::browser_io::{default_io, SharedBrowserIo};
+use super::events::{BrowserEvent, EventEmitter, SharedEventListener};
+use super::debot_cache::{default_cache, SharedDebotCache};
 use super::config::{make_shared_settings, UserSettings, SharedUserSettings};
 use super::helpers::{load_abi, load_ton_address, TonClient};
-use super::{Callbacks, ChainProcessor, DebotManifest, SupportedInterfaces};
-use log::info;
+use super::http_client::{default_http_client, make_shared_http_client, SharedHttpClient};
+use super::local_engine::{create_engine, DebotEngine, LocalDebots, NetworkSource, SharedDebotSource};
+use super::loop_guard::{with_timeout, LoopGuard, LoopLimits, MessageRecord};
+use super::policy::ApprovePolicy;
+use super::run_report::{Invocation, RunReport};
+use super::trust::{DebotCode, TrustList};
+use super::{ApproveKind, Callbacks, ChainProcessor, DebotManifest, ProcessorError, SupportedInterfaces};
+use log::{info, warn};
 use serde_json::json;
 use std::collections::{HashMap, VecDeque};
-use std::io::{self, BufRead, Write};
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
 use ton_client::abi::{
     decode_message, encode_internal_message, Abi, CallSet, ParamsOfDecodeMessage,
     ParamsOfEncodeInternalMessage,
 };
 use ton_client::boc::{parse_message, ParamsOfParse};
-use ton_client::debot::{DEngine, DebotInfo, DebotInterfaceExecutor, DEBOT_WC};
+use ton_client::debot::{DAction, DebotInfo, DebotInterfaceExecutor, DEBOT_WC};
 
 const BROWSER_ID: &'static str = "0000000000000000000000000000000000000000000000000000000000000000";
 /// Stores Debot info needed for DBrowser.
 struct DebotEntry {
     abi: Abi,
-    dengine: DEngine,
+    dengine: Box<dyn DebotEngine>,
     callbacks: Arc<Callbacks>,
     info: DebotInfo,
+    code: DebotCode,
+}
+
+/// Optional parameters of the browser.
+pub struct BrowserOptions {
+    /// User I/O used for all prompts and messages.
+    pub io: SharedBrowserIo,
+    /// DeBots executed locally. Other DeBots are downloaded from network.
+    pub local_debots: LocalDebots,
+    /// Source of the other DeBots. If not set, DeBots are downloaded from network.
+    pub source: Option<SharedDebotSource>,
+    /// Cache of DeBot info and ABI. By default it is shared by all browsers.
+    pub cache: SharedDebotCache,
+    /// DeBots allowed to run. Manifest can add DeBots to this list.
+    pub trust_list: TrustList,
+    /// Approve policy for onchain calls used if manifest has no policy.
+    pub policy: Option<ApprovePolicy>,
+    /// Receivers of the browser events.
+    pub listeners: Vec<SharedEventListener>,
+    /// Records the session into a replayable manifest.
+    pub record: bool,
+}
+
+impl Default for BrowserOptions {
+    fn default() -> Self {
+        Self {
+            io: default_io(),
+            local_debots: LocalDebots::new(),
+            source: None,
+            cache: default_cache(),
+            trust_list: TrustList::default(),
+            policy: None,
+            listeners: vec![],
+            record: false,
+        }
+    }
 }
 
 /// Top level object. Created only once.
@@ -40,10 +86,33 @@ pub struct TerminalBrowser {
     pub client: TonClient,
     /// User Information used by UserInfo interface
     pub user_settings: SharedUserSettings,
+    /// HTTP transport used by Network interface.
+    pub http_client: SharedHttpClient,
+    /// User I/O.
+    io: SharedBrowserIo,
     /// Address of starting DeBot.
     main_debot_addr: String,
     /// common message queue for both inteface calls and invoke calls (from different debots).
-    msg_queue: VecDeque<String>,
+    /// Each message is stored with its call depth.
+    msg_queue: VecDeque<(String, u32)>,
+    /// Call depth of the messages produced by currently processed message.
+    depth: u32,
+    /// Stops message loop if DeBots are looping.
+    guard: LoopGuard,
+    /// Number of messages processed since the last message limit approve.
+    msg_count: u32,
+    /// DeBots executed locally.
+    local_debots: LocalDebots,
+    /// Source of DeBots which are not local.
+    source: SharedDebotSource,
+    /// Cache of DeBot info and ABI.
+    cache: SharedDebotCache,
+    /// Trust list from browser options.
+    base_trust_list: TrustList,
+    /// Trust list of the current run: browser list and manifest list.
+    trust_list: TrustList,
+    /// Sends browser events to listeners.
+    events: EventEmitter,
     /// Map of instantiated Debots. [addr] -> entry.
     /// New debots are created by invoke requests.
     bots: HashMap<String, DebotEntry>,
@@ -57,6 +126,10 @@ pub struct TerminalBrowser {
     interactive: bool,
     /// Browser exit argument. Initialized only if DeBot sends message to the DeBot Browser address.
     pub exit_arg: Option<serde_json::Value>,
+    /// Addresses of DeBots invoked by `Debot.invoke` actions. The last one is executed now.
+    call_stack: Vec<String>,
+    /// Details of the last run.
+    report: RunReport,
 }
 
 impl TerminalBrowser {
@@ -65,37 +138,78 @@ impl TerminalBrowser {
         user_settings: UserSettings,
         addr: String,
     ) -> Result<Self, String> {
-        let processor = Arc::new(tokio::sync::RwLock::new(ChainProcessor::new()));
-        
+        Self::new_with_options(client, user_settings, addr, BrowserOptions::default()).await
+    }
+
+    pub async fn new_with_options(
+        client: TonClient,
+        user_settings: UserSettings,
+        addr: String,
+        options: BrowserOptions,
+    ) -> Result<Self, String> {
+        let mut processor = ChainProcessor::new();
+        processor.set_default_policy(options.policy);
+        if options.record {
+            processor.start_recording();
+        }
+        let processor = Arc::new(tokio::sync::RwLock::new(processor));
+        let io = options.io;
+        let events = EventEmitter::new(options.listeners);
+
+        let user_settings = make_shared_settings(user_settings);
+
         let callbacks = Arc::new(
             Callbacks::new(
                 client.clone(),
                 processor.clone(),
+                io.clone(),
+                events.clone(),
+                user_settings.clone(),
             )
         );
 
-        let user_settings = make_shared_settings(user_settings);
+        let http_client = make_shared_http_client(default_http_client());
 
         let interfaces = SupportedInterfaces::new(
             client.clone(), 
             user_settings.clone(),
             processor.clone(),
+            http_client.clone(),
+            io.clone(),
         );
 
+        let source = match options.source {
+            Some(source) => source,
+            None => Arc::new(NetworkSource::new(client.clone())),
+        };
+
         // TODO remove clone
         let main_debot_addr = addr.clone();
         
         let mut browser = Self {
             client,
             user_settings,
+            http_client,
+            io,
             main_debot_addr,
             interfaces,
             callbacks,
             processor,
             msg_queue: Default::default(),
+            depth: 0,
+            guard: LoopGuard::new(LoopLimits::default()),
+            msg_count: 0,
+            local_debots: options.local_debots,
+            source,
+            cache: options.cache,
+            events,
+            base_trust_list: options.trust_list.clone(),
+            trust_list: options.trust_list,
             bots: Default::default(),
             interactive: false,
             exit_arg: None,
+            call_stack: vec![],
+            report: RunReport::default(),
         };
 
         let _ = browser.fetch_debot(&addr, false, true).await?;
@@ -111,29 +225,64 @@ impl TerminalBrowser {
     ) -> Result<String, String> {
         let debot_addr = load_ton_address(addr, 0)?;
         let callbacks_ref = Arc::clone(&self.callbacks);
-        let mut dengine = DEngine::new_with_client(
+        let is_local = self.local_debots.contains(&debot_addr);
+        let account = if is_local {
+            self.local_debots.parse_account(self.client.clone(), &debot_addr).await
+        } else {
+            self.source.query_account(&debot_addr).await
+        };
+        let code = match &account {
+            Some(account) => DebotCode::from_account(self.client.clone(), account).await,
+            None => DebotCode::default(),
+        };
+        self.trust_list.check(&debot_addr, &code)?;
+        let code_hash = if is_local { None } else { code.code_hash.clone() };
+        let cached = match &code_hash {
+            Some(hash) => self.cache.write().await.get(&debot_addr, hash),
+            None => None,
+        };
+        let mut dengine = create_engine(
             debot_addr.clone(),
-            None,
+            &self.local_debots,
+            &self.source,
             self.client.clone(),
-            self.callbacks.clone()
-        );
-        let info: DebotInfo = dengine.init().await?.into();
+            self.callbacks.clone(),
+            cached.as_ref().and_then(|(info, _)| info.dabi.clone()),
+        )?;
+        let from_cache = cached.is_some();
+        let (info, abi) = match cached {
+            Some(cached) => {
+                dengine.load().await?;
+                cached
+            },
+            None => {
+                let info = dengine.init().await?;
+                let abi_ref = info.dabi.as_ref();
+                let abi = load_abi(&abi_ref.ok_or(format!("DeBot ABI is not defined"))?)?;
+                if let Some(hash) = &code_hash {
+                    let result = self.cache.write().await
+                        .insert(&debot_addr, hash, info.clone(), abi.clone());
+                    if let Err(e) = result {
+                        warn!("{}", e);
+                    }
+                }
+                (info, abi)
+            },
+        };
         let abi_version = info.dabi_version.clone();
-        let abi_ref = info.dabi.as_ref();
-        let abi = load_abi(&abi_ref.ok_or(format!("DeBot ABI is not defined"))?)?;
-        
+        self.processor.write().await.add_debot_abi(&debot_addr, abi.clone());
+        self.events.emit(BrowserEvent::DebotFetched {
+            address: debot_addr.clone(),
+            name: info.name.clone(),
+            abi_version: abi_version.clone(),
+            cached: from_cache,
+        });
+
         if call_start {
             let mut run_debot = autorun;
             if !autorun {
-                Self::print_info(&info);
-                let _ = terminal_input("Run the DeBot (y/n)?", |val| {
-                    run_debot = match val.as_str() {
-                        "y" => true,
-                        "n" => false,
-                        _ => Err(format!("invalid enter"))?,
-                    };
-                    Ok(())
-                });
+                self.print_info(&info).await;
+                run_debot = self.io.confirm("Run the DeBot?").await?;
             }
             if !run_debot {
                 return Err(format!("DeBot rejected"));
@@ -141,17 +290,19 @@ impl TerminalBrowser {
             dengine.start().await?;
         }
 
-        callbacks_ref.take_messages(&mut self.msg_queue);
+        enqueue(&mut self.msg_queue, &callbacks_ref, self.depth);
 
         self.bots.insert(
-            debot_addr,
+            debot_addr.clone(),
             DebotEntry {
                 abi,
                 dengine,
                 callbacks: callbacks_ref,
                 info,
+                code,
             }
         );
+        self.run_invokes(debot_addr).await?;
         Ok(abi_version)
     }
 
@@ -161,12 +312,38 @@ impl TerminalBrowser {
         interface_id: &String,
         debot_addr: &str,
     ) -> Result<(), String> {
+        if self.events.enabled() {
+            let call = self.decode_interface_call(&msg, interface_id).await;
+            self.events.emit(BrowserEvent::InterfaceCall {
+                debot: debot_addr.to_owned(),
+                interface: interface_id.clone(),
+                function: call.as_ref().map(|(name, _)| name.clone()),
+                args: call.map(|(_, args)| args),
+            });
+        }
         let debot = self
             .bots
             .get_mut(debot_addr)
             .ok_or_else(|| "Internal browser error: debot not found".to_owned())?;
+        self.interfaces.set_caller_abi(debot.info.dabi.clone());
         if let Some(result) = self.interfaces.try_execute(&msg, interface_id, &debot.info.dabi_version).await {
-            let (func_id, return_args) = result?;
+            let (func_id, return_args) = match result {
+                Ok(answer) => answer,
+                Err(e) => {
+                    self.events.emit(BrowserEvent::InterfaceError {
+                        debot: debot_addr.to_owned(),
+                        interface: interface_id.clone(),
+                        error: e.clone(),
+                    });
+                    return Err(e);
+                },
+            };
+            self.events.emit(BrowserEvent::InterfaceAnswer {
+                debot: debot_addr.to_owned(),
+                interface: interface_id.clone(),
+                answer_id: func_id,
+                result: return_args.clone(),
+            });
             let call_set = match func_id {
                 0 => None,
                 _ => CallSet::some_with_function_and_input(&format!("0x{:x}", func_id), return_args),
@@ -185,15 +362,28 @@ impl TerminalBrowser {
             .map_err(|e| format!("{}", e))?
             .message;
             let result = debot.dengine.send(response_msg).await;
-            debot.callbacks.take_messages(&mut self.msg_queue);
+            enqueue(&mut self.msg_queue, &debot.callbacks, self.depth);
             if let Err(e) = result {
-                println!("Debot error: {}", e);
+                self.io.print(&format!("Debot error: {}", e)).await;
             }
+            self.run_invokes(debot_addr.to_owned()).await?;
         }
 
         Ok(())
     }
 
+    /// Decodes function name and arguments of the interface call.
+    async fn decode_interface_call(&self, msg: &str, interface_id: &str) -> Option<(String, serde_json::Value)> {
+        let abi = self.interfaces.get_interfaces().get(interface_id)?.get_abi();
+        let decoded = decode_message(
+            self.client.clone(),
+            ParamsOfDecodeMessage { abi, message: msg.to_owned() },
+        )
+        .await
+        .ok()?;
+        Some((decoded.name, decoded.value.unwrap_or(json!({}))))
+    }
+
     async fn call_debot(&mut self, addr: &str, msg: String) -> Result<(), String> {
         if self.bots.get_mut(addr).is_none() {
             self.fetch_debot(addr, false, !self.interactive).await?;
@@ -207,13 +397,80 @@ impl TerminalBrowser {
             .send(msg)
             .await
             .map_err(|e| format!("Debot failed: {}", e))?;
-        debot.callbacks.take_messages(&mut self.msg_queue);
-        Ok(())
+        enqueue(&mut self.msg_queue, &debot.callbacks, self.depth);
+        self.run_invokes(addr.to_owned()).await
+    }
+
+    /// Executes invocations requested by DeBot `caller`.
+    fn run_invokes<'a>(
+        &'a mut self,
+        caller: String,
+    ) -> Pin<Box<dyn Future<Output = Result<(), String>> + 'a>> {
+        Box::pin(async move {
+            let invokes = self.callbacks.take_invokes();
+            for (debot, action) in invokes {
+                self.invoke_debot(&caller, &debot, action).await?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Runs action of the invoked DeBot and processes all its messages before
+    /// returning control to the caller.
+    async fn invoke_debot(&mut self, caller: &str, debot: &str, action: DAction) -> Result<(), String> {
+        let addr = load_ton_address(debot, 0)?;
+        let max_depth = self.processor.read().await.loop_limits().max_depth;
+        if self.call_stack.contains(&addr) || addr == self.main_debot_addr {
+            return Err(format!("recursive invocation of DeBot {}", addr));
+        }
+        if let Some(max) = max_depth {
+            if self.call_stack.len() as u32 >= max {
+                return Err(format!("invocation depth limit ({}) is exceeded", max));
+            }
+        }
+        self.call_stack.push(addr.clone());
+        let index = self.report.invocations.len();
+        self.report.invocations.push(Invocation {
+            caller: caller.to_owned(),
+            debot: addr.clone(),
+            action: action.name.clone(),
+            depth: self.call_stack.len() as u32,
+            error: None,
+        });
+
+        let caller_queue = std::mem::take(&mut self.msg_queue);
+        let result = self.run_invoked_action(&addr, action).await;
+        self.msg_queue = caller_queue;
+        self.call_stack.pop();
+
+        if let Err(ref e) = result {
+            self.report.invocations[index].error = Some(e.clone());
+        }
+        result
+    }
+
+    async fn run_invoked_action(&mut self, addr: &str, action: DAction) -> Result<(), String> {
+        if self.bots.get(addr).is_none() {
+            self.fetch_debot(addr, false, !self.interactive).await?;
+        }
+        let debot = self
+            .bots
+            .get_mut(addr)
+            .ok_or_else(|| "Internal error: debot not found")?;
+        debot
+            .dengine
+            .execute_action(&action)
+            .await
+            .map_err(|e| format!("Debot failed: {}", e))?;
+        enqueue(&mut self.msg_queue, &debot.callbacks, self.depth);
+        self.run_invokes(addr.to_owned()).await?;
+        self.run_message_loop().await
     }
 
     pub async fn run_message_loop(&mut self) -> Result<(), String> {
         let mut next_msg = self.msg_queue.pop_front();
-        while let Some(msg) = next_msg {
+        while let Some((msg, depth)) = next_msg {
+            self.check_message_limit().await?;
             let parsed = parse_message(self.client.clone(), ParamsOfParse { boc: msg.clone() })
                 .await
                 .map_err(|e| format!("{}", e))?
@@ -231,23 +488,94 @@ impl TerminalBrowser {
             let id = wc_and_addr[1].to_string();
             let wc = i8::from_str_radix(wc_and_addr[0], 10).map_err(|e| format!("{}", e))?;
 
-            if wc == DEBOT_WC {
-                if id == BROWSER_ID {
-                    info!("Message from DeBot to Browser"); 
-                    self.set_exit_arg(msg, msg_src).await?;
-                } else {
-                    self.call_interface(msg, &id, msg_src).await?;
-                }
+            self.guard
+                .check(MessageRecord {
+                    src: msg_src.to_owned(),
+                    dst: msg_dest.to_owned(),
+                    depth,
+                })
+                .map_err(|e| e.to_string())?;
+            // Depth counts consecutive calls between DeBots. Messages produced
+            // in reply to the browser or interfaces start from zero depth.
+            let from_debot = !msg_src.starts_with(&format!("{}:", DEBOT_WC));
+            self.depth = if wc != DEBOT_WC && from_debot { depth + 1 } else { 0 };
+            self.events.emit(BrowserEvent::MessageRouted {
+                src: msg_src.to_owned(),
+                dst: msg_dest.to_owned(),
+                depth,
+            });
+
+            let time_left = self.guard.time_left();
+            with_timeout(time_left, self.route_message(msg, msg_dest, msg_src, wc, id))
+                .await
+                .unwrap_or_else(|| Err(self.guard.time_error().to_string()))?;
+
+            next_msg = self.msg_queue.pop_front();
+        }
+        Ok(())
+    }
+
+    /// Passes message to the browser, interface or DeBot.
+    async fn route_message(
+        &mut self,
+        msg: String,
+        msg_dest: &str,
+        msg_src: &str,
+        wc: i8,
+        id: String,
+    ) -> Result<(), String> {
+        if wc == DEBOT_WC {
+            if id == BROWSER_ID {
+                info!("Message from DeBot to Browser"); 
+                self.set_exit_arg(msg, msg_src).await
             } else {
-                self.call_debot(msg_dest, msg).await?;
+                self.call_interface(msg, &id, msg_src).await
             }
+        } else {
+            self.call_debot(msg_dest, msg).await
+        }
+    }
 
-            next_msg = self.msg_queue.pop_front();
+    async fn check_message_limit(&mut self) -> Result<(), String> {
+        let limit = match self.processor.read().await.message_limit() {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+        if self.msg_count < limit {
+            self.msg_count += 1;
+            return Ok(());
+        }
+        let result = self.processor.read().await.approve_message_limit();
+        let approved = match result {
+            Err(ProcessorError::InteractiveApproveNeeded) => {
+                let info = format!("DeBot has sent {} messages.", self.msg_count);
+                let activity = json!({ "type": "MessageLimit", "limit": limit });
+                let approved = self.io.approve(&info, &activity).await?;
+                self.processor
+                    .write()
+                    .await
+                    .record_approve(ApproveKind::ApproveMessageLimit, approved);
+                approved
+            },
+            Err(e) => Err(format!("{:?}", e))?,
+            Ok(approved) => approved,
+        };
+        if !approved {
+            return Err(format!("message limit ({}) is exceeded", limit));
         }
+        self.msg_count = 1;
         Ok(())
     }
 
     pub async fn run_manifest(&mut self, manifest: DebotManifest) -> Result<Option<serde_json::Value>, String> {
+        self.trust_list = self.base_trust_list.clone();
+        if let Some(list) = &manifest.trust_list {
+            self.trust_list.merge(list);
+        }
+        for (addr, debot) in &self.bots {
+            self.trust_list.check(addr, &debot.code)?;
+        }
+
         let (start, call_set, mut init_message) = {
             let mut processor = self.processor.write().await;
             processor.load_manifest(manifest);
@@ -259,6 +587,11 @@ impl TerminalBrowser {
         };
         
         self.exit_arg = None;
+        self.report = RunReport::default();
+        self.call_stack.clear();
+        self.msg_count = 0;
+        self.depth = 0;
+        self.guard = LoopGuard::new(self.processor.read().await.loop_limits());
 
         let abi = self.bots.get(&self.main_debot_addr)
             .ok_or_else(|| format!("Starting DeBot not found: {}", &self.main_debot_addr))?
@@ -283,28 +616,60 @@ impl TerminalBrowser {
             );
         }
 
+        let mut result = Ok(());
         if let Some(msg) = init_message {
             let addr = self.main_debot_addr.clone();
-            self.call_debot(&addr, msg).await?;
+            let time_left = self.guard.time_left();
+            result = with_timeout(time_left, self.call_debot(&addr, msg))
+                .await
+                .unwrap_or_else(|| Err(self.guard.time_error().to_string()));
         }
-
-        self.run_message_loop().await?;
+        if result.is_ok() {
+            result = self.run_message_loop().await;
+        }
+        self.report.approvals = self.callbacks.take_approvals();
+        if let Some(failure) = self.processor.write().await.take_failure() {
+            result = Err(failure);
+        }
+        self.events.emit(BrowserEvent::Exit {
+            value: self.exit_arg.clone(),
+            error: result.as_ref().err().cloned(),
+        });
+        result?;
 
         Ok(self.exit_arg.clone())
     }
 
-    fn print_info(info: &DebotInfo) {
-        println!("DeBot Info:");
+    /// Returns details of the last run.
+    pub fn report(&self) -> &RunReport {
+        &self.report
+    }
+
+    /// Returns manifest which reproduces the last run in quiet mode.
+    /// `SigningBox` chain links have placeholder handles. `None` if
+    /// recording is off.
+    pub async fn recorded_manifest(&self) -> Option<DebotManifest> {
+        self.processor.read().await.recorded_manifest()
+    }
+
+    /// Turns on session recording. Takes effect from the next run.
+    pub async fn start_recording(&self) {
+        self.processor.write().await.start_recording();
+    }
+
+    async fn print_info(&self, info: &DebotInfo) {
         fn print<'a>(field: &'a Option<String>) -> &'a str {
             field.as_ref().map(|v| v.as_str()).unwrap_or("None")
-        } 
-        println!("Name   : {}", print(&info.name));
-        println!("Version: {}", print(&info.version));
-        println!("Author : {}", print(&info.author));
-        println!("Publisher: {}", print(&info.publisher));
-        println!("Support: {}", print(&info.support));
-        println!("Description: {}", print(&info.caption));
-        println!("{}", print(&info.hello));
+        }
+        let mut text = String::from("DeBot Info:\n");
+        text += &format!("Name   : {}\n", print(&info.name));
+        text += &format!("Version: {}\n", print(&info.version));
+        text += &format!("Author : {}\n", print(&info.author));
+        text += &format!("Publisher: {}\n", print(&info.publisher));
+        text += &format!("Support: {}\n", print(&info.support));
+        text += &format!("Description: {}\n", print(&info.caption));
+        text += print(&info.hello);
+        self.io.print(&text).await;
     }
 
     async fn set_exit_arg(&mut self, message: String, _debot_addr: &str) -> Result<(), String> {
@@ -323,73 +688,476 @@ impl TerminalBrowser {
     }
 }
 
-pub(crate) fn input<R, W>(prefix: &str, reader: &mut R, writer: &mut W) -> String
-where
-    R: BufRead,
-    W: Write,
-{
-    let mut input_str = "".to_owned();
-    let mut argc = 0;
-    while argc == 0 {
-        println!("{}", prefix);
-        if let Err(e) = writer.flush() {
-            println!("failed to flush: {}", e);
-            return input_str;
-        }
-        if let Err(e) = reader.read_line(&mut input_str) {
-            println!("failed to read line: {}", e);
-            return input_str;
-        }
-        argc = input_str
-            .split_whitespace()
-            .map(|x| x.parse::<String>().unwrap())
-            .collect::<Vec<String>>()
-            .len();
-    }
-    input_str.trim().to_owned()
+fn enqueue(queue: &mut VecDeque<(String, u32)>, callbacks: &Callbacks, depth: u32) {
+    let mut messages = VecDeque::new();
+    callbacks.take_messages(&mut messages);
+    queue.extend(messages.into_iter().map(|msg| (msg, depth)));
 }
 
-pub(crate) fn terminal_input<F>(prompt: &str, mut validator: F) -> String
-where
-    F: FnMut(&String) -> Result<(), String>,
-{
-    let stdio = io::stdin();
-    let mut reader = stdio.lock();
-    let mut writer = io::stdout();
-    let mut value = input(prompt, &mut reader, &mut writer);
-    while let Err(e) = validator(&value) {
-        println!("{}. Try again.", e);
-        value = input(prompt, &mut reader, &mut writer);
-    }
-    value
-}
-pub fn action_input(max: usize) -> Result<(usize, usize, Vec<String>), String> {
-    let mut a_str = String::new();
-    let mut argc = 0;
-    let mut argv = vec![];
-    println!();
-    while argc == 0 {
-        print!("debash$ ");
-        let _ = io::stdout().flush();
-        io::stdin()
-            .read_line(&mut a_str)
-            .map_err(|e| format!("failed to read line: {}", e))?;
-        argv = a_str
-            .split_whitespace()
-            .map(|x| x.parse::<String>().expect("parse error"))
-            .collect::<Vec<String>>();
-        argc = argv.len();
-    }
-    let n = usize::from_str_radix(&argv[0], 10)
-        .map_err(|_| format!("Oops! Invalid action. Try again, please."))?;
-    if n > max {
-        Err(format!("Auch! Invalid action. Try again, please."))?;
-    }
-
-    Ok((n, argc, argv))
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::browser_io::ScriptedIo;
+    use crate::debot_cache::DebotCache;
+    use crate::events::EventListener;
+    use crate::helpers::create_client_local;
+    use crate::interfaces::terminal::{ABI as TERMINAL_ABI, ID as TERMINAL_ID};
+    use crate::local_engine::DebotSource;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use tokio::sync::RwLock;
+    use ton_client::debot::BrowserCallbacks;
 
+    const MAIN: &str = "0:1111111111111111111111111111111111111111111111111111111111111111";
+    const OTHER: &str = "0:2222222222222222222222222222222222222222222222222222222222222222";
+    const THIRD: &str = "0:3333333333333333333333333333333333333333333333333333333333333333";
+    /// Id of the fake DeBot function which receives interface answers.
+    const ANSWER_ID: u32 = 0x11;
+    const DEBOT_ABI: &str = r#"{
+        "version": "2.2",
+        "functions": [
+            {"name": "run", "inputs": [], "outputs": []},
+            {"name": "onAnswer", "id": "0x00000011", "inputs": [], "outputs": []}
+        ]
+    }"#;
 
+    /// Action of the fake DeBot.
+    #[derive(Clone)]
+    enum Out {
+        /// Calls `Terminal.print` with answer to `onAnswer`.
+        Print(&'static str),
+        /// Invokes another DeBot.
+        Invoke(&'static str),
+        /// Sends message to browser which stops the run.
+        Exit,
+        /// Fails the engine call.
+        Fail(&'static str),
+        /// Never returns from the engine call.
+        Hang,
+    }
 
-#[cfg(test)]
-mod tests {}
+    /// DeBot which reacts to every engine call with the next step of its
+    /// script. There is no reaction if the script is over. Fails if its
+    /// state is not loaded by `init` or `load`.
+    struct FakeEngine {
+        addr: String,
+        client: TonClient,
+        callbacks: Arc<dyn BrowserCallbacks + Send + Sync>,
+        steps: VecDeque<Vec<Out>>,
+        loaded: bool,
+        inits: Arc<AtomicUsize>,
+        loads: Arc<AtomicUsize>,
+    }
+
+    impl FakeEngine {
+        async fn next_step(&mut self) -> Result<(), String> {
+            if !self.loaded {
+                return Err(format!("state of DeBot {} is not loaded", self.addr));
+            }
+            for out in self.steps.pop_front().unwrap_or_default() {
+                let debot_abi = load_abi(DEBOT_ABI)?;
+                let (abi, dst, call_set) = match out {
+                    Out::Print(text) => (
+                        Abi::Json(TERMINAL_ABI.to_owned()),
+                        format!("{}:{}", DEBOT_WC, TERMINAL_ID),
+                        CallSet::some_with_function_and_input(
+                            "print",
+                            json!({ "answerId": ANSWER_ID.to_string(), "message": text }),
+                        ),
+                    ),
+                    Out::Exit => (debot_abi, format!("{}:{}", DEBOT_WC, BROWSER_ID), None),
+                    Out::Invoke(addr) => {
+                        let action = DAction { name: "run".to_owned(), ..Default::default() };
+                        self.callbacks.invoke_debot(addr.to_owned(), action).await?;
+                        continue;
+                    },
+                    Out::Fail(error) => return Err(error.to_owned()),
+                    Out::Hang => std::future::pending().await,
+                };
+                let message = encode_internal_message(
+                    self.client.clone(),
+                    ParamsOfEncodeInternalMessage {
+                        abi: Some(abi),
+                        address: Some(dst),
+                        src_address: Some(self.addr.clone()),
+                        call_set,
+                        value: "1000000000".to_owned(),
+                        ..Default::default()
+                    },
+                )
+                .await
+                .map_err(|e| format!("{}", e))?
+                .message;
+                self.callbacks.send(message).await;
+            }
+            Ok(())
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl DebotEngine for FakeEngine {
+        async fn init(&mut self) -> Result<DebotInfo, String> {
+            self.inits.fetch_add(1, Ordering::SeqCst);
+            self.loaded = true;
+            let mut info = DebotInfo::default();
+            info.name = Some(self.addr.clone());
+            info.dabi = Some(DEBOT_ABI.to_owned());
+            info.dabi_version = "2.2".to_owned();
+            Ok(info)
+        }
+
+        async fn load(&mut self) -> Result<(), String> {
+            self.loads.fetch_add(1, Ordering::SeqCst);
+            self.loaded = true;
+            Ok(())
+        }
+
+        async fn start(&mut self) -> Result<(), String> {
+            self.next_step().await
+        }
+
+        async fn execute_action(&mut self, _action: &DAction) -> Result<(), String> {
+            self.next_step().await
+        }
+
+        async fn send(&mut self, _message: String) -> Result<(), String> {
+            self.next_step().await
+        }
+    }
+
+    /// Creates fake DeBots and counts network requests of the browser.
+    #[derive(Default)]
+    struct FakeSource {
+        client: Option<TonClient>,
+        scripts: Mutex<HashMap<String, Vec<Vec<Out>>>>,
+        queries: AtomicUsize,
+        inits: Arc<AtomicUsize>,
+        loads: Arc<AtomicUsize>,
+    }
+
+    impl FakeSource {
+        fn new(client: TonClient, scripts: Vec<(&str, Vec<Vec<Out>>)>) -> Arc<Self> {
+            let scripts = scripts.into_iter().map(|(addr, steps)| (addr.to_owned(), steps)).collect();
+            Arc::new(Self { client: Some(client), scripts: Mutex::new(scripts), ..Default::default() })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl DebotSource for FakeSource {
+        async fn query_account(&self, _addr: &str) -> Option<serde_json::Value> {
+            self.queries.fetch_add(1, Ordering::SeqCst);
+            Some(json!({ "code_hash": "aa" }))
+        }
+
+        fn create_engine(
+            &self,
+            addr: String,
+            _abi: Option<String>,
+            callbacks: Arc<dyn BrowserCallbacks + Send + Sync>,
+        ) -> Box<dyn DebotEngine> {
+            let steps = self.scripts.lock().unwrap().remove(&addr).unwrap_or_default();
+            Box::new(FakeEngine {
+                addr,
+                client: self.client.clone().unwrap(),
+                callbacks,
+                steps: steps.into(),
+                loaded: false,
+                inits: self.inits.clone(),
+                loads: self.loads.clone(),
+            })
+        }
+    }
+
+    #[derive(Default)]
+    struct EventLog {
+        events: Mutex<Vec<BrowserEvent>>,
+    }
+
+    impl EventLog {
+        /// Returns received events except `MessageRouted`.
+        fn events(&self) -> Vec<BrowserEvent> {
+            self.events
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|event| !matches!(event, BrowserEvent::MessageRouted { .. }))
+                .cloned()
+                .collect()
+        }
+
+        fn types(&self) -> Vec<String> {
+            self.events()
+                .iter()
+                .map(|event| serde_json::to_value(event).unwrap()["type"].as_str().unwrap().to_owned())
+                .collect()
+        }
+    }
+
+    impl EventListener for EventLog {
+        fn on_event(&self, event: &BrowserEvent) {
+            self.events.lock().unwrap().push(event.clone());
+        }
+    }
+
+    async fn create_browser(
+        client: TonClient,
+        source: Arc<FakeSource>,
+        io: Arc<ScriptedIo>,
+        events: Arc<EventLog>,
+    ) -> TerminalBrowser {
+        let cache = Arc::new(RwLock::new(DebotCache::new()));
+        create_browser_with_cache(client, source, io, events, cache).await
+    }
+
+    async fn create_browser_with_cache(
+        client: TonClient,
+        source: Arc<FakeSource>,
+        io: Arc<ScriptedIo>,
+        events: Arc<EventLog>,
+        cache: Arc<RwLock<DebotCache>>,
+    ) -> TerminalBrowser {
+        let options = BrowserOptions {
+            io,
+            source: Some(source),
+            cache,
+            listeners: vec![events],
+            ..Default::default()
+        };
+        TerminalBrowser::new_with_options(client, UserSettings::default(), MAIN.to_owned(), options)
+            .await
+            .unwrap()
+    }
+
+    /// Manifest which calls `run` function of the main DeBot.
+    fn manifest(quiet: bool) -> DebotManifest {
+        let mut manifest = DebotManifest::new();
+        manifest.debot_address = MAIN.to_owned();
+        manifest.init_method = "run".to_owned();
+        manifest.quiet = quiet;
+        manifest
+    }
+
+    #[tokio::test]
+    async fn test_cache_hit_loads_state() {
+        let client = create_client_local().unwrap();
+        let source = FakeSource::new(client.clone(), vec![]);
+        let cache = Arc::new(RwLock::new(DebotCache::new()));
+        for _ in 0..2 {
+            let options = BrowserOptions {
+                source: Some(source.clone()),
+                cache: cache.clone(),
+                ..Default::default()
+            };
+            let browser = TerminalBrowser::new_with_options(
+                client.clone(),
+                UserSettings::default(),
+                MAIN.to_owned(),
+                options,
+            )
+            .await
+            .unwrap();
+            assert_eq!(browser.bots[MAIN].info.name, Some(MAIN.to_owned()));
+        }
+        assert_eq!(source.queries.load(Ordering::SeqCst), 2);
+        assert_eq!(source.inits.load(Ordering::SeqCst), 1);
+        assert_eq!(source.loads.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cached_sub_debot_is_loaded() {
+        let client = create_client_local().unwrap();
+        let cache = Arc::new(RwLock::new(DebotCache::new()));
+        for _ in 0..2 {
+            let source = FakeSource::new(client.clone(), vec![
+                (MAIN, vec![vec![Out::Invoke(OTHER)], vec![Out::Exit]]),
+                (OTHER, vec![vec![Out::Print("other")], vec![]]),
+            ]);
+            let io = Arc::new(ScriptedIo::new(Vec::<String>::new()));
+            let mut browser = create_browser_with_cache(
+                client.clone(),
+                source.clone(),
+                io.clone(),
+                Default::default(),
+                cache.clone(),
+            )
+            .await;
+            // invoked DeBot receives action and interface answer
+            browser.run_manifest(manifest(false)).await.unwrap();
+            assert_eq!(io.output(), vec!["other"]);
+            assert_eq!(browser.report().invocations, vec![invocation(MAIN, OTHER, 1, None)]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_events() {
+        let client = create_client_local().unwrap();
+        let source = FakeSource::new(client.clone(), vec![(MAIN, vec![vec![Out::Print("Hello")], vec![Out::Exit]])]);
+        let io = Arc::new(ScriptedIo::new(Vec::<String>::new()));
+        let events = Arc::new(EventLog::default());
+        let mut browser = create_browser(client, source, io.clone(), events.clone()).await;
+
+        let exit_arg = browser.run_manifest(manifest(false)).await.unwrap();
+        assert!(exit_arg.is_some());
+        assert_eq!(io.output(), vec!["Hello"]);
+        assert_eq!(events.types(), vec!["DebotFetched", "InterfaceCall", "InterfaceAnswer", "Exit"]);
+
+        let events = events.events();
+        match &events[1] {
+            BrowserEvent::InterfaceCall { debot, interface, function, .. } => {
+                assert_eq!(debot, MAIN);
+                assert_eq!(interface, TERMINAL_ID);
+                assert_eq!(function.as_deref(), Some("print"));
+            },
+            event => panic!("unexpected event {:?}", event),
+        }
+        match &events[2] {
+            BrowserEvent::InterfaceAnswer { answer_id, .. } => assert_eq!(*answer_id, ANSWER_ID),
+            event => panic!("unexpected event {:?}", event),
+        }
+        match events.last().unwrap() {
+            BrowserEvent::Exit { value, error } => {
+                assert!(value.is_some());
+                assert!(error.is_none());
+            },
+            event => panic!("unexpected event {:?}", event),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_message_limit_rejected_in_quiet_mode() {
+        let client = create_client_local().unwrap();
+        let steps = vec![vec![Out::Print("first"), Out::Print("second")], vec![], vec![Out::Exit]];
+        let source = FakeSource::new(client.clone(), vec![(MAIN, steps)]);
+        let io = Arc::new(ScriptedIo::new(Vec::<String>::new()));
+        let events = Arc::new(EventLog::default());
+        let mut browser = create_browser(client, source, io.clone(), events.clone()).await;
+
+        let mut manifest = manifest(true);
+        manifest.message_limit = Some(1);
+        let err = browser.run_manifest(manifest).await.unwrap_err();
+        assert_eq!(err, "message limit (1) is exceeded");
+        assert!(io.output().is_empty());
+        let calls = events
+            .events()
+            .into_iter()
+            .filter(|event| matches!(event, BrowserEvent::InterfaceCall { .. }))
+            .count();
+        assert_eq!(calls, 1);
+    }
+
+    #[tokio::test]
+    async fn test_message_limit_approved_interactively() {
+        let client = create_client_local().unwrap();
+        let steps = vec![vec![Out::Print("first"), Out::Print("second")], vec![], vec![Out::Exit]];
+        let source = FakeSource::new(client.clone(), vec![(MAIN, steps)]);
+        let io = Arc::new(ScriptedIo::new(vec!["y", "y"]));
+        let events = Arc::new(EventLog::default());
+        let mut browser = create_browser(client, source, io.clone(), events).await;
+
+        let mut manifest = manifest(false);
+        manifest.message_limit = Some(1);
+        let exit_arg = browser.run_manifest(manifest).await.unwrap();
+        assert!(exit_arg.is_some());
+        let approve = ["DeBot has sent 1 messages.", "Allow DeBot to continue?"];
+        let mut expected = vec!["first"];
+        expected.extend_from_slice(&approve);
+        expected.push("second");
+        // exit message needs approve too
+        expected.extend_from_slice(&approve);
+        assert_eq!(io.output(), expected);
+    }
+
+    fn invocation(caller: &str, debot: &str, depth: u32, error: Option<&str>) -> Invocation {
+        Invocation {
+            caller: caller.to_owned(),
+            debot: debot.to_owned(),
+            action: "run".to_owned(),
+            depth,
+            error: error.map(|e| e.to_owned()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invocation_order() {
+        let client = create_client_local().unwrap();
+        let source = FakeSource::new(client.clone(), vec![
+            (MAIN, vec![vec![Out::Print("main"), Out::Invoke(OTHER)], vec![Out::Exit]]),
+            (OTHER, vec![vec![Out::Print("other"), Out::Invoke(THIRD)]]),
+            (THIRD, vec![vec![Out::Print("third")]]),
+        ]);
+        let io = Arc::new(ScriptedIo::new(Vec::<String>::new()));
+        let mut browser = create_browser(client, source, io.clone(), Default::default()).await;
+
+        browser.run_manifest(manifest(false)).await.unwrap();
+        // invoked DeBot finishes before its caller continues
+        assert_eq!(io.output(), vec!["third", "other", "main"]);
+        assert_eq!(browser.report().invocations, vec![
+            invocation(MAIN, OTHER, 1, None),
+            invocation(OTHER, THIRD, 2, None),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_invocation_depth_limit() {
+        let client = create_client_local().unwrap();
+        let source = FakeSource::new(client.clone(), vec![
+            (MAIN, vec![vec![Out::Invoke(OTHER)]]),
+            (OTHER, vec![vec![Out::Invoke(THIRD)]]),
+            (THIRD, vec![vec![Out::Print("third")]]),
+        ]);
+        let io = Arc::new(ScriptedIo::new(Vec::<String>::new()));
+        let mut browser = create_browser(client, source, io.clone(), Default::default()).await;
+
+        let mut manifest = manifest(false);
+        manifest.limits.max_depth = Some(1);
+        let err = browser.run_manifest(manifest).await.unwrap_err();
+        assert_eq!(err, "invocation depth limit (1) is exceeded");
+        assert!(io.output().is_empty());
+        assert_eq!(browser.report().invocations, vec![
+            invocation(MAIN, OTHER, 1, Some("invocation depth limit (1) is exceeded")),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_invocation_error() {
+        let client = create_client_local().unwrap();
+        let source = FakeSource::new(client.clone(), vec![
+            (MAIN, vec![vec![Out::Invoke(OTHER), Out::Exit]]),
+            (OTHER, vec![vec![Out::Fail("boom")]]),
+        ]);
+        let io = Arc::new(ScriptedIo::new(Vec::<String>::new()));
+        let events = Arc::new(EventLog::default());
+        let mut browser = create_browser(client, source, io, events.clone()).await;
+
+        let err = browser.run_manifest(manifest(false)).await.unwrap_err();
+        assert_eq!(err, "Debot failed: boom");
+        assert_eq!(browser.report().invocations, vec![
+            invocation(MAIN, OTHER, 1, Some("Debot failed: boom")),
+        ]);
+        assert_eq!(events.events().last(), Some(&BrowserEvent::Exit {
+            value: None,
+            error: Some("Debot failed: boom".to_owned()),
+        }));
+    }
+
+    #[tokio::test]
+    async fn test_message_handling_timeout() {
+        // DeBot hangs while handling the interface answer or the initial message
+        let scripts = vec![vec![vec![Out::Print("first")], vec![Out::Hang]], vec![vec![Out::Hang]]];
+        for steps in scripts {
+            let client = create_client_local().unwrap();
+            let source = FakeSource::new(client.clone(), vec![(MAIN, steps)]);
+            let io = Arc::new(ScriptedIo::new(Vec::<String>::new()));
+            let mut browser = create_browser(client, source, io, Default::default()).await;
+
+            let mut manifest = manifest(false);
+            manifest.limits.max_time_ms = Some(100);
+            let run = browser.run_manifest(manifest);
+            let err = tokio::time::timeout(std::time::Duration::from_secs(5), run)
+                .await
+                .expect("run is not stopped by time limit")
+                .unwrap_err();
+            assert!(err.contains(r#""limit":"maxTimeMs""#), "{}", err);
+        }
+    }
+}
@@ -1,8 +1,9 @@
-use super::term_browser::input;
-use crate::crypto::load_keypair;
-use crate::helpers::{TonClient, HD_PATH};
+use crate::browser_io::SharedBrowserIo;
+use crate::config::MnemonicConfig;
+use crate::crypto::{is_mnemonic, load_keypair};
+use crate::helpers::TonClient;
+use crate::keystore::KeyStore;
 use serde_json::json;
-use std::io::{self};
 use std::sync::Arc;
 use ton_client::crypto::{
     chacha20, nacl_box, nacl_box_open, nacl_secret_box, nacl_secret_box_open,
@@ -20,11 +21,49 @@ pub(crate) enum EncryptionBoxType {
     ChaCha20,
 }
 
+impl EncryptionBoxType {
+    /// Algorithm name as returned in encryption box info.
+    pub fn algorithm(&self) -> &'static str {
+        match self {
+            EncryptionBoxType::SecretNaCl => "NaclSecretBox",
+            EncryptionBoxType::NaCl => "NaclBox",
+            EncryptionBoxType::ChaCha20 => "ChaCha20",
+        }
+    }
+
+    /// Nonce length in bytes.
+    pub fn nonce_len(&self) -> usize {
+        match self {
+            EncryptionBoxType::SecretNaCl | EncryptionBoxType::NaCl => 24,
+            EncryptionBoxType::ChaCha20 => 12,
+        }
+    }
+
+    /// Checks that `nonce` is a hex string of the algorithm nonce length.
+    pub fn check_nonce(&self, nonce: &str) -> Result<(), String> {
+        let bytes = hex::decode(nonce).map_err(|e| format!("invalid nonce: {}", e))?;
+        if bytes.len() != self.nonce_len() {
+            return Err(format!(
+                "invalid nonce length {}: {} requires {} bytes",
+                bytes.len(), self.algorithm(), self.nonce_len()
+            ));
+        }
+        Ok(())
+    }
+}
+
 pub(crate) struct ParamsOfTerminalEncryptionBox {
     pub box_type: EncryptionBoxType,
     pub their_pubkey: String,
     pub nonce: String,
     pub context: TonClient,
+    pub io: SharedBrowserIo,
+    /// Keystore whose entries can be chosen by name or public key.
+    pub keystore: Option<KeyStore>,
+    /// Seed phrase or path to keypair file. User is asked for keys if not defined.
+    pub keys: Option<String>,
+    /// Used to derive keys from seed phrase.
+    pub mnemonic: MnemonicConfig,
 }
 
 pub struct NaClSecretBox {
@@ -32,6 +71,8 @@ pub struct NaClSecretBox {
     pub key: String,
     /// 96-bit nonce, encoded in `hex`.
     pub nonce: String,
+    /// HD path used to derive the key, `None` if key is not derived from seed phrase.
+    pub hdpath: Option<String>,
     /// Client params.context.
     pub client: TonClient,
 }
@@ -41,6 +82,8 @@ pub struct ChaChaBox {
     pub key: String,
     /// 96-bit nonce, encoded in `hex`.
     pub nonce: String,
+    /// HD path used to derive the key, `None` if key is not derived from seed phrase.
+    pub hdpath: Option<String>,
     /// Client params.context.
     pub client: TonClient,
 }
@@ -52,6 +95,8 @@ pub struct NaClBox {
     pub secret: String,
     /// Nonce, encoded in `hex`.
     pub nonce: String,
+    /// HD path used to derive the key, `None` if key is not derived from seed phrase.
+    pub hdpath: Option<String>,
     /// Client params.context.
     pub client: TonClient,
 }
@@ -60,14 +105,14 @@ pub struct NaClBox {
 impl ton_client::crypto::EncryptionBox for NaClSecretBox {
     async fn get_info(&self, _context: Arc<ClientContext>) -> ClientResult<EncryptionBoxInfo> {
         Ok(EncryptionBoxInfo {
-            hdpath: Some(String::from(HD_PATH)),
+            hdpath: self.hdpath.clone(),
             algorithm: Some(format!("NaclSecretBox")),
             options: Some(json!({"nonce": self.nonce.clone()})),
             public: None,
         })
     }
     async fn encrypt(&self, _context: Arc<ClientContext>, data: &String) -> ClientResult<String> {
-        Ok(nacl_secret_box(
+        nacl_secret_box(
             self.client.clone(),
             ParamsOfNaclSecretBox {
                 decrypted: data.clone(),
@@ -75,11 +120,10 @@ impl ton_client::crypto::EncryptionBox for NaClSecretBox {
                 nonce: self.nonce.clone(),
             },
         )
-        .unwrap()
-        .encrypted)
+        .map(|r| r.encrypted)
     }
     async fn decrypt(&self, _context: Arc<ClientContext>, data: &String) -> ClientResult<String> {
-        Ok(nacl_secret_box_open(
+        nacl_secret_box_open(
             self.client.clone(),
             ParamsOfNaclSecretBoxOpen {
                 encrypted: data.clone(),
@@ -87,8 +131,7 @@ impl ton_client::crypto::EncryptionBox for NaClSecretBox {
                 nonce: self.nonce.clone(),
             },
         )
-        .unwrap()
-        .decrypted)
+        .map(|r| r.decrypted)
     }
 }
 
@@ -96,14 +139,14 @@ impl ton_client::crypto::EncryptionBox for NaClSecretBox {
 impl ton_client::crypto::EncryptionBox for ChaChaBox {
     async fn get_info(&self, _context: Arc<ClientContext>) -> ClientResult<EncryptionBoxInfo> {
         Ok(EncryptionBoxInfo {
-            hdpath: Some(String::from(HD_PATH)),
+            hdpath: self.hdpath.clone(),
             algorithm: Some(format!("ChaCha20")),
             options: Some(json!({"nonce": self.nonce.clone()})),
             public: None,
         })
     }
     async fn encrypt(&self, _context: Arc<ClientContext>, data: &String) -> ClientResult<String> {
-        Ok(chacha20(
+        chacha20(
             self.client.clone(),
             ParamsOfChaCha20 {
                 data: data.clone(),
@@ -111,11 +154,10 @@ impl ton_client::crypto::EncryptionBox for ChaChaBox {
                 nonce: self.nonce.clone(),
             },
         )
-        .unwrap()
-        .data)
+        .map(|r| r.data)
     }
     async fn decrypt(&self, _context: Arc<ClientContext>, data: &String) -> ClientResult<String> {
-        Ok(chacha20(
+        chacha20(
             self.client.clone(),
             ParamsOfChaCha20 {
                 data: data.clone(),
@@ -123,8 +165,7 @@ impl ton_client::crypto::EncryptionBox for ChaChaBox {
                 nonce: self.nonce.clone(),
             },
         )
-        .unwrap()
-        .data)
+        .map(|r| r.data)
     }
 }
 
@@ -132,7 +173,7 @@ impl ton_client::crypto::EncryptionBox for ChaChaBox {
 impl ton_client::crypto::EncryptionBox for NaClBox {
     async fn get_info(&self, _context: Arc<ClientContext>) -> ClientResult<EncryptionBoxInfo> {
         Ok(EncryptionBoxInfo {
-            hdpath: Some(String::from(HD_PATH)),
+            hdpath: self.hdpath.clone(),
             algorithm: Some(format!("NaclBox")),
             options: Some(json!({
                 "their_public": self.their_pubkey.clone(),
@@ -141,7 +182,7 @@ impl ton_client::crypto::EncryptionBox for NaClBox {
         })
     }
     async fn encrypt(&self, _context: Arc<ClientContext>, data: &String) -> ClientResult<String> {
-        Ok(nacl_box(
+        nacl_box(
             self.client.clone(),
             ParamsOfNaclBox {
                 decrypted: data.clone(),
@@ -150,11 +191,10 @@ impl ton_client::crypto::EncryptionBox for NaClBox {
                 secret: self.secret.clone(),
             },
         )
-        .unwrap()
-        .encrypted)
+        .map(|r| r.encrypted)
     }
     async fn decrypt(&self, _context: Arc<ClientContext>, data: &String) -> ClientResult<String> {
-        Ok(nacl_box_open(
+        nacl_box_open(
             self.client.clone(),
             ParamsOfNaclBoxOpen {
                 encrypted: data.clone(),
@@ -163,8 +203,7 @@ impl ton_client::crypto::EncryptionBox for NaClBox {
                 secret: self.secret.clone(),
             },
         )
-        .unwrap()
-        .decrypted)
+        .map(|r| r.decrypted)
     }
 }
 
@@ -189,14 +228,27 @@ impl Drop for TerminalEncryptionBox {
 impl TerminalEncryptionBox {
     pub async fn new(params: ParamsOfTerminalEncryptionBox) -> Result<Self, String> {
         let key: String;
+        let hdpath: Option<String>;
 
+        params.box_type.check_nonce(&params.nonce)?;
         {
-            let stdio = io::stdin();
-            let mut reader = stdio.lock();
-            let mut writer = io::stdout();
-            let enter_str = "enter seed phrase or path to keypair file";
-            let value = input(enter_str, &mut reader, &mut writer);
-            let pair = load_keypair(&value).map_err(|e| e.to_string())?;
+            let enter_str = match params.keystore {
+                Some(_) => "enter seed phrase, path to keypair file or keystore key name",
+                None => "enter seed phrase or path to keypair file",
+            };
+            let value = match params.keys {
+                Some(keys) => keys,
+                None => params.io.secret(enter_str).await?,
+            };
+            let keystore = params.keystore.as_ref().filter(|ks| ks.find(&value).is_some());
+            let pair = match keystore {
+                Some(keystore) => keystore.unlock_interactive(&value, params.io.as_ref(), 3).await?,
+                None => load_keypair(&value, &params.mnemonic).map_err(|e| e.to_string())?,
+            };
+            hdpath = match keystore {
+                None if is_mnemonic(&value) => Some(params.mnemonic.hd_path.clone()),
+                _ => None,
+            };
             key = format!("{:064}", pair.secret);
         }
 
@@ -207,6 +259,7 @@ impl TerminalEncryptionBox {
                     NaClSecretBox {
                         key: key,
                         nonce: params.nonce,
+                        hdpath,
                         client: params.context.clone(),
                     },
                 )
@@ -221,6 +274,7 @@ impl TerminalEncryptionBox {
                         their_pubkey: params.their_pubkey,
                         secret: key,
                         nonce: params.nonce,
+                        hdpath,
                         client: params.context.clone(),
                     },
                 )
@@ -234,6 +288,7 @@ impl TerminalEncryptionBox {
                     ChaChaBox {
                         key: key,
                         nonce: params.nonce,
+                        hdpath,
                         client: params.context.clone(),
                     },
                 )
@@ -251,3 +306,134 @@ impl TerminalEncryptionBox {
         self.handle.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::create_client_local;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use ton_client::crypto::EncryptionBox;
+
+    const ITERATIONS: usize = 64;
+    const NACL_NONCE_LEN: usize = 24;
+    const CHACHA_NONCE_LEN: usize = 12;
+
+    fn random_bytes(rng: &mut StdRng, len: usize) -> Vec<u8> {
+        (0..len).map(|_| rng.gen()).collect()
+    }
+
+    fn random_hex(rng: &mut StdRng, len: usize) -> String {
+        hex::encode(random_bytes(rng, len))
+    }
+
+    fn random_data(rng: &mut StdRng) -> String {
+        let len = rng.gen_range(0, 512);
+        base64::encode(&random_bytes(rng, len))
+    }
+
+    /// Random nonce of any length except `valid_len`, sometimes not a hex at all.
+    fn random_bad_nonce(rng: &mut StdRng, valid_len: usize) -> String {
+        if rng.gen_bool(0.2) {
+            return format!("{}zz", random_hex(rng, valid_len));
+        }
+        let mut len = rng.gen_range(0, 64);
+        if len == valid_len {
+            len += 1;
+        }
+        random_hex(rng, len)
+    }
+
+    /// Encrypted data must be decrypted back, arbitrary data must not panic.
+    async fn check_box(client: TonClient, enc_box: &dyn EncryptionBox, rng: &mut StdRng, authenticated: bool) {
+        for _ in 0..ITERATIONS {
+            let data = random_data(rng);
+            let encrypted = enc_box.encrypt(client.clone(), &data).await.unwrap();
+            let decrypted = enc_box.decrypt(client.clone(), &encrypted).await.unwrap();
+            assert_eq!(decrypted, data);
+
+            let garbage = random_data(rng);
+            let result = enc_box.decrypt(client.clone(), &garbage).await;
+            if authenticated {
+                assert!(result.is_err());
+            }
+        }
+        assert!(enc_box.encrypt(client.clone(), &"not base64!".to_owned()).await.is_err());
+        assert!(enc_box.decrypt(client.clone(), &"not base64!".to_owned()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_nacl_secret_box_random_input() {
+        let client = create_client_local().unwrap();
+        let mut rng = StdRng::seed_from_u64(0x5ec2e7);
+        let valid = NaClSecretBox {
+            key: random_hex(&mut rng, 32),
+            nonce: random_hex(&mut rng, NACL_NONCE_LEN),
+            hdpath: None,
+            client: client.clone(),
+        };
+        check_box(client.clone(), &valid, &mut rng, true).await;
+        for _ in 0..ITERATIONS {
+            let enc_box = NaClSecretBox {
+                key: random_hex(&mut rng, 32),
+                nonce: random_bad_nonce(&mut rng, NACL_NONCE_LEN),
+                hdpath: None,
+                client: client.clone(),
+            };
+            let data = random_data(&mut rng);
+            assert!(enc_box.encrypt(client.clone(), &data).await.is_err());
+            assert!(enc_box.decrypt(client.clone(), &data).await.is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chacha20_box_random_input() {
+        let client = create_client_local().unwrap();
+        let mut rng = StdRng::seed_from_u64(0xc4ac4a);
+        let valid = ChaChaBox {
+            key: random_hex(&mut rng, 32),
+            nonce: random_hex(&mut rng, CHACHA_NONCE_LEN),
+            hdpath: None,
+            client: client.clone(),
+        };
+        // Stream cipher: any data is decrypted to something.
+        check_box(client.clone(), &valid, &mut rng, false).await;
+        for _ in 0..ITERATIONS {
+            let enc_box = ChaChaBox {
+                key: random_hex(&mut rng, 32),
+                nonce: random_bad_nonce(&mut rng, CHACHA_NONCE_LEN),
+                hdpath: None,
+                client: client.clone(),
+            };
+            let data = random_data(&mut rng);
+            assert!(enc_box.encrypt(client.clone(), &data).await.is_err());
+            assert!(enc_box.decrypt(client.clone(), &data).await.is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_nacl_box_random_input() {
+        let client = create_client_local().unwrap();
+        let mut rng = StdRng::seed_from_u64(0x7ac1b0);
+        let valid = NaClBox {
+            their_pubkey: random_hex(&mut rng, 32),
+            secret: random_hex(&mut rng, 32),
+            nonce: random_hex(&mut rng, NACL_NONCE_LEN),
+            hdpath: None,
+            client: client.clone(),
+        };
+        check_box(client.clone(), &valid, &mut rng, true).await;
+        for _ in 0..ITERATIONS {
+            let enc_box = NaClBox {
+                their_pubkey: random_hex(&mut rng, 32),
+                secret: random_hex(&mut rng, 32),
+                nonce: random_bad_nonce(&mut rng, NACL_NONCE_LEN),
+                hdpath: None,
+                client: client.clone(),
+            };
+            let data = random_data(&mut rng);
+            assert!(enc_box.encrypt(client.clone(), &data).await.is_err());
+            assert!(enc_box.decrypt(client.clone(), &data).await.is_err());
+        }
+    }
+}
@@ -1,8 +1,9 @@
 use crate::helpers::read_keys;
 use super::crypto::load_keypair;
 use super::helpers::TonClient;
-use super::term_browser::input;
-use std::io::{self, BufRead, BufReader, Read, Write};
+use crate::browser_io::BrowserIo;
+use crate::config::MnemonicConfig;
+use crate::keystore::KeyStore;
 use ton_client::crypto::{
     get_signing_box, remove_signing_box, KeyPair, RegisteredSigningBox, SigningBoxHandle,
 };
@@ -13,33 +14,24 @@ pub(super) struct TerminalSigningBox {
 }
 
 impl TerminalSigningBox {
-    pub async fn new<R: Read>(
+    pub async fn new(
         client: TonClient,
         possible_keys: Vec<String>,
-        reader: Option<BufReader<R>>,
+        io: &(dyn BrowserIo + Send + Sync),
+        keystore: Option<&KeyStore>,
+        mnemonic: &MnemonicConfig,
     ) -> Result<Self, String> {
-        let keys = {
-            if let Some(mut reader) = reader {
-                let mut writer = io::stdout();
-                input_keys(None, possible_keys, &mut reader, &mut writer, 3)?
-            } else {
-                let stdio = std::io::stdin();
-                let mut reader = stdio.lock();
-                let mut writer = io::stdout();
-                input_keys(None, possible_keys, &mut reader, &mut writer, 3)?
-            }
-        };
-        let handle = get_signing_box(client.clone(), keys)
-            .await
-            .map(|r| r.handle)
-            .map_err(|e| e.to_string())?;
-
-        Ok(Self { handle, client })
+        let keys = input_keys(None, possible_keys, io, 3, keystore, mnemonic).await?;
+        Self::new_with_keys(client, keys).await
     }
 
     //#[cfg(not(target_arch = "wasm32"))]
     pub async fn new_with_keypath(client: TonClient, keys_path: String) -> Result<Self, String> {
         let keys = read_keys(&keys_path).unwrap_or_default();
+        Self::new_with_keys(client, keys).await
+    }
+
+    pub async fn new_with_keys(client: TonClient, keys: KeyPair) -> Result<Self, String> {
         let handle = get_signing_box(client.clone(), keys)
             .await
             .map(|r| r.handle)
@@ -72,17 +64,23 @@ impl Drop for TerminalSigningBox {
     }
 }
 
-pub(super) fn input_keys<R, W>(
+/// Asks user for keys: seed phrase, path to keypair file or, if keystore
+/// is defined, name or public key of the keystore entry. If keystore has
+/// one of `possible_keys`, only its passphrase is asked. Seed phrase is
+/// converted to keys according to `mnemonic`.
+pub(super) async fn input_keys(
     prompt: Option<&str>,
     possible_keys: Vec<String>,
-    reader: &mut R,
-    writer: &mut W,
+    io: &(dyn BrowserIo + Send + Sync),
     tries: u8,
-) -> Result<KeyPair, String>
-where
-    R: BufRead,
-    W: Write,
-{
+    keystore: Option<&KeyStore>,
+    mnemonic: &MnemonicConfig,
+) -> Result<KeyPair, String> {
+    if let Some(keystore) = keystore {
+        if let Some(entry) = keystore.find_any(&possible_keys) {
+            return keystore.unlock_interactive(&entry.name, io, tries).await;
+        }
+    }
     let enter_str = prompt.unwrap_or_default();
     let mut pair = Err("no keypair".to_string());
     let mut format_pubkeys = String::new();
@@ -90,22 +88,25 @@ where
         .iter()
         .for_each(|x| format_pubkeys += &format!(" {},", x));
     for _ in 0..tries {
-        let value = input(enter_str, reader, writer);
-        pair = load_keypair(&value).map_err(|e| {
-            println!("Invalid keys: {}. Try again.", e);
-            e.to_string()
-        });
+        let value = io.secret(enter_str).await?;
+        pair = match keystore.filter(|ks| ks.find(&value).is_some()) {
+            Some(keystore) => keystore.unlock_interactive(&value, io, 1).await,
+            None => load_keypair(&value, mnemonic).map_err(|e| e.to_string()),
+        };
+        if let Err(ref e) = pair {
+            io.print(&format!("Invalid keys: {}. Try again.", e)).await;
+        }
         if let Ok(ref keys) = pair {
             if possible_keys.len() != 0 {
                 if let None = possible_keys
                     .iter()
                     .find(|x| x.get(2..).unwrap() == keys.public.as_str())
                 {
-                    println!("Unexpected keys.");
-                    println!(
+                    io.print("Unexpected keys.").await;
+                    io.print(&format!(
                         "Hint: enter keypair which contains one of the following public keys: {}",
                         format_pubkeys
-                    );
+                    )).await;
                 } else {
                     break;
                 }
@@ -120,7 +121,10 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::browser_io::ScriptedIo;
+    use serde_json::json;
     use std::fs::File;
+    use std::io::Write;
 
     const PUBLIC: &'static str = "9711a04f0b19474272bc7bae5472a8fbbb6ef71ce9c193f5ec3f5af808069a41";
     const PRIVATE: &'static str =
@@ -144,24 +148,37 @@ mod tests {
         .unwrap();
     }
 
-    #[test]
-    fn load_key_from_file() {
-        let mut in_data = KEYS_FILE.as_bytes();
-        let mut out_data = vec![];
+    #[tokio::test]
+    async fn load_key_from_file() {
+        let io = ScriptedIo::new(vec![KEYS_FILE]);
 
         create_keypair_file(KEYS_FILE);
-        let keys = input_keys(None, vec![], &mut in_data, &mut out_data, 1).unwrap();
+        let keys = input_keys(None, vec![], &io, 1, None, &MnemonicConfig::default()).await.unwrap();
         assert_eq!(format!("{}", keys.public), PUBLIC);
         assert_eq!(format!("{}", keys.secret), PRIVATE);
     }
 
-    #[test]
-    fn load_key_from_seed() {
-        let mut in_data = SEED.as_bytes();
-        let mut out_data = vec![];
+    #[tokio::test]
+    async fn load_key_from_seed() {
+        let io = ScriptedIo::new(vec![SEED]);
 
-        let keys = input_keys(None, vec![], &mut in_data, &mut out_data, 1).unwrap();
+        let keys = input_keys(None, vec![], &io, 1, None, &MnemonicConfig::default()).await.unwrap();
         assert_eq!(format!("{}", keys.public), PUBLIC);
         assert_eq!(format!("{}", keys.secret), PRIVATE);
     }
+
+    #[tokio::test]
+    async fn keystore_tries_are_shared() {
+        let keystore = KeyStore::from_json(&json!({
+            "version": 1,
+            "entries": [
+                { "name": "main", "public": PUBLIC, "secret": "", "salt": "", "nonce": "" }
+            ]
+        }).to_string()).unwrap();
+        let io = ScriptedIo::new(vec!["main", "wrong", "main", "wrong", "main", "wrong"]);
+        let result = input_keys(None, vec![], &io, 3, Some(&keystore), &MnemonicConfig::default()).await;
+        assert!(result.is_err());
+        let prompts = io.output().iter().filter(|x| x.contains("enter passphrase")).count();
+        assert_eq!(prompts, 3);
+    }
 }
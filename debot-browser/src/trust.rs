@@ -0,0 +1,184 @@
+/*
+* Copyright 2018-2021 TON DEV SOLUTIONS LTD.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific TON DEV software governing permissions and
+* limitations under the License.
+*/
+use crate::helpers::{load_ton_address, normalize_key, TonClient};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use ton_client::abi::{decode_initial_data, ParamsOfDecodeInitialData};
+
+/// Expected code of the trusted DeBot.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TrustedDebot {
+    /// Hash of the DeBot code.
+    pub code_hash: String,
+    /// Public keys of DeBot publishers. If not empty, DeBot public key
+    /// must be one of them.
+    #[serde(default)]
+    pub publisher_keys: Vec<String>,
+}
+
+/// List of trusted DeBots: [addr] -> expected code.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TrustList {
+    #[serde(default)]
+    pub debots: HashMap<String, TrustedDebot>,
+    /// If true, DeBots missing in the list are refused.
+    #[serde(default)]
+    pub strict: bool,
+}
+
+/// Code hash and public key of the deployed DeBot.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct DebotCode {
+    pub code_hash: Option<String>,
+    pub pubkey: Option<String>,
+}
+
+impl DebotCode {
+    /// Reads code hash and public key from account fields `code_hash` and `data`.
+    pub async fn from_account(client: TonClient, account: &serde_json::Value) -> Self {
+        let code_hash = account["code_hash"].as_str().map(|s| s.to_owned());
+        let pubkey = match account["data"].as_str() {
+            Some(data) => decode_initial_data(
+                client,
+                ParamsOfDecodeInitialData { abi: None, data: data.to_owned() },
+            )
+            .await
+            .ok()
+            .map(|decoded| decoded.initial_pubkey),
+            None => None,
+        };
+        Self { code_hash, pubkey }
+    }
+}
+
+impl TrustList {
+    /// Adds entries of `other` list for DeBots missing in this list.
+    /// Existing entries are never replaced.
+    pub fn merge(&mut self, other: &TrustList) {
+        for (addr, debot) in &other.debots {
+            let addr = load_ton_address(addr, 0).unwrap_or(addr.clone());
+            if self.find(&addr).is_none() {
+                self.debots.insert(addr, debot.clone());
+            }
+        }
+        self.strict = self.strict || other.strict;
+    }
+
+    fn find(&self, addr: &str) -> Option<&TrustedDebot> {
+        self.debots.get(addr).or_else(|| {
+            self.debots
+                .iter()
+                .find(|(key, _)| load_ton_address(key, 0).ok().as_deref() == Some(addr))
+                .map(|(_, debot)| debot)
+        })
+    }
+
+    /// Checks that DeBot code matches the list.
+    pub(crate) fn check(&self, addr: &str, code: &DebotCode) -> Result<(), String> {
+        let trusted = match self.find(addr) {
+            Some(trusted) => trusted,
+            None if self.strict => return Err(format!("DeBot {} is not in trust list", addr)),
+            None => return Ok(()),
+        };
+        let code_hash = code
+            .code_hash
+            .as_ref()
+            .ok_or(format!("failed to get code hash of DeBot {}", addr))?;
+        if normalize_key(code_hash) != normalize_key(&trusted.code_hash) {
+            return Err(format!(
+                "code hash of DeBot {} is changed: expected {}, found {}",
+                addr, trusted.code_hash, code_hash
+            ));
+        }
+        if !trusted.publisher_keys.is_empty() {
+            let pubkey = code.pubkey.as_deref().map(normalize_key).unwrap_or_default();
+            let known = trusted
+                .publisher_keys
+                .iter()
+                .any(|key| normalize_key(key) == pubkey);
+            if !known {
+                return Err(format!("DeBot {} is not signed by trusted publisher", addr));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const ADDR: &str = "0:1111111111111111111111111111111111111111111111111111111111111111";
+    const HASH: &str = "a0a1a2a3a4a5a6a7a8a9aaabacadaeafb0b1b2b3b4b5b6b7b8b9babbbcbdbebf";
+    const KEY: &str = "9f7fd3df9d72b133fe155c087928c4f9da423076cc20c9f5386614b462e49811";
+
+    fn code(code_hash: &str, pubkey: &str) -> DebotCode {
+        DebotCode { code_hash: Some(code_hash.to_owned()), pubkey: Some(pubkey.to_owned()) }
+    }
+
+    #[test]
+    fn test_check() {
+        let list: TrustList = serde_json::from_value(json!({
+            "debots": {
+                ADDR: { "codeHash": HASH, "publisherKeys": [format!("0x{}", KEY)] }
+            }
+        }))
+        .unwrap();
+        assert!(list.check(ADDR, &code(HASH, KEY)).is_ok());
+        assert!(list.check(ADDR, &code(&HASH.to_uppercase(), KEY)).is_ok());
+        let err = list.check(ADDR, &code(&HASH.replace("a0", "00"), KEY)).unwrap_err();
+        assert!(err.contains("code hash"));
+        assert!(list.check(ADDR, &code(HASH, &KEY.replace("9f", "00"))).is_err());
+        assert!(list.check(ADDR, &DebotCode::default()).is_err());
+
+        assert!(list.check("0:22", &DebotCode::default()).is_ok());
+        let strict = TrustList { strict: true, ..list };
+        assert!(strict.check("0:22", &DebotCode::default()).is_err());
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut list = TrustList::default();
+        let mut other = TrustList::default();
+        other.debots.insert(
+            ADDR.trim_start_matches("0:").to_owned(),
+            TrustedDebot { code_hash: HASH.to_owned(), publisher_keys: vec![] },
+        );
+        list.merge(&other);
+        assert!(!list.strict);
+        assert!(list.check(ADDR, &code("00", KEY)).is_err());
+        assert!(list.check(ADDR, &code(HASH, KEY)).is_ok());
+    }
+
+    #[test]
+    fn test_merge_keeps_base_pins() {
+        let mut list = TrustList::default();
+        list.debots.insert(
+            ADDR.to_owned(),
+            TrustedDebot { code_hash: HASH.to_owned(), publisher_keys: vec![KEY.to_owned()] },
+        );
+        let mut manifest = TrustList::default();
+        manifest.debots.insert(
+            ADDR.trim_start_matches("0:").to_owned(),
+            TrustedDebot { code_hash: "00".to_owned(), publisher_keys: vec![] },
+        );
+        list.merge(&manifest);
+        assert_eq!(list.debots.len(), 1);
+        assert!(list.check(ADDR, &code(HASH, KEY)).is_ok());
+        assert!(list.check(ADDR, &code("00", KEY)).is_err());
+        assert!(list.check(ADDR, &code(HASH, "00")).is_err());
+    }
+}